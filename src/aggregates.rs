@@ -0,0 +1,89 @@
+use log::info;
+use rusqlite::{params, Connection, OptionalExtension, NO_PARAMS};
+
+use crate::stats::weighted_score;
+use crate::{GameId, LOCAL_PLAYER_JOIN};
+
+/// Denormalized per-leader & per-map records of the local player, kept up to
+/// date game by game during the merge so reports don't need to scan the archive.
+const TABLES: &str = "CREATE TABLE MergeLeaderSummary (LeaderType TEXT PRIMARY KEY NOT NULL, Games INTEGER NOT NULL, \
+    Wins INTEGER NOT NULL, TotalScore INTEGER NOT NULL, BestScore INTEGER, FastestWin INTEGER, \
+    TotalWeightedScore REAL NOT NULL, BestWeightedScore REAL);
+CREATE TABLE MergeMapSummary (Map TEXT NOT NULL, MapSizeType TEXT NOT NULL, Games INTEGER NOT NULL, Wins INTEGER NOT NULL, \
+    PRIMARY KEY (Map, MapSizeType));";
+
+/// Creates the summary tables if they don't exist yet & fills them with all games already in the target.
+pub fn ensure_tables(con: &Connection) -> rusqlite::Result<()> {
+    let columns = crate::schema::columns(con, "MergeLeaderSummary")?;
+    if columns.iter().any(|column| column == "TotalWeightedScore") {
+        return Ok(());
+    }
+    if !columns.is_empty() {
+        // Created by an older version without the weighted scores
+        con.execute_batch("DROP TABLE MergeLeaderSummary; DROP TABLE MergeMapSummary;")?;
+    }
+
+    con.execute_batch(TABLES)?;
+    let mut stmt = con.prepare("SELECT GameId FROM Games")?;
+    let game_ids = stmt
+        .query_map(NO_PARAMS, |row| row.get(0))?
+        .collect::<rusqlite::Result<Vec<GameId>>>()?;
+    for game_id in &game_ids {
+        record_game(con, *game_id)?;
+    }
+    info!("Created summary tables from {} games", game_ids.len());
+    Ok(())
+}
+
+/// Recomputes existing summary tables after games were removed.
+pub fn rebuild(con: &Connection) -> rusqlite::Result<()> {
+    if crate::schema::columns(con, "MergeLeaderSummary")?.is_empty() {
+        return Ok(());
+    }
+    con.execute_batch("DROP TABLE MergeLeaderSummary; DROP TABLE MergeMapSummary;")?;
+    ensure_tables(con)
+}
+
+/// Adds a game of the target to the summary tables.
+pub fn record_game(con: &Connection, game_id: GameId) -> rusqlite::Result<()> {
+    let player: Option<(Option<i64>, String, Option<String>)> = con
+        .query_row(
+            &format!(
+                "SELECT lp.Score, g.GameSpeedType, lp.DifficultyType FROM Games g {} WHERE g.GameId = ?",
+                LOCAL_PLAYER_JOIN
+            ),
+            params![game_id],
+            |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
+        )
+        .optional()?;
+    let weighted = match player {
+        Some((Some(score), speed, difficulty)) => weighted_score(score, &speed, difficulty.as_deref()),
+        _ => 0.0,
+    };
+    con.execute(
+        &format!(
+            "INSERT INTO MergeLeaderSummary (LeaderType, Games, Wins, TotalScore, BestScore, FastestWin, \
+             TotalWeightedScore, BestWeightedScore) \
+             SELECT lp.LeaderType, 1, COALESCE(g.VictorTeamId = lp.TeamId, 0), lp.Score, lp.Score, \
+             CASE WHEN g.VictorTeamId = lp.TeamId THEN g.TurnCount END, ?2, ?2 \
+             FROM Games g {} WHERE g.GameId = ?1 AND lp.LeaderType IS NOT NULL \
+             ON CONFLICT (LeaderType) DO UPDATE SET Games = Games + 1, Wins = Wins + excluded.Wins, \
+             TotalScore = TotalScore + excluded.TotalScore, BestScore = MAX(BestScore, excluded.BestScore), \
+             FastestWin = COALESCE(MIN(FastestWin, excluded.FastestWin), FastestWin, excluded.FastestWin), \
+             TotalWeightedScore = TotalWeightedScore + excluded.TotalWeightedScore, \
+             BestWeightedScore = MAX(BestWeightedScore, excluded.BestWeightedScore)",
+            LOCAL_PLAYER_JOIN
+        ),
+        params![game_id, weighted],
+    )?;
+    con.execute(
+        &format!(
+            "INSERT INTO MergeMapSummary (Map, MapSizeType, Games, Wins) \
+             SELECT g.Map, g.MapSizeType, 1, COALESCE(g.VictorTeamId = lp.TeamId, 0) FROM Games g {} WHERE g.GameId = ?1 \
+             ON CONFLICT (Map, MapSizeType) DO UPDATE SET Games = Games + 1, Wins = Wins + excluded.Wins",
+            LOCAL_PLAYER_JOIN
+        ),
+        params![game_id],
+    )?;
+    Ok(())
+}
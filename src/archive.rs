@@ -0,0 +1,97 @@
+//! Append-only archive of merge results: Each merge writes a new generation
+//! `HallofFame-<timestamp>.sqlite` & the `latest` file names the newest one.
+
+use std::path::{Path, PathBuf};
+
+use log::{debug, info, warn};
+
+const LATEST: &str = "latest";
+
+/// The newest generation in the archive, if any.
+pub fn latest(archive: &Path) -> std::io::Result<Option<PathBuf>> {
+    match std::fs::read_to_string(archive.join(LATEST)) {
+        Ok(name) => Ok(Some(archive.join(name.trim()))),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+        Err(e) => Err(e),
+    }
+}
+
+/// Path of a new generation - never one that exists already. Creates nothing,
+/// so a dry run leaves the archive be.
+pub fn next_generation(archive: &Path) -> PathBuf {
+    let now = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_secs() as i64)
+        .unwrap_or_default();
+    let stamp = crate::dates::format_file_stamp(now);
+
+    let mut path = archive.join(format!("HallofFame-{}.sqlite", stamp));
+    let mut n = 1;
+    while path.exists() {
+        n += 1;
+        path = archive.join(format!("HallofFame-{}-{}.sqlite", stamp, n));
+    }
+    path
+}
+
+/// A new generation being merged: Unless committed, it is removed again when
+/// dropped - with the archive directory, if that was created for it - so a
+/// failed merge leaves no generation behind that `latest` doesn't name.
+pub struct Generation {
+    archive: PathBuf,
+    path: PathBuf,
+    created_archive: bool,
+    committed: bool,
+}
+
+impl Generation {
+    /// Creates the archive directory if needed & picks the path of the new generation.
+    pub fn start(archive: &Path) -> std::io::Result<Generation> {
+        let created_archive = !archive.is_dir();
+        std::fs::create_dir_all(archive)?;
+        Ok(Generation {
+            archive: archive.to_path_buf(),
+            path: next_generation(archive),
+            created_archive,
+            committed: false,
+        })
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Keeps the generation - call it once the merge into it committed - & points `latest` at it.
+    pub fn commit(mut self) -> std::io::Result<()> {
+        set_latest(&self.archive, &self.path)?;
+        self.committed = true;
+        Ok(())
+    }
+}
+
+impl Drop for Generation {
+    fn drop(&mut self) {
+        if self.committed {
+            return;
+        }
+        match std::fs::remove_file(&self.path) {
+            Ok(()) => debug!("Removed the unfinished generation {:?}", &self.path),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+            Err(e) => warn!("Couldn't remove the unfinished generation {:?}: {}", &self.path, e),
+        }
+        if self.created_archive {
+            // Only if still empty
+            let _ = std::fs::remove_dir(&self.archive);
+        }
+    }
+}
+
+/// Points `latest` at a finished generation.
+fn set_latest(archive: &Path, generation: &Path) -> std::io::Result<()> {
+    let name = generation
+        .file_name()
+        .ok_or_else(|| std::io::Error::other(format!("{:?} has no file name", generation)))?;
+    std::fs::write(archive.join(LATEST), format!("{}\n", name.to_string_lossy()))?;
+    info!("Archived generation {:?}", &generation);
+    Ok(())
+}
@@ -0,0 +1,220 @@
+use std::collections::HashSet;
+use std::hash::{Hash, Hasher};
+use std::io::{BufRead, Write};
+use std::path::{Path, PathBuf};
+
+use log::{debug, info};
+use rusqlite::{Connection, NO_PARAMS};
+use serde_rusqlite::from_rows;
+
+use crate::hash::Fnv64;
+use crate::Game;
+
+/// Identity hashes of the games known to be in a target, persisted under a
+/// path that stays the same from merge to merge - `<target>.gamecache`, or
+/// `gamecache` in the archive directory - so repeated merges can skip known
+/// games without querying the target for each of them.
+///
+/// The file holds the hashes of the target as the merge left it & as it was
+/// copied from the base, each under the fingerprint of those games: The next
+/// merge either continues from that result - in place or in an archive - or
+/// copies the same base again.
+pub struct GameCache {
+    path: PathBuf,
+    /// Fingerprint & hashes of the target as loaded, before the merge
+    base: (String, HashSet<u64>),
+    hashes: HashSet<u64>,
+}
+
+/// Hashes the columns the exact duplicate check of `dedupe::duplicate_of` compares.
+pub fn game_hash(game: &Game) -> u64 {
+    let mut hasher = Fnv64::default();
+    game.rule_set.hash(&mut hasher);
+    game.game_mode.hash(&mut hasher);
+    game.turn_count.hash(&mut hasher);
+    game.game_speed_type.hash(&mut hasher);
+    game.map_size_type.hash(&mut hasher);
+    crate::maps::canonical(&game.map).hash(&mut hasher);
+    game.start_era_type.hash(&mut hasher);
+    game.start_turn.hash(&mut hasher);
+    game.victor_team_id.hash(&mut hasher);
+    game.victory_type.hash(&mut hasher);
+    game.last_played.hash(&mut hasher);
+    hasher.finish()
+}
+
+/// Hashes of all games in a database.
+pub fn game_hashes(con: &Connection) -> Result<HashSet<u64>, Box<dyn std::error::Error>> {
+    let mut stmt = con.prepare("SELECT * FROM Games")?;
+    let mut hashes = HashSet::new();
+    for game in from_rows::<Game>(stmt.query(NO_PARAMS)?) {
+        hashes.insert(game_hash(&game?));
+    }
+    Ok(hashes)
+}
+
+/// Hash of the ids & last played times of the target's games - hashes saved
+/// under it are only trusted if no game was added, removed or continued
+/// since. Unlike the file's size & modification time it survives the target
+/// being rewritten with the same games, as the merge's copy of the base does.
+fn fingerprint(target_connection: &Connection) -> rusqlite::Result<String> {
+    let mut stmt = target_connection.prepare("SELECT GameId, LastPlayed FROM Games ORDER BY GameId")?;
+    let mut rows = stmt.query(NO_PARAMS)?;
+    let mut hasher = Fnv64::default();
+    while let Some(row) = rows.next()? {
+        row.get::<_, i64>(0)?.hash(&mut hasher);
+        row.get::<_, i64>(1)?.hash(&mut hasher);
+    }
+    Ok(format!("{:016x}", hasher.finish()))
+}
+
+/// The hashes saved under `fingerprint` in a cache file, if any.
+fn read_section(file: std::fs::File, fingerprint: &str) -> Result<Option<HashSet<u64>>, Box<dyn std::error::Error>> {
+    let mut section = None;
+    for line in std::io::BufReader::new(file).lines() {
+        let line = line?;
+        match (line.strip_prefix('@'), section.as_mut()) {
+            (Some(_), Some(_)) => break,
+            (Some(saved), None) if saved == fingerprint => section = Some(HashSet::new()),
+            (None, Some(hashes)) => {
+                hashes.insert(u64::from_str_radix(&line, 16)?);
+            }
+            _ => {}
+        }
+    }
+    Ok(section)
+}
+
+impl GameCache {
+    /// `<target>.gamecache`, the cache of a target merged into by path.
+    pub fn path_of(target_path: &Path) -> PathBuf {
+        let mut path = target_path.as_os_str().to_owned();
+        path.push(".gamecache");
+        PathBuf::from(path)
+    }
+
+    /// Loads the hashes `path` holds for the target's games or - if missing
+    /// or stale - rebuilds them with a single scan over the target's games.
+    pub fn load(path: PathBuf, target_connection: &Connection) -> Result<GameCache, Box<dyn std::error::Error>> {
+        let fingerprint = fingerprint(target_connection)?;
+        let saved = match std::fs::File::open(&path) {
+            Ok(file) => read_section(file, &fingerprint)?,
+            Err(_) => None,
+        };
+        let hashes = match saved {
+            Some(hashes) => {
+                info!("Loaded {} game hashes from {:?}", hashes.len(), &path);
+                hashes
+            }
+            None => {
+                if path.exists() {
+                    debug!("{:?} is stale", &path);
+                }
+                let hashes = game_hashes(target_connection)?;
+                info!("Rebuilt {:?} with {} game hashes", &path, hashes.len());
+                hashes
+            }
+        };
+        Ok(GameCache {
+            path,
+            base: (fingerprint, hashes.clone()),
+            hashes,
+        })
+    }
+
+    pub fn contains(&self, game: &Game) -> bool {
+        self.hashes.contains(&game_hash(game))
+    }
+
+    pub fn insert(&mut self, game: &Game) {
+        self.hashes.insert(game_hash(game));
+    }
+
+    /// Writes the cache - call it after the last change to the target's games.
+    pub fn save(&self, target_connection: &Connection) -> Result<(), Box<dyn std::error::Error>> {
+        let mut file = std::io::BufWriter::new(std::fs::File::create(&self.path)?);
+        let fingerprint = fingerprint(target_connection)?;
+        let mut sections = vec![(&fingerprint, &self.hashes)];
+        if self.base.0 != fingerprint {
+            sections.push((&self.base.0, &self.base.1));
+        }
+        for (fingerprint, hashes) in sections {
+            writeln!(file, "@{}", fingerprint)?;
+            for hash in hashes {
+                writeln!(file, "{:016x}", hash)?;
+            }
+        }
+        info!("Saved {} game hashes to {:?}", self.hashes.len(), &self.path);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rusqlite::params;
+
+    use super::*;
+
+    fn database() -> Connection {
+        let con = Connection::open_in_memory().unwrap();
+        crate::schema::create(&con).unwrap();
+        con
+    }
+
+    fn add_game(con: &Connection, game_id: i64, map: &str) {
+        con.execute(
+            "INSERT INTO Games (GameId, Ruleset, GameMode, TurnCount, GameSpeedType, MapSizeType, Map, StartEraType, \
+             StartTurn, LastPlayed) VALUES (?, 'RULESET_STANDARD', 0, 100, 'GAMESPEED_STANDARD', 'MAPSIZE_SMALL', ?, \
+             'ERA_ANCIENT', 1, 0)",
+            params![game_id, map],
+        )
+        .unwrap();
+    }
+
+    fn game(con: &Connection, game_id: i64) -> Game {
+        crate::load_game(con, game_id).unwrap()
+    }
+
+    fn cache_path(name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!("civ6-hof-merge-test-{}-{}.gamecache", std::process::id(), name))
+    }
+
+    // Renaming a map keeps the fingerprint, so a cache loaded from the file
+    // still knows the game by its old map - a rebuilt one by its new map.
+    #[test]
+    fn loads_the_cache_of_the_target_the_last_merge_left() {
+        let path = cache_path("result");
+        let con = database();
+        add_game(&con, 1, "Continents.lua");
+        let before = game(&con, 1);
+        GameCache::load(path.clone(), &con).unwrap().save(&con).unwrap();
+        con.execute("UPDATE Games SET Map = 'Pangaea.lua'", NO_PARAMS).unwrap();
+
+        let cache = GameCache::load(path.clone(), &con).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert!(cache.contains(&before));
+        assert!(!cache.contains(&game(&con, 1)));
+    }
+
+    #[test]
+    fn loads_the_cache_of_the_base_copied_again() {
+        let path = cache_path("base");
+        let con = database();
+        add_game(&con, 1, "Continents.lua");
+        let base = game(&con, 1);
+        let mut cache = GameCache::load(path.clone(), &con).unwrap();
+        add_game(&con, 2, "Pangaea.lua");
+        let merged = game(&con, 2);
+        cache.insert(&merged);
+        cache.save(&con).unwrap();
+
+        // The next merge copies the same base again
+        let copy = database();
+        add_game(&copy, 1, "Fractal.lua");
+        let cache = GameCache::load(path.clone(), &copy).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert!(cache.contains(&base));
+        assert!(!cache.contains(&merged));
+        assert!(!cache.contains(&game(&copy, 1)));
+    }
+}
@@ -0,0 +1,108 @@
+//! `career`: All games the local player played with one leader across the
+//! merged machines - what the game's Hall-of-Fame screen shows per leader for
+//! a single PC only.
+
+use std::collections::BTreeMap;
+
+use rusqlite::{Connection, NO_PARAMS};
+
+use crate::names::Names;
+use crate::table::Table;
+use crate::{filters, stats, GameId, LOCAL_PLAYER_JOIN};
+
+/// A game of the leader.
+struct Played {
+    game_id: GameId,
+    won: bool,
+    score: Option<i64>,
+    difficulty: Option<String>,
+    turns: i64,
+}
+
+/// A leader's career: The summary & the games per difficulty.
+pub struct Career {
+    pub summary: Table,
+    pub difficulties: Table,
+}
+
+/// Whether `leader` - a LeaderType with or without `LEADER_`, or the leader's name in any case - means
+/// this leader.
+fn is_leader(names: &Names, leader: &str, leader_type: &str, leader_name: Option<&str>) -> bool {
+    filters::key("LEADER_", leader) == leader_type || names.leader(leader_type, leader_name).eq_ignore_ascii_case(leader)
+}
+
+fn win_rate(wins: usize, games: usize) -> String {
+    format!("{:.0}%", 100.0 * wins as f64 / games as f64)
+}
+
+/// The career of the leader, failing if the local player never played it.
+pub fn career(con: &Connection, names: &Names, leader: &str) -> Result<Career, Box<dyn std::error::Error>> {
+    let mut stmt = con.prepare(&format!(
+        "SELECT g.GameId, lp.LeaderType, lp.LeaderName, COALESCE(g.VictorTeamId = lp.TeamId, 0), lp.Score, \
+         lp.DifficultyType, g.TurnCount FROM Games g {} WHERE lp.LeaderType IS NOT NULL ORDER BY g.LastPlayed",
+        LOCAL_PLAYER_JOIN
+    ))?;
+    let mut display = None;
+    let mut games = Vec::new();
+    let mut rows = stmt.query(NO_PARAMS)?;
+    while let Some(row) = rows.next()? {
+        let leader_type: String = row.get(1)?;
+        let leader_name: Option<String> = row.get(2)?;
+        if !is_leader(names, leader, &leader_type, leader_name.as_deref()) {
+            continue;
+        }
+        display.get_or_insert_with(|| names.leader(&leader_type, leader_name.as_deref()));
+        games.push(Played {
+            game_id: row.get(0)?,
+            won: row.get(3)?,
+            score: row.get(4)?,
+            difficulty: row.get(5)?,
+            turns: row.get(6)?,
+        });
+    }
+    let display = display.ok_or_else(|| format!("The local player never played {:?}", leader))?;
+
+    let wins = games.iter().filter(|game| game.won).count();
+    let scores: Vec<i64> = games.iter().filter_map(|game| game.score).collect();
+    let best = games.iter().filter(|game| game.score.is_some()).max_by_key(|game| game.score);
+    let fastest = games.iter().filter(|game| game.won).min_by_key(|game| game.turns);
+    let mut summary = Table::new(&["Leader", "Games", "Wins", "Win rate", "Avg score", "Best score", "Fastest win"]);
+    summary.push(vec![
+        display,
+        games.len().to_string(),
+        wins.to_string(),
+        win_rate(wins, games.len()),
+        if scores.is_empty() {
+            "-".to_string()
+        } else {
+            format!("{:.0}", scores.iter().sum::<i64>() as f64 / scores.len() as f64)
+        },
+        best.map_or_else(
+            || "-".to_string(),
+            |game| format!("{} (game {})", game.score.unwrap_or_default(), game.game_id),
+        ),
+        fastest.map_or_else(
+            || "-".to_string(),
+            |game| format!("{} turns (game {})", game.turns, game.game_id),
+        ),
+    ]);
+
+    let mut spread: BTreeMap<usize, (String, usize, usize)> = BTreeMap::new();
+    for game in &games {
+        let difficulty = game.difficulty.as_deref().unwrap_or("NULL");
+        let position = stats::DIFFICULTIES
+            .iter()
+            .position(|known| *known == difficulty)
+            .unwrap_or(stats::DIFFICULTIES.len());
+        let counts = spread
+            .entry(position)
+            .or_insert_with(|| (names.display(difficulty).to_string(), 0, 0));
+        counts.1 += 1;
+        counts.2 += game.won as usize;
+    }
+    let mut difficulties = Table::new(&["Difficulty", "Games", "Wins", "Win rate"]);
+    for (difficulty, games, wins) in spread.into_values() {
+        difficulties.push(vec![difficulty, games.to_string(), wins.to_string(), win_rate(wins, games)]);
+    }
+    Ok(Career { summary, difficulties })
+}
@@ -0,0 +1,109 @@
+//! SQLCipher-encrypted databases, with the `sqlcipher` cargo feature: Given a
+//! passphrase, encrypted databases are unlocked on opening & merge targets
+//! are encrypted after the merge. Plain databases - like the game's own Hall
+//! of Fame - keep opening as before.
+
+use std::path::Path;
+use std::sync::OnceLock;
+use std::time::Duration;
+
+use log::info;
+use rusqlite::backup::Backup;
+use rusqlite::{params, Connection, NO_PARAMS};
+
+static PASSPHRASE: OnceLock<String> = OnceLock::new();
+
+/// Pages copied per step of a backup, & the pause before retrying a step the
+/// game's lock on the database blocked.
+const BACKUP_PAGES: i32 = 256;
+const BACKUP_PAUSE: Duration = Duration::from_millis(250);
+
+/// Sets the passphrase of encrypted databases for the rest of the run.
+pub fn set_passphrase(passphrase: Option<String>) -> Result<(), String> {
+    let passphrase = match passphrase {
+        Some(passphrase) => passphrase,
+        None => return Ok(()),
+    };
+    if !cfg!(feature = "sqlcipher") {
+        return Err("A passphrase needs civ6-hof-merge built with `--features sqlcipher`".to_string());
+    }
+    let _ = PASSPHRASE.set(passphrase);
+    Ok(())
+}
+
+fn is_readable(con: &Connection) -> bool {
+    con.query_row("SELECT COUNT(*) FROM sqlite_master", NO_PARAMS, |row| row.get::<_, i64>(0))
+        .is_ok()
+}
+
+/// Opens a database, unlocking it with the passphrase if it isn't plain.
+pub fn open(path: &Path) -> rusqlite::Result<Connection> {
+    let con = Connection::open(path)?;
+    match PASSPHRASE.get() {
+        Some(passphrase) if !is_readable(&con) => {
+            drop(con);
+            let con = Connection::open(path)?;
+            con.pragma_update(None, "key", passphrase)?;
+            Ok(con)
+        }
+        _ => Ok(con),
+    }
+}
+
+/// Attaches a database as `schema`, unlocking it with the passphrase if it isn't plain.
+pub fn attach(con: &Connection, path: &Path, schema: &str) -> rusqlite::Result<()> {
+    let key = match PASSPHRASE.get() {
+        Some(passphrase) if !is_readable(&Connection::open(path)?) => passphrase.as_str(),
+        _ => "",
+    };
+    con.execute(
+        &format!("ATTACH DATABASE ? AS {} KEY ?", schema),
+        params![path.to_string_lossy(), key],
+    )?;
+    Ok(())
+}
+
+/// Copies a database with SQLite's online backup - consistent even while the
+/// game has it open, & including what is still in its write-ahead log. The
+/// copy replaces whatever `target` held & is encrypted like the original.
+pub fn backup(path: &Path, target: &Path) -> rusqlite::Result<()> {
+    let source = open(path)?;
+    let mut copy = Connection::open(target)?;
+    if let Some(passphrase) = PASSPHRASE.get() {
+        if !is_readable(&Connection::open(path)?) {
+            copy.pragma_update(None, "key", passphrase)?;
+        }
+    }
+    let backup = Backup::new(&source, &mut copy)?;
+    backup.run_to_completion(BACKUP_PAGES, BACKUP_PAUSE, None)
+}
+
+/// Encrypts a plain database in place with the passphrase, if one is set.
+/// Returns whether the database was encrypted.
+pub fn encrypt(path: &Path) -> Result<bool, Box<dyn std::error::Error>> {
+    let passphrase = match PASSPHRASE.get() {
+        Some(passphrase) => passphrase,
+        None => return Ok(false),
+    };
+    let con = Connection::open(path)?;
+    if !is_readable(&con) {
+        // Already encrypted
+        return Ok(false);
+    }
+    let mut encrypted = path.as_os_str().to_owned();
+    encrypted.push(".encrypting");
+    let encrypted = std::path::PathBuf::from(encrypted);
+    if encrypted.exists() {
+        std::fs::remove_file(&encrypted)?;
+    }
+    con.execute(
+        "ATTACH DATABASE ? AS encrypted KEY ?",
+        params![encrypted.to_string_lossy(), passphrase],
+    )?;
+    con.query_row("SELECT sqlcipher_export('encrypted')", NO_PARAMS, |_| Ok(()))?;
+    con.execute("DETACH DATABASE encrypted", NO_PARAMS)?;
+    drop(con);
+    std::fs::rename(&encrypted, path)?;
+    info!("Encrypted {:?}", &path);
+    Ok(true)
+}
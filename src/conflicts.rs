@@ -0,0 +1,137 @@
+use std::path::Path;
+
+use log::info;
+use rusqlite::{params, Connection};
+use serde_json::{json, Value};
+
+use crate::{Game, GameId};
+
+/// Which version is kept of a game both the source & the target have, when
+/// they aren't the same: `--on-conflict`.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum Keep {
+    /// The target's version, skipping the source's
+    #[default]
+    Target,
+    /// The source's version, replacing the target's
+    Source,
+    /// The version played last - the longer one if both were played at the same time
+    Latest,
+    /// Both versions, as separate games
+    Both,
+}
+
+impl std::str::FromStr for Keep {
+    type Err = String;
+
+    fn from_str(policy: &str) -> Result<Self, Self::Err> {
+        match policy {
+            "keep-target" => Ok(Keep::Target),
+            "keep-source" => Ok(Keep::Source),
+            "keep-latest" => Ok(Keep::Latest),
+            "keep-both" => Ok(Keep::Both),
+            _ => Err(format!(
+                "Unknown conflict policy {:?}, expected keep-target, keep-source, keep-latest or keep-both",
+                policy
+            )),
+        }
+    }
+}
+
+/// What a merge does with a source game the target has a version of.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Resolution {
+    /// Skips the source's game
+    Skip,
+    /// Deletes the target's game & copies the source's
+    Replace,
+    /// Copies the source's game next to the target's
+    Copy,
+}
+
+impl Keep {
+    pub fn resolve(self, source: &Game, target: &Game) -> Resolution {
+        match self {
+            Keep::Target => Resolution::Skip,
+            Keep::Source => Resolution::Replace,
+            Keep::Latest if (source.last_played, source.turn_count) > (target.last_played, target.turn_count) => {
+                Resolution::Replace
+            }
+            Keep::Latest => Resolution::Skip,
+            Keep::Both => Resolution::Copy,
+        }
+    }
+}
+
+/// The versions of games a merge discarded in favor of another, kept for
+/// manual review - as `export --format json` writes them, so `import` can
+/// bring one back.
+#[derive(Default)]
+pub struct Journal {
+    games: Vec<Value>,
+    rulesets: Vec<Value>,
+}
+
+impl Journal {
+    /// Records all rows of the discarded `game_id` in `database` & why it lost.
+    pub fn record(&mut self, con: &Connection, database: &Path, game_id: GameId, reason: &str) -> Result<(), Box<dyn std::error::Error>> {
+        info!("Journaling game {} of {:?}: {}", game_id, &database, reason);
+        let mut document = crate::export::json(con, &[game_id])?;
+        let mut game = document["games"][0].take();
+        game["Journal"] = json!({
+            "database": database.to_string_lossy(),
+            "gameId": game_id,
+            "reason": reason,
+        });
+        self.games.push(game);
+        if let Value::Array(rows) = document["RulesetDataPointValues"].take() {
+            self.rulesets.extend(rows);
+        }
+        Ok(())
+    }
+
+    pub fn len(&self) -> usize {
+        self.games.len()
+    }
+
+    /// Forgets the entries after the first `len`, recorded for changes rolled back since.
+    pub fn truncate(&mut self, len: usize) {
+        self.games.truncate(len);
+    }
+
+    /// Appends the games to the export in `path`, creating it if missing.
+    pub fn save(&self, path: &Path) -> Result<(), Box<dyn std::error::Error>> {
+        let mut document = match std::fs::read_to_string(path) {
+            Ok(content) => serde_json::from_str(&content)?,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => json!({ "games": [], "RulesetDataPointValues": [] }),
+            Err(e) => return Err(e.into()),
+        };
+        let (mut games, mut rulesets) = match (document["games"].take(), document["RulesetDataPointValues"].take()) {
+            (Value::Array(games), Value::Array(rulesets)) => (games, rulesets),
+            _ => return Err(format!("{:?} is no conflicts journal - pass another file", path).into()),
+        };
+        games.extend(self.games.iter().cloned());
+        for row in &self.rulesets {
+            if !rulesets.contains(row) {
+                rulesets.push(row.clone());
+            }
+        }
+        document["games"] = Value::Array(games);
+        document["RulesetDataPointValues"] = Value::Array(rulesets);
+        std::fs::write(path, serde_json::to_string_pretty(&document)?)?;
+        info!("Wrote {} conflict(s) to {:?}", self.games.len(), &path);
+        Ok(())
+    }
+}
+
+/// Leaders, player ids & scores of a game - copies of one game that differ
+/// in these diverged, e.g. one was continued after the other was saved.
+pub fn players(con: &Connection, game_id: GameId) -> rusqlite::Result<Vec<(String, i64, i64)>> {
+    let mut stmt = con.prepare(
+        "SELECT DISTINCT gp.LeaderType, gp.PlayerId, gp.Score FROM GamePlayers gp \
+         JOIN GameObjects go ON go.PlayerObjectId = gp.PlayerObjectId \
+         WHERE go.GameId = ? ORDER BY gp.PlayerId, gp.LeaderType, gp.Score",
+    )?;
+    let players = stmt.query_map(params![game_id], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)))?;
+    players.collect()
+}
@@ -0,0 +1,159 @@
+//! Infers the downloadable content active in a game from its ruleset & the
+//! leaders taking part - installs owning different DLC produce games that
+//! aren't directly comparable.
+
+use std::collections::{BTreeMap, BTreeSet};
+
+use rusqlite::{Connection, NO_PARAMS};
+
+use crate::GameId;
+
+/// Leaders of the base game.
+const BASE_LEADERS: &[&str] = &[
+    "LEADER_BARBAROSSA",
+    "LEADER_CATHERINE_DE_MEDICI",
+    "LEADER_CLEOPATRA",
+    "LEADER_GANDHI",
+    "LEADER_GILGAMESH",
+    "LEADER_GORGO",
+    "LEADER_HOJO",
+    "LEADER_MVEMBA",
+    "LEADER_PEDRO",
+    "LEADER_PERICLES",
+    "LEADER_PETER_GREAT",
+    "LEADER_PHILIP_II",
+    "LEADER_QIN",
+    "LEADER_SALADIN",
+    "LEADER_T_ROOSEVELT",
+    "LEADER_TOMYRIS",
+    "LEADER_TRAJAN",
+    "LEADER_VICTORIA",
+];
+
+/// Expansions by the ruleset they add.
+const RULESETS: &[(&str, &str)] = &[
+    ("RULESET_EXPANSION_1", "Rise and Fall"),
+    ("RULESET_EXPANSION_2", "Gathering Storm"),
+];
+
+/// Leaders not in the base game, by the pack adding them.
+const LEADER_PACKS: &[(&str, &str)] = &[
+    ("LEADER_MONTEZUMA", "Aztec"),
+    ("LEADER_JADWIGA", "Poland"),
+    ("LEADER_HARDRADA", "Vikings Scenario"),
+    ("LEADER_JOHN_CURTIN", "Australia"),
+    ("LEADER_CYRUS", "Persia and Macedon"),
+    ("LEADER_ALEXANDER", "Persia and Macedon"),
+    ("LEADER_AMANITORE", "Nubia"),
+    ("LEADER_JAYAVARMAN", "Khmer and Indonesia"),
+    ("LEADER_GITARJA", "Khmer and Indonesia"),
+    ("LEADER_CHANDRAGUPTA", "Rise and Fall"),
+    ("LEADER_GENGHIS_KHAN", "Rise and Fall"),
+    ("LEADER_LAUTARO", "Rise and Fall"),
+    ("LEADER_POUNDMAKER", "Rise and Fall"),
+    ("LEADER_ROBERT_THE_BRUCE", "Rise and Fall"),
+    ("LEADER_SEONDEOK", "Rise and Fall"),
+    ("LEADER_SHAKA", "Rise and Fall"),
+    ("LEADER_TAMAR", "Rise and Fall"),
+    ("LEADER_WILHELMINA", "Rise and Fall"),
+    ("LEADER_DIDO", "Gathering Storm"),
+    ("LEADER_ELEANOR_ENGLAND", "Gathering Storm"),
+    ("LEADER_ELEANOR_FRANCE", "Gathering Storm"),
+    ("LEADER_KRISTINA", "Gathering Storm"),
+    ("LEADER_KUPE", "Gathering Storm"),
+    ("LEADER_LAURIER", "Gathering Storm"),
+    ("LEADER_MANSA_MUSA", "Gathering Storm"),
+    ("LEADER_MATTHIAS_CORVINUS", "Gathering Storm"),
+    ("LEADER_PACHACUTI", "Gathering Storm"),
+    ("LEADER_SULEIMAN", "Gathering Storm"),
+    ("LEADER_LADY_SIX_SKY", "New Frontier Pass"),
+    ("LEADER_SIMON_BOLIVAR", "New Frontier Pass"),
+    ("LEADER_MENELIK", "New Frontier Pass"),
+    ("LEADER_BASIL", "New Frontier Pass"),
+    ("LEADER_AMBIORIX", "New Frontier Pass"),
+    ("LEADER_HAMMURABI", "New Frontier Pass"),
+    ("LEADER_JOAO_III", "New Frontier Pass"),
+    ("LEADER_BA_TRIEU", "New Frontier Pass"),
+    ("LEADER_KUBLAI_KHAN_CHINA", "New Frontier Pass"),
+    ("LEADER_KUBLAI_KHAN_MONGOLIA", "New Frontier Pass"),
+];
+
+/// Content configuration of each game, e.g. `Gathering Storm + Aztec, Poland`.
+pub fn configurations(con: &Connection) -> rusqlite::Result<BTreeMap<GameId, String>> {
+    let mut packs: BTreeMap<GameId, BTreeSet<&'static str>> = BTreeMap::new();
+
+    let mut stmt = con.prepare("SELECT GameId, Ruleset FROM Games")?;
+    let rulesets = stmt.query_map(NO_PARAMS, |row| Ok((row.get::<_, GameId>(0)?, row.get::<_, String>(1)?)))?;
+    for ruleset in rulesets {
+        let (game_id, ruleset) = ruleset?;
+        let game = packs.entry(game_id).or_default();
+        if let Some((_, pack)) = RULESETS.iter().find(|(key, _)| *key == ruleset) {
+            game.insert(pack);
+        }
+    }
+
+    let mut stmt = con.prepare(
+        "SELECT DISTINCT go.GameId, gp.LeaderType FROM GamePlayers gp \
+         JOIN GameObjects go ON go.PlayerObjectId = gp.PlayerObjectId",
+    )?;
+    let leaders = stmt.query_map(NO_PARAMS, |row| Ok((row.get::<_, GameId>(0)?, row.get::<_, String>(1)?)))?;
+    for leader in leaders {
+        let (game_id, leader) = leader?;
+        if let Some((_, pack)) = LEADER_PACKS.iter().find(|(key, _)| *key == leader) {
+            packs.entry(game_id).or_default().insert(pack);
+        }
+    }
+
+    Ok(packs
+        .into_iter()
+        .map(|(game_id, packs)| (game_id, describe(&packs)))
+        .collect())
+}
+
+/// Names the expansion first, followed by the other packs.
+fn describe(packs: &BTreeSet<&str>) -> String {
+    let expansion = RULESETS.iter().rev().map(|(_, name)| *name).find(|name| packs.contains(name));
+    let others: Vec<&str> = packs
+        .iter()
+        .copied()
+        .filter(|pack| Some(*pack) != expansion)
+        .collect();
+    match (expansion, others.is_empty()) {
+        (Some(expansion), true) => expansion.to_string(),
+        (Some(expansion), false) => format!("{} + {}", expansion, others.join(", ")),
+        (None, true) => "Base game".to_string(),
+        (None, false) => format!("Base game + {}", others.join(", ")),
+    }
+}
+
+/// The leaders & rulesets of each game outside the official content -
+/// traces of mods. Games without any are left out.
+pub fn modded_games(con: &Connection) -> rusqlite::Result<BTreeMap<GameId, Vec<String>>> {
+    let mut modded: BTreeMap<GameId, Vec<String>> = BTreeMap::new();
+
+    let mut stmt = con.prepare("SELECT GameId, Ruleset FROM Games")?;
+    let rulesets = stmt.query_map(NO_PARAMS, |row| Ok((row.get::<_, GameId>(0)?, row.get::<_, String>(1)?)))?;
+    for ruleset in rulesets {
+        let (game_id, ruleset) = ruleset?;
+        if ruleset != "RULESET_STANDARD" && !RULESETS.iter().any(|(key, _)| *key == ruleset) {
+            modded.entry(game_id).or_default().push(ruleset);
+        }
+    }
+
+    let mut stmt = con.prepare(
+        "SELECT DISTINCT go.GameId, gp.LeaderType FROM GamePlayers gp \
+         JOIN GameObjects go ON go.PlayerObjectId = gp.PlayerObjectId WHERE gp.IsMajor ORDER BY 1, 2",
+    )?;
+    let leaders = stmt.query_map(NO_PARAMS, |row| Ok((row.get::<_, GameId>(0)?, row.get::<_, String>(1)?)))?;
+    for leader in leaders {
+        let (game_id, leader) = leader?;
+        if !is_official_leader(&leader) {
+            modded.entry(game_id).or_default().push(leader);
+        }
+    }
+    Ok(modded)
+}
+
+fn is_official_leader(leader: &str) -> bool {
+    BASE_LEADERS.contains(&leader) || LEADER_PACKS.iter().any(|(key, _)| *key == leader)
+}
@@ -0,0 +1,192 @@
+//! Copying rows of any table between databases: The columns to copy are
+//! looked up with `PRAGMA table_info` at runtime, so columns only some Hall
+//! of Fame versions have - e.g. added by a game patch - are copied along
+//! whenever both databases have them.
+
+use std::rc::Rc;
+
+use rusqlite::types::Value;
+use rusqlite::{Connection, Statement, ToSql};
+
+use crate::schema;
+
+/// A row read by a `Copier`, with the values of all columns both databases have.
+#[derive(Debug)]
+pub struct Row {
+    columns: Rc<[String]>,
+    values: Vec<Value>,
+}
+
+impl Row {
+    /// The value of a column, NULL if the databases don't share it.
+    pub fn get(&self, column: &str) -> &Value {
+        match self.columns.iter().position(|c| c == column) {
+            Some(index) => &self.values[index],
+            None => &Value::Null,
+        }
+    }
+
+    /// The value of an integer column - an id or a reference.
+    pub fn id(&self, column: &str) -> Option<i64> {
+        match self.get(column) {
+            Value::Integer(id) => Some(*id),
+            _ => None,
+        }
+    }
+
+    /// The value of a text column.
+    pub fn text(&self, column: &str) -> Option<&str> {
+        match self.get(column) {
+            Value::Text(text) => Some(text),
+            _ => None,
+        }
+    }
+
+    /// The value of an id column the row can't lack.
+    pub fn key(&self, column: &str) -> rusqlite::Result<i64> {
+        self.id(column)
+            .ok_or_else(|| rusqlite::Error::InvalidColumnName(column.to_string()))
+    }
+
+    /// The columns with their values.
+    pub fn values(&self) -> impl Iterator<Item = (&str, &Value)> {
+        self.columns.iter().map(String::as_str).zip(&self.values)
+    }
+
+    /// Overwrites a column before the row is inserted, if the databases share it.
+    pub fn set(&mut self, column: &str, value: impl Into<Value>) {
+        if let Some(index) = self.columns.iter().position(|c| c == column) {
+            self.values[index] = value.into();
+        }
+    }
+}
+
+fn select_sql(columns: &[String], table: &str, filter: &str) -> String {
+    let selected: Vec<String> = columns.iter().map(|column| format!("t.\"{}\"", column)).collect();
+    format!("SELECT {} FROM \"{}\" t {}", selected.join(", "), table, filter)
+}
+
+fn read_rows<P>(select: &mut Statement, columns: &Rc<[String]>, params: P) -> rusqlite::Result<Vec<Row>>
+where
+    P: IntoIterator,
+    P::Item: ToSql,
+{
+    let mut rows = select.query(params)?;
+    let mut read = Vec::new();
+    while let Some(row) = rows.next()? {
+        let values = (0..columns.len())
+            .map(|index| row.get(index))
+            .collect::<rusqlite::Result<Vec<Value>>>()?;
+        read.push(Row {
+            columns: columns.clone(),
+            values,
+        });
+    }
+    Ok(read)
+}
+
+/// Reads the rows of `table` that `filter` selects - as for `Copier::new` -
+/// with all of its columns, e.g. to show them.
+pub fn read<P>(con: &Connection, table: &str, filter: &str, params: P) -> rusqlite::Result<Vec<Row>>
+where
+    P: IntoIterator,
+    P::Item: ToSql,
+{
+    let columns: Rc<[String]> = schema::columns(con, table)?.into();
+    let mut select = con.prepare(&select_sql(&columns, table, filter))?;
+    read_rows(&mut select, &columns, params)
+}
+
+/// Reads rows of a table from the source & inserts them into the target.
+pub struct Copier<'s, 't> {
+    target: &'t Connection,
+    table: String,
+    columns: Rc<[String]>,
+    key: Option<String>,
+    select: Statement<'s>,
+    insert: Statement<'t>,
+}
+
+impl<'s, 't> Copier<'s, 't> {
+    /// Copies the rows of `table` that `filter` selects - joins & a WHERE
+    /// clause, with the table aliased as `t`. The `key` column is the table's
+    /// own id, assigned anew by the target.
+    pub fn new(
+        source: &'s Connection,
+        target: &'t Connection,
+        table: &str,
+        key: Option<&str>,
+        filter: &str,
+    ) -> rusqlite::Result<Self> {
+        let target_columns = schema::columns(target, table)?;
+        let columns: Rc<[String]> = schema::columns(source, table)?
+            .into_iter()
+            .filter(|column| target_columns.contains(column))
+            .collect();
+        let inserted: Vec<String> = columns
+            .iter()
+            .filter(|column| Some(column.as_str()) != key)
+            .map(|column| format!("\"{}\"", column))
+            .collect();
+        let select = source.prepare(&select_sql(&columns, table, filter))?;
+        let insert = target.prepare(&format!(
+            "INSERT INTO \"{}\" ({}) VALUES ({})",
+            table,
+            inserted.join(", "),
+            vec!["?"; inserted.len()].join(", ")
+        ))?;
+        Ok(Copier {
+            target,
+            table: table.to_string(),
+            columns,
+            key: key.map(str::to_string),
+            select,
+            insert,
+        })
+    }
+
+    /// The rows the filter selects with `params`.
+    pub fn rows<P>(&mut self, params: P) -> rusqlite::Result<Vec<Row>>
+    where
+        P: IntoIterator,
+        P::Item: ToSql,
+    {
+        read_rows(&mut self.select, &self.columns, params)
+    }
+
+    /// Inserts a row into the target without its key, returning the rowid it got.
+    pub fn insert(&mut self, row: &Row) -> rusqlite::Result<i64> {
+        let key = self.key.as_deref();
+        let values = row
+            .columns
+            .iter()
+            .zip(&row.values)
+            .filter(|(column, _)| Some(column.as_str()) != key)
+            .map(|(_, value)| value);
+        self.insert.insert(values)
+    }
+
+    /// Inserts a row like `insert` unless the target has a row with the same
+    /// values, returning whether it did.
+    pub fn insert_new(&mut self, row: &Row) -> rusqlite::Result<bool> {
+        let key = self.key.as_deref();
+        let (columns, values): (Vec<String>, Vec<&Value>) = row
+            .columns
+            .iter()
+            .zip(&row.values)
+            .filter(|(column, _)| Some(column.as_str()) != key)
+            .map(|(column, value)| (format!("\"{}\" IS ?", column), value))
+            .unzip();
+        let mut stmt = self.target.prepare_cached(&format!(
+            "SELECT COUNT(*) FROM \"{}\" WHERE {}",
+            self.table,
+            if columns.is_empty() { "1".to_string() } else { columns.join(" AND ") }
+        ))?;
+        let existing: i64 = stmt.query_row(values, |row| row.get(0))?;
+        if existing > 0 {
+            return Ok(false);
+        }
+        self.insert(row)?;
+        Ok(true)
+    }
+}
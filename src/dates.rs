@@ -0,0 +1,211 @@
+//! Conversions between `YYYY-MM-DD` dates (UTC) & the Unix timestamps of `Games.LastPlayed`.
+
+/// Days since 1970-01-01 of a proleptic Gregorian date.
+fn days_from_civil(year: i64, month: i64, day: i64) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year - era * 400;
+    let day_of_year = (153 * ((month + 9) % 12) + 2) / 5 + day - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    era * 146_097 + day_of_era - 719_468
+}
+
+fn civil_from_days(days: i64) -> (i64, i64, i64) {
+    let days = days + 719_468;
+    let era = days.div_euclid(146_097);
+    let day_of_era = days - era * 146_097;
+    let year_of_era = (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month_index + 2) / 5 + 1;
+    let month = if month_index < 10 { month_index + 3 } else { month_index - 9 };
+    let year = year_of_era + era * 400 + if month <= 2 { 1 } else { 0 };
+    (year, month, day)
+}
+
+/// Days of a month, February counting 29 in leap years.
+fn days_in_month(year: i64, month: i64) -> i64 {
+    match month {
+        2 if year % 4 == 0 && (year % 100 != 0 || year % 400 == 0) => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    }
+}
+
+/// Parses `YYYY-MM-DD` into the timestamp of that day's start.
+pub fn parse_date(text: &str) -> Result<i64, String> {
+    let parts: Vec<&str> = text.trim().split('-').collect();
+    let invalid = || format!("Invalid date {:?}, expected YYYY-MM-DD", text);
+    if parts.len() != 3 {
+        return Err(invalid());
+    }
+    let year: i64 = parts[0].parse().map_err(|_| invalid())?;
+    let month: i64 = parts[1].parse().map_err(|_| invalid())?;
+    let day: i64 = parts[2].parse().map_err(|_| invalid())?;
+    if !(1..=12).contains(&month) || !(1..=days_in_month(year, month)).contains(&day) {
+        return Err(invalid());
+    }
+    Ok(days_from_civil(year, month, day) * 86_400)
+}
+
+/// Formats a timestamp as `YYYY-MM-DD`.
+pub fn format_date(timestamp: i64) -> String {
+    let (year, month, day) = civil_from_days(timestamp.div_euclid(86_400));
+    format!("{:04}-{:02}-{:02}", year, month, day)
+}
+
+/// Formats a timestamp as `YYYY-MM-DD HH:MM:SS`.
+pub fn format_date_time(timestamp: i64) -> String {
+    let seconds = timestamp.rem_euclid(86_400);
+    format!(
+        "{} {:02}:{:02}:{:02}",
+        format_date(timestamp),
+        seconds / 3600,
+        seconds / 60 % 60,
+        seconds % 60
+    )
+}
+
+/// Formats a timestamp as `YYYY-MM-DD_HHMMSS` - sortable & safe in file names.
+pub fn format_file_stamp(timestamp: i64) -> String {
+    let seconds = timestamp.rem_euclid(86_400);
+    format!(
+        "{}_{:02}{:02}{:02}",
+        format_date(timestamp),
+        seconds / 3600,
+        seconds / 60 % 60,
+        seconds % 60
+    )
+}
+
+/// End of `--until`: The start of the day after a date - so the date itself
+/// is included - or a Unix timestamp.
+pub fn parse_until(text: &str) -> Result<i64, String> {
+    let text = text.trim();
+    if !text.is_empty() && text.bytes().all(|b| b.is_ascii_digit()) {
+        return text.parse().map_err(|_| format!("Invalid timestamp {:?}", text));
+    }
+    parse_date(text)
+        .map(|start| start + 86_400)
+        .map_err(|_| format!("Invalid end {:?}, expected YYYY-MM-DD or a Unix timestamp", text))
+}
+
+/// Cutoff of `--since`: A date, a Unix timestamp or `auto` for the newest
+/// game already in the target.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Since {
+    Timestamp(i64),
+    Auto,
+}
+
+impl std::str::FromStr for Since {
+    type Err = String;
+
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        match text.trim() {
+            "auto" => Ok(Since::Auto),
+            timestamp if !timestamp.is_empty() && timestamp.bytes().all(|b| b.is_ascii_digit()) => timestamp
+                .parse()
+                .map(Since::Timestamp)
+                .map_err(|_| format!("Invalid timestamp {:?}", text)),
+            date => parse_date(date)
+                .map(Since::Timestamp)
+                .map_err(|_| format!("Invalid cutoff {:?}, expected YYYY-MM-DD, a Unix timestamp or auto", text)),
+        }
+    }
+}
+
+/// Cutoff of `--older-than`: An age like `30d`, `6w`, `3m` or `2y` - months of
+/// 30 & years of 365 days - back from now, or a date.
+pub fn parse_older_than(text: &str) -> Result<i64, String> {
+    let text = text.trim();
+    let invalid = || format!("Invalid age {:?}, expected e.g. 30d, 6w, 3m, 2y or YYYY-MM-DD", text);
+    let days = match text.char_indices().last() {
+        Some((index, unit)) if text[..index].bytes().all(|b| b.is_ascii_digit()) && index > 0 => {
+            let number: i64 = text[..index].parse().map_err(|_| invalid())?;
+            match unit {
+                'd' => number,
+                'w' => number * 7,
+                'm' => number * 30,
+                'y' => number * 365,
+                _ => return Err(invalid()),
+            }
+        }
+        _ => return parse_date(text).map_err(|_| invalid()),
+    };
+    let now = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map_or(0, |d| d.as_secs() as i64);
+    Ok(now - days * 86_400)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_date_takes_the_start_of_the_day() {
+        assert_eq!(parse_date("1970-01-01"), Ok(0));
+        assert_eq!(parse_date(" 2021-03-04 "), Ok(1_614_816_000));
+        assert_eq!(format_date(parse_date("2021-12-31").unwrap()), "2021-12-31");
+    }
+
+    #[test]
+    fn parse_date_checks_the_day_against_the_month() {
+        assert!(parse_date("2021-01-31").is_ok());
+        assert!(parse_date("2021-04-30").is_ok());
+        assert!(parse_date("2021-04-31").is_err());
+        assert!(parse_date("2021-02-28").is_ok());
+        assert!(parse_date("2021-02-29").is_err());
+        assert!(parse_date("2020-02-29").is_ok());
+        assert!(parse_date("2000-02-29").is_ok());
+        assert!(parse_date("1900-02-29").is_err());
+        assert!(parse_date("2021-01-00").is_err());
+        assert!(parse_date("2021-00-01").is_err());
+        assert!(parse_date("2021-13-01").is_err());
+    }
+
+    #[test]
+    fn parse_date_refuses_other_formats() {
+        for text in &["", "2021", "2021-03", "2021-03-04-05", "2021/03/04", "21-3-x"] {
+            assert!(parse_date(text).is_err(), "{:?}", text);
+        }
+    }
+
+    #[test]
+    fn parse_until_includes_the_date() {
+        assert_eq!(parse_until("1970-01-01"), Ok(86_400));
+        assert_eq!(parse_until("2020-02-29"), Ok(parse_date("2020-03-01").unwrap()));
+        assert_eq!(parse_until("1614816000"), Ok(1_614_816_000));
+        assert!(parse_until("2021-02-30").is_err());
+        assert!(parse_until("").is_err());
+    }
+
+    #[test]
+    fn since_passes_timestamps_through() {
+        assert_eq!("1614816000".parse(), Ok(Since::Timestamp(1_614_816_000)));
+        assert_eq!("0".parse(), Ok(Since::Timestamp(0)));
+        assert_eq!("2021-03-04".parse(), Ok(Since::Timestamp(1_614_816_000)));
+        assert_eq!(" auto ".parse(), Ok(Since::Auto));
+        assert!("2021-02-29".parse::<Since>().is_err());
+        assert!("yesterday".parse::<Since>().is_err());
+        assert!("".parse::<Since>().is_err());
+    }
+
+    #[test]
+    fn parse_older_than_counts_back_from_now() {
+        let now = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap()
+            .as_secs() as i64;
+        for (text, days) in &[("0d", 0), ("30d", 30), ("6w", 42), ("3m", 90), ("2y", 730)] {
+            let cutoff = parse_older_than(text).unwrap();
+            assert!((now - days * 86_400 - cutoff).abs() <= 5, "{:?}", text);
+        }
+        assert_eq!(parse_older_than("2021-03-04"), Ok(1_614_816_000));
+        for text in &["", "d", "30", "30x", "-3d", "2021-02-29"] {
+            assert!(parse_older_than(text).is_err(), "{:?}", text);
+        }
+    }
+}
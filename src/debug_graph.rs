@@ -0,0 +1,196 @@
+use std::collections::HashSet;
+use std::fmt::Write as _;
+
+use rusqlite::{params, Connection};
+
+use crate::GameId;
+
+fn quote(text: &str) -> String {
+    format!("\"{}\"", text.replace('\\', "\\\\").replace('"', "\\\"").replace('\n', "\\n"))
+}
+
+/// Renders the reference graph of a game - Game, GameObjects, GamePlayers,
+/// Game/ObjectDataPointValues & DataSets - in Graphviz' dot format.
+/// References to rows that don't exist are drawn as red edges to red nodes.
+pub fn render(con: &Connection, game_id: GameId) -> Result<String, Box<dyn std::error::Error>> {
+    let mut dot = String::new();
+    let _ = writeln!(dot, "digraph game_{} {{\n  rankdir=LR;\n  node [shape=box, fontsize=10];", game_id);
+
+    let (turns, victory): (i32, Option<String>) = con
+        .query_row(
+            "SELECT TurnCount, VictoryType FROM Games WHERE GameId = ?",
+            params![game_id],
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        )
+        .map_err(|_| format!("Game {} not found", game_id))?;
+    let _ = writeln!(
+        dot,
+        "  game [label={}, style=filled, fillcolor=gold];",
+        quote(&format!("Game {}\n{} turns\n{}", game_id, turns, victory.as_deref().unwrap_or("-")))
+    );
+
+    let mut objects = HashSet::new();
+    let mut stmt = con.prepare("SELECT ObjectId, PlayerObjectId, Type, Name FROM GameObjects WHERE GameId = ?")?;
+    let rows = stmt
+        .query_map(params![game_id], |row| {
+            Ok((
+                row.get::<_, i64>(0)?,
+                row.get::<_, Option<i64>>(1)?,
+                row.get::<_, String>(2)?,
+                row.get::<_, Option<String>>(3)?,
+            ))
+        })?
+        .collect::<rusqlite::Result<Vec<_>>>()?;
+    for (object_id, _, object_type, name) in &rows {
+        objects.insert(*object_id);
+        let _ = writeln!(
+            dot,
+            "  o{} [label={}];\n  game -> o{};",
+            object_id,
+            quote(&format!("GameObject {}\n{}\n{}", object_id, object_type, name.as_deref().unwrap_or(""))),
+            object_id
+        );
+    }
+
+    let mut players = HashSet::new();
+    let mut player_stmt = con.prepare("SELECT LeaderType, IsLocal, Score FROM GamePlayers WHERE PlayerObjectId = ?")?;
+    for (object_id, player_object_id, _, _) in &rows {
+        let player_object_id = match player_object_id {
+            Some(id) => *id,
+            None => continue,
+        };
+        if players.insert(player_object_id) {
+            let player = player_stmt
+                .query_map(params![player_object_id], |row| {
+                    Ok((row.get::<_, String>(0)?, row.get::<_, bool>(1)?, row.get::<_, i32>(2)?))
+                })?
+                .next()
+                .transpose()?;
+            match player {
+                Some((leader, is_local, score)) => {
+                    let _ = writeln!(
+                        dot,
+                        "  p{} [label={}, style=filled, fillcolor={}];",
+                        player_object_id,
+                        quote(&format!("GamePlayer {}\n{}\nscore {}", player_object_id, leader, score)),
+                        if is_local { "lightblue" } else { "lightgrey" }
+                    );
+                }
+                None => {
+                    let _ = writeln!(
+                        dot,
+                        "  p{} [label={}, color=red, fontcolor=red];",
+                        player_object_id,
+                        quote(&format!("missing GamePlayer {}", player_object_id))
+                    );
+                }
+            }
+        }
+        let _ = writeln!(dot, "  o{} -> p{} [label=player];", object_id, player_object_id);
+    }
+
+    let mut missing_objects = HashSet::new();
+    let mut reference = |dot: &mut String, from: &str, object_id: i64, label: &str| {
+        if objects.contains(&object_id) {
+            let _ = writeln!(dot, "  {} -> o{} [label={}];", from, object_id, quote(label));
+        } else {
+            if missing_objects.insert(object_id) {
+                let _ = writeln!(
+                    dot,
+                    "  o{} [label={}, color=red, fontcolor=red];",
+                    object_id,
+                    quote(&format!("missing GameObject {}", object_id))
+                );
+            }
+            let _ = writeln!(dot, "  {} -> o{} [label={}, color=red];", from, object_id, quote(label));
+        }
+    };
+
+    let mut stmt = con.prepare(
+        "SELECT rowid, DataPoint, ValueObjectId, COALESCE(ValueString, CAST(ValueNumeric AS TEXT)) FROM GameDataPointValues WHERE GameId = ?",
+    )?;
+    let values = stmt
+        .query_map(params![game_id], |row| {
+            Ok((
+                row.get::<_, i64>(0)?,
+                row.get::<_, String>(1)?,
+                row.get::<_, Option<i64>>(2)?,
+                row.get::<_, Option<String>>(3)?,
+            ))
+        })?
+        .collect::<rusqlite::Result<Vec<_>>>()?;
+    for (rowid, data_point, value_object_id, value) in values {
+        let node = format!("gdpv{}", rowid);
+        let _ = writeln!(
+            dot,
+            "  {} [label={}, shape=note];\n  game -> {};",
+            node,
+            quote(&format!("{}\n{}", data_point, value.as_deref().unwrap_or(""))),
+            node
+        );
+        if let Some(object_id) = value_object_id {
+            reference(&mut dot, &node, object_id, "value");
+        }
+    }
+
+    let mut stmt = con.prepare(
+        "SELECT rowid, ObjectId, DataPoint, ValueObjectId, COALESCE(ValueString, CAST(ValueNumeric AS TEXT)) FROM ObjectDataPointValues \
+         WHERE ObjectId IN (SELECT ObjectId FROM GameObjects WHERE GameId = ?)",
+    )?;
+    let values = stmt
+        .query_map(params![game_id], |row| {
+            Ok((
+                row.get::<_, i64>(0)?,
+                row.get::<_, i64>(1)?,
+                row.get::<_, String>(2)?,
+                row.get::<_, Option<i64>>(3)?,
+                row.get::<_, Option<String>>(4)?,
+            ))
+        })?
+        .collect::<rusqlite::Result<Vec<_>>>()?;
+    for (rowid, object_id, data_point, value_object_id, value) in values {
+        let node = format!("odpv{}", rowid);
+        let _ = writeln!(
+            dot,
+            "  {} [label={}, shape=note];\n  o{} -> {};",
+            node,
+            quote(&format!("{}\n{}", data_point, value.as_deref().unwrap_or(""))),
+            object_id,
+            node
+        );
+        if let Some(value_object_id) = value_object_id {
+            reference(&mut dot, &node, value_object_id, "value");
+        }
+    }
+
+    let mut stmt = con.prepare(
+        "SELECT ds.DataSetId, ds.ObjectId, ds.DataSet, COUNT(dsv.DataSetId) FROM DataSets ds \
+         LEFT JOIN DataSetValues dsv ON dsv.DataSetId = ds.DataSetId WHERE ds.GameId = ? GROUP BY ds.DataSetId",
+    )?;
+    let sets = stmt
+        .query_map(params![game_id], |row| {
+            Ok((
+                row.get::<_, i64>(0)?,
+                row.get::<_, Option<i64>>(1)?,
+                row.get::<_, String>(2)?,
+                row.get::<_, i64>(3)?,
+            ))
+        })?
+        .collect::<rusqlite::Result<Vec<_>>>()?;
+    for (data_set_id, object_id, data_set, values) in sets {
+        let node = format!("ds{}", data_set_id);
+        let _ = writeln!(
+            dot,
+            "  {} [label={}, shape=cylinder];\n  game -> {};",
+            node,
+            quote(&format!("DataSet {}\n{}\n{} values", data_set_id, data_set, values)),
+            node
+        );
+        if let Some(object_id) = object_id {
+            reference(&mut dot, &node, object_id, "object");
+        }
+    }
+
+    dot.push_str("}\n");
+    Ok(dot)
+}
@@ -0,0 +1,361 @@
+//! Strategies deciding whether a source game is already in the target, chosen
+//! with `--dedupe-by`.
+
+use std::collections::HashMap;
+use std::hash::{Hash, Hasher};
+use std::str::FromStr;
+
+use rusqlite::{params, Connection, OptionalExtension, NO_PARAMS};
+use serde_rusqlite::from_rows;
+
+use crate::hash::Fnv64;
+use crate::{cache, maps, Game, GameId};
+
+/// How far apart in seconds the LastPlayed of two versions of a game may be
+/// for `fingerprint` to take them for the same game.
+const FINGERPRINT_WINDOW: i64 = 30 * 24 * 60 * 60;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum Strategy {
+    /// All columns of Games are equal - the game saved at the same point
+    #[default]
+    Exact,
+    /// Same setup & players, last played within 30 days - also matches a game continued since
+    Fingerprint,
+    /// Same GameId - for databases that are copies of one Hall of Fame
+    GameId,
+}
+
+impl FromStr for Strategy {
+    type Err = String;
+
+    fn from_str(strategy: &str) -> Result<Self, Self::Err> {
+        match strategy {
+            "exact" => Ok(Strategy::Exact),
+            "fingerprint" => Ok(Strategy::Fingerprint),
+            "game-id" => Ok(Strategy::GameId),
+            _ => Err(format!(
+                "Unknown dedupe strategy {:?}, expected exact, fingerprint or game-id",
+                strategy
+            )),
+        }
+    }
+}
+
+impl Strategy {
+    pub fn name(self) -> &'static str {
+        match self {
+            Strategy::Exact => "exact",
+            Strategy::Fingerprint => "fingerprint",
+            Strategy::GameId => "game-id",
+        }
+    }
+}
+
+/// Hashes what stays the same while a game is continued: Its setup & the
+/// leaders, ids & teams of its players.
+pub fn fingerprint(con: &Connection, game: &Game) -> rusqlite::Result<u64> {
+    let mut hasher = Fnv64::default();
+    game.rule_set.hash(&mut hasher);
+    game.game_mode.hash(&mut hasher);
+    game.game_speed_type.hash(&mut hasher);
+    game.map_size_type.hash(&mut hasher);
+    maps::canonical(&game.map).hash(&mut hasher);
+    game.start_era_type.hash(&mut hasher);
+    game.start_turn.hash(&mut hasher);
+
+    let mut stmt = con.prepare(
+        "SELECT DISTINCT gp.LeaderType, gp.PlayerId, gp.TeamId FROM GamePlayers gp \
+         JOIN GameObjects go ON go.PlayerObjectId = gp.PlayerObjectId \
+         WHERE go.GameId = ? ORDER BY gp.PlayerId, gp.LeaderType",
+    )?;
+    let players = stmt.query_map(params![game.game_id], |row| {
+        Ok((row.get::<_, String>(0)?, row.get::<_, i64>(1)?, row.get::<_, i64>(2)?))
+    })?;
+    for player in players {
+        player?.hash(&mut hasher);
+    }
+    Ok(hasher.finish())
+}
+
+fn exact(target: &Connection, game: &Game) -> rusqlite::Result<Option<GameId>> {
+    // Map is compared in its canonical form, as its path form varies
+    let mut stmt = target.prepare("SELECT GameId, Map FROM Games WHERE Ruleset = ?1 AND GameMode = ?2 AND TurnCount = ?3 AND GameSpeedType = ?4 AND MapSizeType = ?5 AND StartEraType = ?6 AND StartTurn = ?7 AND VictorTeamId IS ?8 AND VictoryType IS ?9 AND LastPlayed = ?10")?;
+    let candidates = stmt
+        .query_map(
+            params![
+                game.rule_set,
+                game.game_mode,
+                game.turn_count,
+                game.game_speed_type,
+                game.map_size_type,
+                game.start_era_type,
+                game.start_turn,
+                game.victor_team_id,
+                game.victory_type,
+                game.last_played,
+            ],
+            |row| Ok((row.get::<_, GameId>(0)?, row.get::<_, String>(1)?)),
+        )?
+        .collect::<rusqlite::Result<Vec<_>>>()?;
+    let map = maps::canonical(&game.map);
+    Ok(candidates
+        .into_iter()
+        .find(|(_, candidate)| maps::canonical(candidate) == map)
+        .map(|(game_id, _)| game_id))
+}
+
+/// The target game closest in LastPlayed with the fingerprint of `game`.
+fn by_fingerprint(source: &Connection, target: &Connection, game: &Game) -> Result<Option<GameId>, Box<dyn std::error::Error>> {
+    let wanted = fingerprint(source, game)?;
+    let mut stmt = target.prepare(
+        "SELECT * FROM Games WHERE Ruleset = ?1 AND MapSizeType = ?2 AND StartEraType = ?3 \
+         AND LastPlayed BETWEEN ?4 - ?5 AND ?4 + ?5 ORDER BY ABS(LastPlayed - ?4)",
+    )?;
+    let candidates = from_rows::<Game>(stmt.query(params![
+        game.rule_set,
+        game.map_size_type,
+        game.start_era_type,
+        game.last_played,
+        FINGERPRINT_WINDOW,
+    ])?);
+    for candidate in candidates {
+        let candidate = candidate?;
+        if fingerprint(target, &candidate)? == wanted {
+            return Ok(Some(candidate.game_id));
+        }
+    }
+    Ok(None)
+}
+
+/// The target game the source's `game` is a duplicate of under `strategy`, if any.
+pub fn duplicate_of(
+    source: &Connection,
+    target: &Connection,
+    game: &Game,
+    strategy: Strategy,
+) -> Result<Option<GameId>, Box<dyn std::error::Error>> {
+    Ok(match strategy {
+        Strategy::Exact => exact(target, game)?,
+        Strategy::Fingerprint => by_fingerprint(source, target, game)?,
+        Strategy::GameId => target
+            .query_row("SELECT GameId FROM Games WHERE GameId = ?", params![game.game_id], |row| row.get(0))
+            .optional()?,
+    })
+}
+
+/// A game kept & its duplicates to delete.
+pub type Duplicates = (GameId, Vec<GameId>);
+
+/// Duplicate games within one database under `strategy`: Of exact copies the
+/// first copied is kept, of versions of a continued game the one played last.
+pub fn duplicates(con: &Connection, strategy: Strategy) -> Result<Vec<Duplicates>, Box<dyn std::error::Error>> {
+    let mut stmt = con.prepare("SELECT * FROM Games ORDER BY LastPlayed, TurnCount, GameId")?;
+    let games = from_rows::<Game>(stmt.query(NO_PARAMS)?).collect::<Result<Vec<_>, _>>()?;
+    let mut groups: Vec<Vec<&Game>> = Vec::new();
+    match strategy {
+        Strategy::Exact => {
+            let fingerprints = version_fingerprints(con)?;
+            let mut by_fingerprint: HashMap<u64, usize> = HashMap::new();
+            for game in &games {
+                match by_fingerprint.get(&fingerprints[&game.game_id]) {
+                    Some(&group) => groups[group].push(game),
+                    None => {
+                        by_fingerprint.insert(fingerprints[&game.game_id], groups.len());
+                        groups.push(vec![game]);
+                    }
+                }
+            }
+            for group in &mut groups {
+                group.sort_by_key(|game| game.game_id);
+            }
+        }
+        Strategy::Fingerprint => {
+            // Versions are chained as long as each was played within the window of the one before
+            let mut latest: HashMap<u64, usize> = HashMap::new();
+            for game in &games {
+                let fingerprint = fingerprint(con, game)?;
+                match latest.get(&fingerprint) {
+                    Some(&group)
+                        if i64::from(game.last_played) - i64::from(groups[group].last().unwrap().last_played)
+                            <= FINGERPRINT_WINDOW =>
+                    {
+                        groups[group].push(game)
+                    }
+                    _ => {
+                        latest.insert(fingerprint, groups.len());
+                        groups.push(vec![game]);
+                    }
+                }
+            }
+            for group in &mut groups {
+                group.reverse();
+            }
+        }
+        Strategy::GameId => return Err("GameIds are unique within a database - dedupe by exact or fingerprint".into()),
+    }
+    Ok(groups
+        .into_iter()
+        .filter(|group| group.len() > 1)
+        .map(|group| (group[0].game_id, group[1..].iter().map(|game| game.game_id).collect()))
+        .collect())
+}
+
+/// Fingerprints of all versions of games in a database, by GameId: The exact
+/// key fields plus the leaders, ids, teams & scores of the players - two
+/// databases with a shared history have these in common. Computed with one
+/// scan of the games & one of the players instead of queries per game.
+pub fn version_fingerprints(con: &Connection) -> Result<HashMap<GameId, u64>, Box<dyn std::error::Error>> {
+    let mut players: HashMap<GameId, Vec<(String, i64, i64, i64)>> = HashMap::new();
+    let mut stmt = con.prepare(
+        "SELECT DISTINCT go.GameId, gp.LeaderType, gp.PlayerId, gp.TeamId, gp.Score FROM GamePlayers gp \
+         JOIN GameObjects go ON go.PlayerObjectId = gp.PlayerObjectId \
+         ORDER BY go.GameId, gp.PlayerId, gp.LeaderType, gp.Score",
+    )?;
+    let mut rows = stmt.query(NO_PARAMS)?;
+    while let Some(row) = rows.next()? {
+        players
+            .entry(row.get(0)?)
+            .or_default()
+            .push((row.get(1)?, row.get(2)?, row.get(3)?, row.get(4)?));
+    }
+
+    let mut stmt = con.prepare("SELECT * FROM Games")?;
+    let mut fingerprints = HashMap::new();
+    for game in from_rows::<Game>(stmt.query(NO_PARAMS)?) {
+        let game = game?;
+        let mut hasher = Fnv64::default();
+        hasher.write_u64(cache::game_hash(&game));
+        players.get(&game.game_id).hash(&mut hasher);
+        fingerprints.insert(game.game_id, hasher.finish());
+    }
+    Ok(fingerprints)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::schema;
+
+    const DAY: i64 = 24 * 60 * 60;
+
+    fn database() -> Connection {
+        let con = Connection::open_in_memory().unwrap();
+        schema::create(&con).unwrap();
+        con
+    }
+
+    fn add_game(con: &Connection, game_id: GameId, map: &str, last_played: i64) {
+        con.execute(
+            "INSERT INTO Games (GameId, Ruleset, GameMode, TurnCount, GameSpeedType, MapSizeType, Map, StartEraType, \
+             StartTurn, LastPlayed) VALUES (?, 'RULESET_STANDARD', 0, 100, 'GAMESPEED_STANDARD', 'MAPSIZE_SMALL', \
+             ?, 'ERA_ANCIENT', 1, ?)",
+            params![game_id, map, last_played],
+        )
+        .unwrap();
+    }
+
+    /// Adds a player object to the game - ObjectId & PlayerObjectId alike are `object_id`.
+    fn add_player(con: &Connection, game_id: GameId, object_id: i64, leader: &str, score: i64) {
+        con.execute(
+            "INSERT INTO GameObjects (ObjectId, GameId, PlayerObjectId, Type) VALUES (?1, ?2, ?1, 'PLAYER')",
+            params![object_id, game_id],
+        )
+        .unwrap();
+        con.execute(
+            "INSERT INTO GamePlayers (PlayerObjectId, IsLocal, IsAI, IsMajor, LeaderType, Score, PlayerId, TeamId) \
+             VALUES (?, 1, 0, 1, ?, ?, 0, 0)",
+            params![object_id, leader, score],
+        )
+        .unwrap();
+    }
+
+    /// A source with game 1, played at day 100 with Gandhi.
+    fn source() -> Connection {
+        let source = database();
+        add_game(&source, 1, "Continents.lua", 100 * DAY);
+        add_player(&source, 1, 10, "LEADER_GANDHI", 100);
+        source
+    }
+
+    fn duplicate(target: &Connection, strategy: Strategy) -> Option<GameId> {
+        let source = source();
+        let game = crate::load_game(&source, 1).unwrap();
+        duplicate_of(&source, target, &game, strategy).unwrap()
+    }
+
+    #[test]
+    fn exact_finds_the_game_saved_at_the_same_point() {
+        let target = database();
+        add_game(&target, 4, "Pangaea.lua", 100 * DAY);
+        add_game(&target, 5, "Maps/CONTINENTS.lua", 100 * DAY);
+        assert_eq!(duplicate(&target, Strategy::Exact), Some(5));
+    }
+
+    #[test]
+    fn exact_misses_the_game_played_on() {
+        let target = database();
+        add_game(&target, 5, "Continents.lua", 100 * DAY + 1);
+        assert_eq!(duplicate(&target, Strategy::Exact), None);
+    }
+
+    #[test]
+    fn fingerprint_finds_the_closest_version_within_the_window() {
+        let target = database();
+        add_game(&target, 4, "Continents.lua", 100 * DAY - FINGERPRINT_WINDOW);
+        add_player(&target, 4, 1, "LEADER_GANDHI", 50);
+        add_game(&target, 5, "Continents.lua", 110 * DAY);
+        add_player(&target, 5, 2, "LEADER_GANDHI", 200);
+        add_game(&target, 6, "Continents.lua", 101 * DAY);
+        add_player(&target, 6, 3, "LEADER_GILGAMESH", 200);
+        assert_eq!(duplicate(&target, Strategy::Fingerprint), Some(5));
+    }
+
+    #[test]
+    fn fingerprint_misses_versions_outside_the_window() {
+        let target = database();
+        add_game(&target, 4, "Continents.lua", 100 * DAY - FINGERPRINT_WINDOW - 1);
+        add_player(&target, 4, 1, "LEADER_GANDHI", 50);
+        add_game(&target, 5, "Continents.lua", 100 * DAY + FINGERPRINT_WINDOW + 1);
+        add_player(&target, 5, 2, "LEADER_GANDHI", 200);
+        assert_eq!(duplicate(&target, Strategy::Fingerprint), None);
+    }
+
+    #[test]
+    fn game_id_finds_the_same_game_id_only() {
+        let target = database();
+        add_game(&target, 2, "Continents.lua", 100 * DAY);
+        assert_eq!(duplicate(&target, Strategy::GameId), None);
+        add_game(&target, 1, "Pangaea.lua", 0);
+        assert_eq!(duplicate(&target, Strategy::GameId), Some(1));
+    }
+
+    #[test]
+    fn duplicates_keeps_the_first_exact_copy() {
+        let con = database();
+        for game_id in 1..=3 {
+            add_game(&con, game_id, "Continents.lua", 100 * DAY);
+            add_player(&con, game_id, game_id * 10, "LEADER_GANDHI", 100);
+        }
+        add_game(&con, 4, "Continents.lua", 100 * DAY);
+        add_player(&con, 4, 40, "LEADER_GANDHI", 101);
+        assert_eq!(duplicates(&con, Strategy::Exact).unwrap(), vec![(1, vec![2, 3])]);
+    }
+
+    #[test]
+    fn duplicates_keeps_the_version_played_last() {
+        let con = database();
+        // 1 to 3 are chained within the window, 4 lies beyond it
+        let days = [100, 120, 145, 145 + FINGERPRINT_WINDOW / DAY + 1];
+        for (game_id, day) in (1..).zip(days.iter()) {
+            add_game(&con, game_id, "Continents.lua", day * DAY);
+            add_player(&con, game_id, game_id * 10, "LEADER_GANDHI", game_id * 100);
+        }
+        assert_eq!(duplicates(&con, Strategy::Fingerprint).unwrap(), vec![(3, vec![2, 1])]);
+    }
+
+    #[test]
+    fn duplicates_refuses_game_ids() {
+        assert!(duplicates(&database(), Strategy::GameId).is_err());
+    }
+}
@@ -0,0 +1,87 @@
+//! `diff`: Compares the games of two databases the way a merge matches them,
+//! so the games only in one of them are exactly those a merge of it into the
+//! other would copy.
+
+use rusqlite::{Connection, NO_PARAMS};
+use serde_rusqlite::from_rows;
+
+use crate::names::Names;
+use crate::{conflicts, dedupe, explain, load_game, show, Game, GameId};
+
+/// A game of A the merge takes for one of B's, although they differ.
+pub struct Differing {
+    pub a: GameId,
+    pub b: GameId,
+    pub differences: Vec<String>,
+}
+
+#[derive(Default)]
+pub struct Diff {
+    pub only_a: Vec<Game>,
+    pub only_b: Vec<Game>,
+    pub differing: Vec<Differing>,
+    pub equal: usize,
+}
+
+fn games(con: &Connection) -> Result<Vec<Game>, Box<dyn std::error::Error>> {
+    let mut stmt = con.prepare("SELECT * FROM Games ORDER BY GameId")?;
+    let games = from_rows::<Game>(stmt.query(NO_PARAMS)?).collect::<Result<_, _>>()?;
+    Ok(games)
+}
+
+/// Matches the games of `a` & `b` with the duplicate check of `strategy`.
+pub fn compare(a: &Connection, b: &Connection, strategy: dedupe::Strategy) -> Result<Diff, Box<dyn std::error::Error>> {
+    let mut diff = Diff::default();
+    for game in games(a)? {
+        match dedupe::duplicate_of(a, b, &game, strategy)? {
+            Some(b_game) => {
+                let mut differences = explain::differences(&game, &load_game(b, b_game)?);
+                if conflicts::players(a, game.game_id)? != conflicts::players(b, b_game)? {
+                    differences.push("players or scores".to_string());
+                }
+                if differences.is_empty() {
+                    diff.equal += 1;
+                } else {
+                    diff.differing.push(Differing {
+                        a: game.game_id,
+                        b: b_game,
+                        differences,
+                    });
+                }
+            }
+            None => diff.only_a.push(game),
+        }
+    }
+    for game in games(b)? {
+        if dedupe::duplicate_of(b, a, &game, strategy)?.is_none() {
+            diff.only_b.push(game);
+        }
+    }
+    Ok(diff)
+}
+
+/// Prints the games only in either database, then those differing.
+pub fn print(
+    a: (&std::path::Path, &Connection),
+    b: (&std::path::Path, &Connection),
+    diff: &Diff,
+    names: &Names,
+) -> rusqlite::Result<()> {
+    for ((path, con), only) in &[(a, &diff.only_a), (b, &diff.only_b)] {
+        println!("Only in {:?}: {} game(s)", path, only.len());
+        for game in only.iter() {
+            println!("  {}", show::describe(con, names, game)?);
+        }
+    }
+    println!("In both but differing: {} game(s)", diff.differing.len());
+    for differing in &diff.differing {
+        println!(
+            "  Game {} / game {}: {}",
+            differing.a,
+            differing.b,
+            differing.differences.join(", ")
+        );
+    }
+    println!("In both & equal: {} game(s)", diff.equal);
+    Ok(())
+}
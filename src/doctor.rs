@@ -0,0 +1,209 @@
+use std::path::{Path, PathBuf};
+
+use rusqlite::{Connection, OpenFlags, NO_PARAMS};
+
+use crate::{integrity, lock, schema, EXPECTED_TABLES};
+
+const GAME_DIR: &str = "Sid Meier's Civilization VI";
+
+/// Places the game keeps its user data in, relative to the home directory.
+fn user_data_dirs(home: &Path) -> Vec<PathBuf> {
+    vec![
+        home.join("Library/Application Support").join(GAME_DIR),
+        home.join("Documents/My Games").join(GAME_DIR),
+        home.join(".local/share/aspyr-media").join(GAME_DIR),
+    ]
+}
+
+/// Default Steam library locations of the game installation.
+fn install_dirs(home: &Path) -> Vec<PathBuf> {
+    vec![
+        PathBuf::from("C:/Program Files (x86)/Steam/steamapps/common").join(GAME_DIR),
+        PathBuf::from("C:/Program Files/Epic Games/SidMeiersCivilizationVI"),
+        home.join("Library/Application Support/Steam/steamapps/common").join(GAME_DIR),
+        home.join(".steam/steam/steamapps/common").join(GAME_DIR),
+        home.join(".local/share/Steam/steamapps/common").join(GAME_DIR),
+    ]
+}
+
+fn home_dir() -> Option<PathBuf> {
+    std::env::var_os("HOME")
+        .or_else(|| std::env::var_os("USERPROFILE"))
+        .map(PathBuf::from)
+}
+
+fn ok(message: String) {
+    println!("[ok]   {}", message);
+}
+
+fn warn(message: String) {
+    println!("[warn] {}", message);
+}
+
+fn fail(message: String) {
+    println!("[fail] {}", message);
+}
+
+/// Free space of the file system holding `dir` in KiB, as reported by `df`.
+fn free_space_kib(dir: &Path) -> Option<u64> {
+    let output = std::process::Command::new("df").arg("-Pk").arg(dir).output().ok()?;
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    stdout.lines().nth(1)?.split_whitespace().nth(3)?.parse().ok()
+}
+
+/// Prints the findings about a database, returning whether none of them failed.
+pub fn check_database(path: &Path) -> bool {
+    let metadata = match std::fs::metadata(path) {
+        Ok(metadata) => metadata,
+        Err(e) => {
+            fail(format!("{:?} can't be accessed: {}", path, e));
+            return false;
+        }
+    };
+    ok(format!("{:?} exists with {} bytes", path, metadata.len()));
+    let mut passed = true;
+
+    if metadata.permissions().readonly() {
+        warn(format!("{:?} is read-only - it can be a source but not a target", path));
+    } else if let Err(e) = std::fs::OpenOptions::new().write(true).open(path) {
+        warn(format!("{:?} can't be opened for writing: {}", path, e));
+    }
+
+    for suffix in &["-wal", "-journal"] {
+        let mut side_file = path.as_os_str().to_owned();
+        side_file.push(suffix);
+        if Path::new(&side_file).exists() {
+            warn(format!(
+                "{:?} exists - Civilization VI might be running or have crashed, close it before merging",
+                side_file
+            ));
+        }
+    }
+
+    if let Ok(true) = lock::is_locked(path) {
+        fail(format!("{:?} is locked by another program - close Civilization VI before merging into it", path));
+        passed = false;
+    }
+
+    let con = match Connection::open_with_flags(path, OpenFlags::SQLITE_OPEN_READ_ONLY) {
+        Ok(con) => con,
+        Err(e) => {
+            fail(format!("{:?} isn't a SQLite database: {}", path, e));
+            return false;
+        }
+    };
+
+    let tables: Vec<String> = match con
+        .prepare("SELECT name FROM sqlite_master WHERE type = 'table'")
+        .and_then(|mut stmt| stmt.query_map(NO_PARAMS, |row| row.get(0))?.collect())
+    {
+        Ok(tables) => tables,
+        Err(e) => {
+            fail(format!("{:?} can't be read: {}", path, e));
+            return false;
+        }
+    };
+    let missing: Vec<&str> = EXPECTED_TABLES
+        .iter()
+        .filter(|table| !tables.iter().any(|t| t == *table))
+        .copied()
+        .collect();
+    if missing.is_empty() {
+        ok(format!("{:?} has all Hall-of-Fame tables", path));
+    } else {
+        fail(format!("{:?} misses the table(s) {:?}", path, missing));
+        passed = false;
+    }
+    match integrity::orphans(&con) {
+        Ok(orphans) if orphans.is_empty() => ok(format!("{:?} has no rows referencing missing rows", path)),
+        Ok(orphans) => {
+            for orphan in orphans {
+                fail(format!(
+                    "{:?} has {} {} row(s) with a {} of no {}: {}",
+                    path,
+                    orphan.rows,
+                    orphan.table,
+                    orphan.column,
+                    orphan.referenced,
+                    orphan.ids_text()
+                ));
+            }
+            passed = false;
+        }
+        Err(e) => warn(format!("{:?} can't be checked for rows referencing missing rows: {}", path, e)),
+    }
+
+    match con.query_row("SELECT COUNT(*) FROM Migrations", NO_PARAMS, |row| row.get::<_, i64>(0)) {
+        Ok(migrations) => ok(format!("{:?} has schema version {} (applied migrations)", path, migrations)),
+        Err(e) => warn(format!("{:?} has no readable schema version: {}", path, e)),
+    }
+    let known = schema::SCHEMA.name;
+    match schema::differences(&con) {
+        Ok(differences) if differences.is_empty() => ok(format!("{:?} matches the {} schema", path, known)),
+        Ok(differences) => {
+            for difference in differences {
+                warn(format!("{:?} deviates from the {} schema: {}", path, known, difference));
+            }
+        }
+        Err(e) => warn(format!("{:?} can't be compared to the known schema: {}", path, e)),
+    }
+    match con.query_row("PRAGMA journal_mode", NO_PARAMS, |row| row.get::<_, String>(0)) {
+        Ok(mode) if mode.eq_ignore_ascii_case("wal") => warn(format!(
+            "{:?} uses journal mode WAL - make sure the game is closed so all changes are in the main file",
+            path
+        )),
+        Ok(mode) => ok(format!("{:?} uses journal mode {}", path, mode)),
+        Err(e) => warn(format!("{:?} has no readable journal mode: {}", path, e)),
+    }
+    match con.query_row("PRAGMA quick_check", NO_PARAMS, |row| row.get::<_, String>(0)) {
+        Ok(result) if result == "ok" => ok(format!("{:?} passes the SQLite integrity check", path)),
+        Ok(result) => {
+            fail(format!("{:?} fails the SQLite integrity check: {}", path, result));
+            passed = false;
+        }
+        Err(e) => {
+            fail(format!("{:?} can't be checked: {}", path, e));
+            passed = false;
+        }
+    }
+    passed
+}
+
+/// Checks the environment for everything a merge depends on & prints findings.
+pub fn run(extra_paths: &[PathBuf]) {
+    println!("civ6-hof-merge {} on {}", env!("CARGO_PKG_VERSION"), std::env::consts::OS);
+
+    let home = home_dir();
+    if home.is_none() {
+        warn("Neither HOME nor USERPROFILE is set - can't look for the game's default locations".to_string());
+    }
+    let home = home.unwrap_or_default();
+
+    let installs: Vec<PathBuf> = install_dirs(&home).into_iter().filter(|dir| dir.is_dir()).collect();
+    if installs.is_empty() {
+        warn("No game installation found in the default Steam/Epic locations".to_string());
+    }
+    for install in &installs {
+        ok(format!("Game installation found at {:?}", install));
+    }
+
+    let mut databases: Vec<PathBuf> = user_data_dirs(&home)
+        .into_iter()
+        .map(|dir| dir.join("HallofFame.sqlite"))
+        .filter(|path| path.exists())
+        .collect();
+    if databases.is_empty() {
+        warn("No HallofFame.sqlite found in the game's default user data locations".to_string());
+    }
+    databases.extend(extra_paths.iter().cloned());
+
+    for database in &databases {
+        check_database(database);
+        let dir = database.parent().filter(|dir| !dir.as_os_str().is_empty()).unwrap_or_else(|| Path::new("."));
+        match free_space_kib(dir) {
+            Some(free) if free < 100 * 1024 => warn(format!("Only {} KiB free in {:?}", free, dir)),
+            Some(free) => ok(format!("{} MiB free in {:?}", free / 1024, dir)),
+            None => warn(format!("Free disk space in {:?} is unknown", dir)),
+        }
+    }
+}
@@ -0,0 +1,51 @@
+//! Newline-delimited JSON events of every significant merge step, for GUI
+//! wrappers & scripts driving their own progress display.
+
+use std::cell::RefCell;
+use std::io::Write;
+use std::path::Path;
+
+use log::warn;
+use serde_json::{json, Value};
+
+/// Where events go - nowhere by default.
+#[derive(Default)]
+pub struct Events {
+    out: Option<RefCell<Box<dyn Write>>>,
+}
+
+impl Events {
+    /// Streams events to a file, or stdout for `-`.
+    pub fn to(path: &Path) -> std::io::Result<Events> {
+        let out: Box<dyn Write> = if crate::output::is_stdout(Some(path)) {
+            Box::new(std::io::stdout())
+        } else {
+            Box::new(std::fs::File::create(path)?)
+        };
+        Ok(Events {
+            out: Some(RefCell::new(out)),
+        })
+    }
+
+    /// Writes one event line: `{"event": <name>, "time": <unix millis>, ...fields}`.
+    pub fn emit(&self, event: &str, fields: Value) {
+        let out = match &self.out {
+            Some(out) => out,
+            None => return,
+        };
+        let time = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map(|d| d.as_millis() as u64)
+            .unwrap_or_default();
+        let mut line = json!({ "event": event, "time": time });
+        if let (Some(line), Value::Object(fields)) = (line.as_object_mut(), fields) {
+            line.extend(fields);
+        }
+
+        let mut out = out.borrow_mut();
+        // A reader going away must not fail the merge
+        if let Err(e) = writeln!(out, "{}", line).and_then(|_| out.flush()) {
+            warn!("Writing event {} failed: {}", event, e);
+        }
+    }
+}
@@ -0,0 +1,124 @@
+use rusqlite::{Connection, NO_PARAMS};
+use serde_rusqlite::from_rows;
+
+use crate::dedupe::Strategy;
+use crate::{Game, GameId};
+
+/// The Games columns the duplicate check compares, with their values.
+fn key_fields(game: &Game) -> Vec<(&'static str, String)> {
+    vec![
+        ("Ruleset", game.rule_set.clone()),
+        ("GameMode", game.game_mode.to_string()),
+        ("TurnCount", game.turn_count.to_string()),
+        ("GameSpeedType", game.game_speed_type.clone()),
+        ("MapSizeType", game.map_size_type.clone()),
+        ("Map", crate::maps::canonical(&game.map)),
+        ("StartEraType", game.start_era_type.clone()),
+        ("StartTurn", game.start_turn.to_string()),
+        ("VictorTeamId", game.victor_team_id.map_or_else(|| "NULL".to_string(), |id| id.to_string())),
+        ("VictoryType", game.victory_type.clone().unwrap_or_else(|| "NULL".to_string())),
+        ("LastPlayed", game.last_played.to_string()),
+    ]
+}
+
+/// The target game with the most key fields equal to `game`, ignoring `exclude`.
+fn closest(target: &Connection, game: &Game, exclude: Option<GameId>) -> rusqlite::Result<Option<(Game, usize)>> {
+    let fields = key_fields(game);
+    let mut stmt = target.prepare("SELECT * FROM Games")?;
+    let mut best: Option<(Game, usize)> = None;
+    for candidate in from_rows::<Game>(stmt.query(NO_PARAMS)?) {
+        let candidate = match candidate {
+            Ok(candidate) => candidate,
+            Err(_) => continue,
+        };
+        if Some(candidate.game_id) == exclude {
+            continue;
+        }
+        let matching = key_fields(&candidate)
+            .iter()
+            .zip(fields.iter())
+            .filter(|(a, b)| a.1 == b.1)
+            .count();
+        if best.as_ref().is_none_or(|(_, most)| matching > *most) {
+            best = Some((candidate, matching));
+        }
+    }
+    Ok(best)
+}
+
+/// The key fields two games differ in, with both values.
+pub fn differences(a: &Game, b: &Game) -> Vec<String> {
+    key_fields(a)
+        .into_iter()
+        .zip(key_fields(b))
+        .filter(|(a, b)| a.1 != b.1)
+        .map(|(a, b)| format!("{} ({} vs {})", a.0, a.1, b.1))
+        .collect()
+}
+
+/// Explains why a source game was skipped as the same game as the target's
+/// `existing` under a looser strategy than all key fields matching.
+pub fn explain_match(
+    target: &Connection,
+    game: &Game,
+    existing: GameId,
+    strategy: Strategy,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let existing = crate::load_game(target, existing)?;
+    let differences = differences(game, &existing);
+    if differences.is_empty() {
+        println!(
+            "Game {} skipped: Duplicate of target game {} by {} - all {} key fields match",
+            game.game_id,
+            existing.game_id,
+            strategy.name(),
+            key_fields(game).len()
+        );
+    } else {
+        println!(
+            "Game {} skipped: Duplicate of target game {} by {}, although it differs in {}",
+            game.game_id,
+            existing.game_id,
+            strategy.name(),
+            differences.join(", ")
+        );
+    }
+    Ok(())
+}
+
+/// Explains why a source game was skipped as a duplicate.
+pub fn explain_skip(target: &Connection, game: &Game) -> rusqlite::Result<()> {
+    match closest(target, game, None)? {
+        Some((existing, _)) if differences(game, &existing).is_empty() => println!(
+            "Game {} skipped: Duplicate of target game {} - all {} key fields match",
+            game.game_id,
+            existing.game_id,
+            key_fields(game).len()
+        ),
+        Some((existing, _)) => println!(
+            "Game {} skipped: Known from the game cache, closest target game {} differs in {}",
+            game.game_id,
+            existing.game_id,
+            differences(game, &existing).join(", ")
+        ),
+        None => println!("Game {} skipped: Known from the game cache", game.game_id),
+    }
+    Ok(())
+}
+
+/// Explains why a source game was copied, naming the most similar target game.
+pub fn explain_copy(target: &Connection, game: &Game, new_game_id: GameId) -> rusqlite::Result<()> {
+    match closest(target, game, Some(new_game_id))? {
+        Some((existing, matching)) => println!(
+            "Game {} copied as {}: Closest target game {} matches {} of {} key fields, differs in {}",
+            game.game_id,
+            new_game_id,
+            existing.game_id,
+            matching,
+            key_fields(game).len(),
+            differences(game, &existing).join(", ")
+        ),
+        None => println!("Game {} copied as {}: The target had no games", game.game_id, new_game_id),
+    }
+    Ok(())
+}
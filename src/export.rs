@@ -0,0 +1,483 @@
+//! Portable dumps of selected games: SQL applicable to another Hall of Fame
+//! with plain `sqlite3`, JSON for other tools & version control or CSV for
+//! spreadsheets.
+
+use std::fmt::Write as _;
+use std::str::FromStr;
+
+use rusqlite::types::{Value, ValueRef};
+use rusqlite::{params, Connection, NO_PARAMS};
+use serde_json::json;
+
+use crate::copier::{self, Row};
+use crate::table::{self, Table};
+use crate::{dates, schema, GameId, LOCAL_PLAYER_JOIN};
+
+/// Export formats.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Format {
+    /// INSERT statements remapping all ids to the ones the importing database assigns
+    Sql,
+    /// A document of the games, each with its rows nested by table - ids as in the database
+    Json,
+    /// One CSV file per table, ids as in the database
+    Csv,
+    /// A single CSV of the games, one row each with its local player's leader, civilization, score & result
+    GamesCsv,
+}
+
+impl FromStr for Format {
+    type Err = String;
+
+    fn from_str(format: &str) -> Result<Self, Self::Err> {
+        match format {
+            "sql" => Ok(Format::Sql),
+            "json" => Ok(Format::Json),
+            "csv" => Ok(Format::Csv),
+            "games-csv" => Ok(Format::GamesCsv),
+            _ => Err(format!(
+                "Unknown export format {:?}, expected sql, json, csv or games-csv",
+                format
+            )),
+        }
+    }
+}
+
+/// How the rows of a game's table are selected & which of their columns are ids.
+pub struct TableExport {
+    pub name: &'static str,
+    /// Selects the rows of game `?1`
+    pub filter: &'static str,
+    /// Id column the importing database assigns, with the kind of id it is
+    generated: Option<(&'static str, &'static str)>,
+    /// Columns referencing ids of a kind, remapped on import
+    references: &'static [(&'static str, &'static str)],
+    /// References set after all rows of the table exist, as they may point to later rows
+    deferred: &'static [(&'static str, &'static str)],
+}
+
+/// The tables of a game, referenced rows first.
+pub const TABLES: &[TableExport] = &[
+    TableExport {
+        name: "Games",
+        filter: "GameId = ?1",
+        generated: Some(("GameId", "game")),
+        references: &[],
+        deferred: &[],
+    },
+    TableExport {
+        name: "GameObjects",
+        filter: "GameId = ?1",
+        generated: Some(("ObjectId", "object")),
+        references: &[("GameId", "game")],
+        deferred: &[("PlayerObjectId", "object")],
+    },
+    TableExport {
+        name: "GamePlayers",
+        filter: "PlayerObjectId IN (SELECT ObjectId FROM GameObjects WHERE GameId = ?1)",
+        generated: None,
+        references: &[("PlayerObjectId", "object")],
+        deferred: &[],
+    },
+    TableExport {
+        name: "GameDataPointValues",
+        filter: "GameId = ?1",
+        generated: None,
+        references: &[("GameId", "game"), ("ValueObjectId", "object")],
+        deferred: &[],
+    },
+    TableExport {
+        name: "ObjectDataPointValues",
+        filter: "ObjectId IN (SELECT ObjectId FROM GameObjects WHERE GameId = ?1)",
+        generated: None,
+        references: &[("ObjectId", "object"), ("ValueObjectId", "object")],
+        deferred: &[],
+    },
+    TableExport {
+        name: "DataSets",
+        filter: "GameId = ?1",
+        generated: Some(("DataSetId", "dataset")),
+        references: &[("GameId", "game"), ("ObjectId", "object")],
+        deferred: &[],
+    },
+    TableExport {
+        name: "DataSetValues",
+        filter: "DataSetId IN (SELECT DataSetId FROM DataSets WHERE GameId = ?1)",
+        generated: None,
+        references: &[("DataSetId", "dataset")],
+        deferred: &[],
+    },
+];
+
+fn literal(value: ValueRef) -> String {
+    match value {
+        ValueRef::Null => "NULL".to_string(),
+        ValueRef::Integer(integer) => integer.to_string(),
+        ValueRef::Real(real) => format!("{:?}", real),
+        ValueRef::Text(text) => format!("'{}'", String::from_utf8_lossy(text).replace('\'', "''")),
+        ValueRef::Blob(blob) => format!("X'{}'", blob.iter().map(|b| format!("{:02X}", b)).collect::<String>()),
+    }
+}
+
+/// The id a row got in the importing database - NULL for references outside the exported games.
+fn remapped(kind: &str, value: ValueRef) -> String {
+    match value {
+        ValueRef::Null => "NULL".to_string(),
+        value => format!(
+            "(SELECT NewId FROM ExportIds WHERE Kind = '{}' AND OldId = {})",
+            kind,
+            literal(value)
+        ),
+    }
+}
+
+/// Dumps the games with all their rows as INSERT statements. The ids are
+/// assigned by the importing database & references remapped through a
+/// temporary `ExportIds` table, so the dump applies to any Hall of Fame.
+pub fn sql(con: &Connection, game_ids: &[GameId]) -> Result<String, Box<dyn std::error::Error>> {
+    let mut sql = String::new();
+    let _ = writeln!(
+        sql,
+        "-- civ6-hof-merge {} export of game(s) {}",
+        env!("CARGO_PKG_VERSION"),
+        game_ids.iter().map(|id| id.to_string()).collect::<Vec<_>>().join(", ")
+    );
+    sql.push_str("BEGIN TRANSACTION;\n");
+    sql.push_str("CREATE TEMP TABLE ExportIds (Kind TEXT NOT NULL, OldId INTEGER NOT NULL, NewId INTEGER NOT NULL, PRIMARY KEY (Kind, OldId));\n");
+
+    for game_id in game_ids {
+        let exists: bool = con.query_row("SELECT COUNT(*) > 0 FROM Games WHERE GameId = ?", params![game_id], |row| row.get(0))?;
+        if !exists {
+            return Err(format!("Game {} not found", game_id).into());
+        }
+        let _ = writeln!(sql, "\n-- Game {}", game_id);
+        for table in TABLES {
+            let columns = schema::columns(con, table.name)?;
+            let mut stmt = con.prepare(&format!(
+                "SELECT {} FROM {} WHERE {}",
+                columns.join(", "),
+                table.name,
+                table.filter
+            ))?;
+            let mut updates = Vec::new();
+            let mut rows = stmt.query(params![game_id])?;
+            while let Some(row) = rows.next()? {
+                let mut names = Vec::new();
+                let mut values = Vec::new();
+                let mut old_id = None;
+                let mut deferred = Vec::new();
+                for (index, column) in columns.iter().enumerate() {
+                    let value = row.get_raw(index);
+                    if let Some((_, kind)) = table.generated.filter(|(generated, _)| generated == column) {
+                        old_id = Some((kind, literal(value)));
+                        continue;
+                    }
+                    if let Some((_, kind)) = table.deferred.iter().find(|(deferred, _)| deferred == column) {
+                        if value != ValueRef::Null {
+                            deferred.push((column.clone(), remapped(kind, value)));
+                        }
+                        continue;
+                    }
+                    names.push(column.clone());
+                    values.push(match table.references.iter().find(|(reference, _)| reference == column) {
+                        Some((_, kind)) => remapped(kind, value),
+                        None => literal(value),
+                    });
+                }
+                let _ = writeln!(
+                    sql,
+                    "INSERT INTO {} ({}) VALUES ({});",
+                    table.name,
+                    names.join(", "),
+                    values.join(", ")
+                );
+                if let Some((kind, old_id)) = old_id {
+                    let _ = writeln!(
+                        sql,
+                        "INSERT INTO ExportIds (Kind, OldId, NewId) VALUES ('{}', {}, last_insert_rowid());",
+                        kind, old_id
+                    );
+                    for (column, value) in deferred {
+                        let (generated, _) = table.generated.unwrap_or_default();
+                        updates.push(format!(
+                            "UPDATE {} SET {} = {} WHERE {} = (SELECT NewId FROM ExportIds WHERE Kind = '{}' AND OldId = {});",
+                            table.name, column, value, generated, kind, old_id
+                        ));
+                    }
+                }
+            }
+            for update in updates {
+                let _ = writeln!(sql, "{}", update);
+            }
+        }
+    }
+
+    sql.push_str("\nDROP TABLE ExportIds;\nCOMMIT;\n");
+    Ok(sql)
+}
+
+/// The GameIds given, all games of the database if none are.
+pub fn selected(con: &Connection, game_ids: &[GameId]) -> rusqlite::Result<Vec<GameId>> {
+    if !game_ids.is_empty() {
+        return Ok(game_ids.to_vec());
+    }
+    let mut stmt = con.prepare("SELECT GameId FROM Games ORDER BY GameId")?;
+    let all = stmt.query_map(NO_PARAMS, |row| row.get(0))?.collect();
+    all
+}
+
+/// A column value as JSON; blobs become `{"hex": "..."}`.
+pub fn json_value(value: &Value) -> serde_json::Value {
+    match value {
+        Value::Null => serde_json::Value::Null,
+        Value::Integer(integer) => json!(integer),
+        Value::Real(real) => json!(real),
+        Value::Text(text) => json!(text),
+        Value::Blob(blob) => json!({ "hex": blob.iter().map(|b| format!("{:02X}", b)).collect::<String>() }),
+    }
+}
+
+fn json_row(row: &Row) -> serde_json::Map<String, serde_json::Value> {
+    row.values().map(|(column, value)| (column.to_string(), json_value(value))).collect()
+}
+
+fn json_rows(rows: &[Row]) -> serde_json::Value {
+    serde_json::Value::Array(rows.iter().map(|row| serde_json::Value::Object(json_row(row))).collect())
+}
+
+/// The games as one JSON document: Each game's `Games` row under `Game`,
+/// its rows of the other tables in arrays named after them - the values of
+/// each data set nested in it - & the `RulesetDataPointValues` of their
+/// rulesets. Every column is included, ids unchanged.
+pub fn json(con: &Connection, game_ids: &[GameId]) -> Result<serde_json::Value, Box<dyn std::error::Error>> {
+    let mut games = Vec::new();
+    let mut rulesets: Vec<String> = Vec::new();
+    for game_id in game_ids {
+        let mut tables = TABLES.iter().map(|table| {
+            copier::read(con, table.name, &format!("WHERE {}", table.filter), params![game_id])
+        });
+        let game_rows = tables.next().expect("Games is the first table")?;
+        let game_row = game_rows.first().ok_or_else(|| format!("Game {} not found", game_id))?;
+        if let Some(ruleset) = game_row.text("Ruleset") {
+            if !rulesets.iter().any(|known| known == ruleset) {
+                rulesets.push(ruleset.to_string());
+            }
+        }
+        let mut game = serde_json::Map::new();
+        game.insert("Game".to_string(), serde_json::Value::Object(json_row(game_row)));
+        for (table, rows) in TABLES[1..].iter().zip(tables) {
+            let rows = rows?;
+            let value = match table.name {
+                // Graph data nested in its data sets
+                "DataSetValues" => continue,
+                "DataSets" => {
+                    let mut data_sets = Vec::new();
+                    for data_set in &rows {
+                        let mut object = json_row(data_set);
+                        let values = copier::read(
+                            con,
+                            "DataSetValues",
+                            "WHERE DataSetId = ?1 ORDER BY X",
+                            params![data_set.key("DataSetId")?],
+                        )?;
+                        object.insert("DataSetValues".to_string(), json_rows(&values));
+                        data_sets.push(serde_json::Value::Object(object));
+                    }
+                    serde_json::Value::Array(data_sets)
+                }
+                _ => json_rows(&rows),
+            };
+            game.insert(table.name.to_string(), value);
+        }
+        games.push(serde_json::Value::Object(game));
+    }
+    let mut ruleset_rows = Vec::new();
+    for ruleset in &rulesets {
+        ruleset_rows.extend(copier::read(con, "RulesetDataPointValues", "WHERE Ruleset = ?1", params![ruleset])?);
+    }
+    Ok(json!({
+        "exporter": format!("civ6-hof-merge {}", env!("CARGO_PKG_VERSION")),
+        "games": games,
+        "RulesetDataPointValues": json_rows(&ruleset_rows),
+    }))
+}
+
+/// A column value as a CSV cell: NULL empty, blobs in hex.
+fn cell(value: &Value) -> String {
+    match value {
+        Value::Null => String::new(),
+        Value::Integer(integer) => integer.to_string(),
+        Value::Real(real) => real.to_string(),
+        Value::Text(text) => text.clone(),
+        Value::Blob(blob) => blob.iter().map(|b| format!("{:02X}", b)).collect(),
+    }
+}
+
+/// Writes the games' rows of each table to `<table>.csv` in `dir`, returning the files with their rows.
+pub fn csv_tables(
+    con: &Connection,
+    game_ids: &[GameId],
+    dir: &std::path::Path,
+) -> Result<Vec<(std::path::PathBuf, usize)>, Box<dyn std::error::Error>> {
+    std::fs::create_dir_all(dir)?;
+    let mut written = Vec::new();
+    for table in TABLES {
+        let columns = schema::columns(con, table.name)?;
+        let mut csv = Table::new(&columns.iter().map(String::as_str).collect::<Vec<_>>());
+        let mut rows = 0;
+        for game_id in game_ids {
+            for row in copier::read(con, table.name, &format!("WHERE {}", table.filter), params![game_id])? {
+                csv.push(row.values().map(|(_, value)| cell(value)).collect());
+                rows += 1;
+            }
+        }
+        let path = dir.join(format!("{}.csv", table.name));
+        std::fs::write(&path, csv.render(table::Format::Csv))?;
+        written.push((path, rows));
+    }
+    Ok(written)
+}
+
+/// The games joined with their local player, one row each - for a spreadsheet of the games played.
+pub fn games_csv(con: &Connection, game_ids: &[GameId]) -> rusqlite::Result<String> {
+    let mut stmt = con.prepare(&format!(
+        "SELECT g.*, lp.LeaderType, lp.LeaderName, lp.CivilizationType, lp.DifficultyType, lp.Score, \
+         COALESCE(g.VictorTeamId = lp.TeamId, 0) AS Won FROM Games g {} WHERE g.GameId = ?",
+        LOCAL_PLAYER_JOIN
+    ))?;
+    let mut headers: Vec<String> = stmt.column_names().into_iter().map(String::from).collect();
+    headers.push("LastPlayedDate".to_string());
+    let last_played = headers.iter().position(|header| header == "LastPlayed");
+    let mut csv = Table::new(&headers.iter().map(String::as_str).collect::<Vec<_>>());
+    for game_id in game_ids {
+        let mut rows = stmt.query(params![game_id])?;
+        while let Some(row) = rows.next()? {
+            let values = (0..headers.len() - 1)
+                .map(|index| row.get::<_, Value>(index))
+                .collect::<rusqlite::Result<Vec<Value>>>()?;
+            let date = match last_played.map(|index| &values[index]) {
+                Some(Value::Integer(timestamp)) => dates::format_date(*timestamp),
+                _ => String::new(),
+            };
+            let mut cells: Vec<String> = values.iter().map(cell).collect();
+            cells.push(date);
+            csv.push(cells);
+        }
+    }
+    Ok(csv.render(table::Format::Csv))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A game whose rows reference each other by id: The player objects 1 & 3,
+    /// the latter referenced by object 2 before it exists, their players, data
+    /// points & two data sets.
+    fn add_game(con: &Connection, game_id: GameId, prefix: &str) {
+        con.execute(
+            "INSERT INTO Games (GameId, Ruleset, GameMode, TurnCount, GameSpeedType, MapSizeType, Map, StartEraType, \
+             StartTurn, LastPlayed) VALUES (?, 'RULESET_STANDARD', 0, 100, 'GAMESPEED_STANDARD', 'MAPSIZE_SMALL', \
+             ?, 'ERA_ANCIENT', 1, 1000)",
+            params![game_id, format!("{}.lua", prefix)],
+        )
+        .unwrap();
+        for (object, owner, name) in &[(1, 1, "Player"), (2, 3, "Capital"), (3, 3, "Rival")] {
+            con.execute(
+                "INSERT INTO GameObjects (ObjectId, GameId, PlayerObjectId, Type, Name) VALUES (?, ?, ?, 'OBJECT', ?)",
+                params![object, game_id, owner, format!("{} {}", prefix, name)],
+            )
+            .unwrap();
+        }
+        for (object, leader) in &[(1, "LEADER_GANDHI"), (3, "LEADER_GILGAMESH")] {
+            con.execute(
+                "INSERT INTO GamePlayers (PlayerObjectId, IsLocal, IsAI, IsMajor, LeaderType, Score, PlayerId, TeamId) \
+                 VALUES (?1, ?1 = ?3, ?1 != ?3, 1, ?2, ?1, ?1, ?1)",
+                params![object, leader, 1],
+            )
+            .unwrap();
+        }
+        con.execute(
+            "INSERT INTO GameDataPointValues (DataPoint, GameId, ValueObjectId, ValueType) VALUES ('WINNER', ?, ?, 'Object')",
+            params![game_id, 3],
+        )
+        .unwrap();
+        con.execute(
+            "INSERT INTO ObjectDataPointValues (ObjectId, DataPoint, ValueObjectId, ValueNumeric) VALUES (?, 'FOUNDER', ?, 7)",
+            params![2, 1],
+        )
+        .unwrap();
+        for (data_set, object, name) in &[(1, Some(1), "SCORE"), (2, None, "TURNS")] {
+            con.execute(
+                "INSERT INTO DataSets (DataSetId, GameId, ObjectId, DataSet) VALUES (?, ?, ?, ?)",
+                params![data_set, game_id, object, name],
+            )
+            .unwrap();
+            for x in 1..=2 {
+                con.execute(
+                    "INSERT INTO DataSetValues (DataSetId, X, Y) VALUES (?, ?, ?)",
+                    params![data_set, x, x as f64 * 10.5],
+                )
+                .unwrap();
+            }
+        }
+    }
+
+    /// The rows of a game with the objects they reference by name instead of id.
+    fn rows(con: &Connection, game_id: GameId) -> Vec<String> {
+        let queries = [
+            "SELECT Ruleset, Map, LastPlayed FROM Games WHERE GameId = ?",
+            "SELECT o.Type, o.Name, p.Name FROM GameObjects o LEFT JOIN GameObjects p ON p.ObjectId = o.PlayerObjectId \
+             WHERE o.GameId = ?",
+            "SELECT o.Name, gp.IsLocal, gp.LeaderType, gp.Score, gp.PlayerId FROM GamePlayers gp \
+             JOIN GameObjects o ON o.ObjectId = gp.PlayerObjectId WHERE o.GameId = ?",
+            "SELECT d.DataPoint, v.Name, d.ValueType FROM GameDataPointValues d \
+             LEFT JOIN GameObjects v ON v.ObjectId = d.ValueObjectId WHERE d.GameId = ?",
+            "SELECT o.Name, d.DataPoint, v.Name, d.ValueNumeric FROM ObjectDataPointValues d \
+             JOIN GameObjects o ON o.ObjectId = d.ObjectId LEFT JOIN GameObjects v ON v.ObjectId = d.ValueObjectId \
+             WHERE o.GameId = ?",
+            "SELECT ds.DataSet, o.Name, dv.X, dv.Y FROM DataSets ds LEFT JOIN GameObjects o ON o.ObjectId = ds.ObjectId \
+             JOIN DataSetValues dv ON dv.DataSetId = ds.DataSetId WHERE ds.GameId = ?",
+        ];
+        let mut found = Vec::new();
+        for (table, query) in queries.iter().enumerate() {
+            let mut stmt = con.prepare(query).unwrap();
+            let mut rows = stmt.query(params![game_id]).unwrap();
+            while let Some(row) = rows.next().unwrap() {
+                let values: Vec<String> = (0..row.column_count()).map(|i| literal(row.get_raw(i))).collect();
+                found.push(format!("{}: {}", table, values.join(", ")));
+            }
+        }
+        found.sort();
+        found
+    }
+
+    #[test]
+    fn sql_applies_to_a_database_using_the_same_ids() {
+        let source = Connection::open_in_memory().unwrap();
+        schema::create(&source).unwrap();
+        add_game(&source, 1, "Source");
+        let target = Connection::open_in_memory().unwrap();
+        schema::create(&target).unwrap();
+        add_game(&target, 1, "Target");
+        let before = rows(&target, 1);
+
+        target.execute_batch(&sql(&source, &[1]).unwrap()).unwrap();
+
+        assert_eq!(rows(&target, 1), before);
+        assert_eq!(rows(&target, 2), rows(&source, 1));
+        assert_eq!(rows(&target, 2).len(), 12);
+        let object_ids: Vec<i64> = target
+            .prepare("SELECT ObjectId FROM GameObjects WHERE GameId = 2 ORDER BY ObjectId")
+            .unwrap()
+            .query_map(NO_PARAMS, |row| row.get(0))
+            .unwrap()
+            .collect::<rusqlite::Result<_>>()
+            .unwrap();
+        assert_eq!(object_ids, vec![4, 5, 6]);
+        let temporary: i64 = target
+            .query_row("SELECT COUNT(*) FROM sqlite_temp_master WHERE name = 'ExportIds'", NO_PARAMS, |row| row.get(0))
+            .unwrap();
+        assert_eq!(temporary, 0);
+    }
+}
@@ -0,0 +1,167 @@
+//! Tables mods add to the Hall of Fame - e.g. Better Report Screen's - copied
+//! with `--copy-extra-tables`: Rows with a GameId or an object column are
+//! copied along with the game or object they belong to, under its new id;
+//! rows of other tables once per source, unless the target has them already.
+
+use log::info;
+use rusqlite::{params, Connection, NO_PARAMS};
+
+use crate::copier::{Copier, Row};
+use crate::ids::{IdMap, Table};
+use crate::{schema, GameId, EXPECTED_TABLES};
+
+/// Columns referencing rows of the Hall-of-Fame tables, rewritten to the ids
+/// the referenced rows got in the target.
+const REFERENCES: [(&str, Table); 5] = [
+    ("GameId", Table::Games),
+    ("ObjectId", Table::GameObjects),
+    ("PlayerObjectId", Table::GameObjects),
+    ("ValueObjectId", Table::GameObjects),
+    ("DataSetId", Table::DataSets),
+];
+
+/// Which rows of an extra table belong to a game.
+enum Scope {
+    /// Rows with the game's GameId
+    Game,
+    /// Rows referencing one of the game's objects in this column
+    Object(&'static str),
+    /// None - the rows are independent of games
+    Global,
+}
+
+/// A table of a mod in the source.
+pub struct ExtraTable {
+    name: String,
+    /// Its INTEGER PRIMARY KEY, assigned anew by the target
+    key: Option<String>,
+    scope: Scope,
+}
+
+/// Whether a table is one of the game's or the merge's own.
+fn is_known(table: &str) -> bool {
+    EXPECTED_TABLES.contains(&table) || table.starts_with("Merge") || table.starts_with("sqlite_")
+}
+
+fn scope(columns: &[String]) -> Scope {
+    if columns.iter().any(|column| column == "GameId") {
+        return Scope::Game;
+    }
+    match ["ObjectId", "PlayerObjectId"]
+        .iter()
+        .find(|reference| columns.iter().any(|column| column == *reference))
+    {
+        Some(reference) => Scope::Object(reference),
+        None => Scope::Global,
+    }
+}
+
+/// The INTEGER PRIMARY KEY of a table - an alias of its rowid - if it has one.
+fn rowid_alias(con: &Connection, table: &str) -> rusqlite::Result<Option<String>> {
+    let mut stmt = con.prepare(&format!("PRAGMA table_info(\"{}\")", table))?;
+    let keys = stmt
+        .query_map(NO_PARAMS, |row| {
+            Ok((row.get::<_, String>(1)?, row.get::<_, String>(2)?, row.get::<_, i64>(5)?))
+        })?
+        .collect::<rusqlite::Result<Vec<_>>>()?
+        .into_iter()
+        .filter(|(_, _, pk)| *pk > 0)
+        .collect::<Vec<_>>();
+    Ok(match keys.as_slice() {
+        [(column, kind, _)] if kind.eq_ignore_ascii_case("INTEGER") => Some(column.clone()),
+        _ => None,
+    })
+}
+
+/// The source's tables beyond the Hall-of-Fame & merge tables, created in the
+/// target if it lacks them.
+pub fn discover(source: &Connection, target: &Connection) -> rusqlite::Result<Vec<ExtraTable>> {
+    let mut stmt = source.prepare("SELECT name, sql FROM sqlite_master WHERE type = 'table' ORDER BY name")?;
+    let tables = stmt
+        .query_map(NO_PARAMS, |row| Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?)))?
+        .collect::<rusqlite::Result<Vec<_>>>()?;
+
+    let mut extra = Vec::new();
+    for (name, sql) in tables {
+        if is_known(&name) {
+            continue;
+        }
+        if schema::columns(target, &name)?.is_empty() {
+            target.execute(&sql, NO_PARAMS)?;
+            info!("Created the table {} in the target", &name);
+        }
+        let scope = scope(&schema::columns(source, &name)?);
+        // A key that references another table's row is kept, not assigned anew
+        let key = rowid_alias(source, &name)?.filter(|key| !REFERENCES.iter().any(|(column, _)| column == key));
+        extra.push(ExtraTable { name, key, scope });
+    }
+    Ok(extra)
+}
+
+fn remap(row: &mut Row, ids: &IdMap) {
+    for (column, table) in REFERENCES.iter() {
+        if let Some(id) = row.id(column) {
+            row.set(column, ids.get(*table, id));
+        }
+    }
+}
+
+/// Copies the rows belonging to a copied game or its objects, returning how many.
+pub fn copy_game(
+    source: &Connection,
+    target: &Connection,
+    tables: &[ExtraTable],
+    game_id: GameId,
+    ids: &IdMap,
+) -> rusqlite::Result<i64> {
+    let mut copied = 0;
+    for table in tables {
+        let (filter, owners) = match table.scope {
+            Scope::Game => ("WHERE t.GameId = ?".to_string(), vec![game_id]),
+            Scope::Object(column) => (format!("WHERE t.\"{}\" = ?", column), ids.copied(Table::GameObjects)),
+            Scope::Global => continue,
+        };
+        let mut copier = Copier::new(source, target, &table.name, table.key.as_deref(), &filter)?;
+        for owner in owners {
+            for mut row in copier.rows(params![owner])? {
+                remap(&mut row, ids);
+                copier.insert(&row)?;
+                copied += 1;
+            }
+        }
+    }
+    Ok(copied)
+}
+
+/// Copies the rows of tables independent of games the target doesn't have,
+/// returning how many.
+pub fn copy_global(source: &Connection, target: &Connection, tables: &[ExtraTable]) -> rusqlite::Result<i64> {
+    let mut copied = 0;
+    for table in tables.iter().filter(|table| matches!(table.scope, Scope::Global)) {
+        let mut copier = Copier::new(source, target, &table.name, table.key.as_deref(), "")?;
+        for row in copier.rows(NO_PARAMS)? {
+            if copier.insert_new(&row)? {
+                copied += 1;
+            }
+        }
+    }
+    Ok(copied)
+}
+
+/// Removes the rows of extra tables belonging to a game or its objects -
+/// before the game's objects are deleted.
+pub fn delete_game(con: &Connection, game_id: GameId) -> rusqlite::Result<()> {
+    let mut stmt = con.prepare("SELECT name FROM sqlite_master WHERE type = 'table'")?;
+    let tables = stmt
+        .query_map(NO_PARAMS, |row| row.get::<_, String>(0))?
+        .collect::<rusqlite::Result<Vec<_>>>()?;
+    for table in tables.iter().filter(|table| !is_known(table)) {
+        let filter = match scope(&schema::columns(con, table)?) {
+            Scope::Game => "GameId = ?".to_string(),
+            Scope::Object(column) => format!("\"{}\" IN (SELECT ObjectId FROM GameObjects WHERE GameId = ?)", column),
+            Scope::Global => continue,
+        };
+        con.execute(&format!("DELETE FROM \"{}\" WHERE {}", table, filter), params![game_id])?;
+    }
+    Ok(())
+}
@@ -0,0 +1,107 @@
+//! Filters restricting the games merged from the sources: `--victory`,
+//! `--leader`, `--civ`, `--map`, `--map-size`, `--speed`, `--completed-only`
+//! & the GameIds of `--only-games` & `--exclude-games`. Each may be given
+//! several times for any of its values; different filters must all match.
+//! `--victory none` matches games without a victory.
+
+use std::collections::HashSet;
+
+use rusqlite::Connection;
+
+use crate::{maps, GameId, LOCAL_PLAYER_JOIN};
+
+#[derive(Debug, Default)]
+pub struct Filters {
+    pub victories: Vec<String>,
+    /// Of the local player
+    pub leaders: Vec<String>,
+    /// Of the local player
+    pub civilizations: Vec<String>,
+    pub maps: Vec<String>,
+    pub map_sizes: Vec<String>,
+    pub speeds: Vec<String>,
+    /// Leaves out abandoned & unfinished games - without victory
+    pub completed_only: bool,
+    pub only_games: Vec<GameId>,
+    pub exclude_games: Vec<GameId>,
+}
+
+/// GameIds as a list for `IN` - numbers, safe to inline.
+fn id_list(game_ids: &[GameId]) -> String {
+    game_ids.iter().map(|game_id| game_id.to_string()).collect::<Vec<_>>().join(", ")
+}
+
+/// A type key with its prefix, e.g. `VICTORY_SCIENCE` for `science`.
+pub fn key(prefix: &str, value: &str) -> String {
+    let value = value.to_uppercase();
+    if value.starts_with(prefix) {
+        value
+    } else {
+        format!("{}{}", prefix, value)
+    }
+}
+
+impl Filters {
+    pub fn is_empty(&self) -> bool {
+        self.victories.is_empty()
+            && self.leaders.is_empty()
+            && self.civilizations.is_empty()
+            && self.maps.is_empty()
+            && self.map_sizes.is_empty()
+            && self.speeds.is_empty()
+            && !self.completed_only
+            && self.only_games.is_empty()
+            && self.exclude_games.is_empty()
+    }
+
+    /// The games of a database passing all filters.
+    pub fn matching(&self, con: &Connection) -> rusqlite::Result<HashSet<GameId>> {
+        let mut conditions = vec!["1".to_string()];
+        let mut values: Vec<String> = Vec::new();
+        if !self.only_games.is_empty() {
+            conditions.push(format!("g.GameId IN ({})", id_list(&self.only_games)));
+        }
+        if !self.exclude_games.is_empty() {
+            conditions.push(format!("g.GameId NOT IN ({})", id_list(&self.exclude_games)));
+        }
+        if self.completed_only {
+            conditions.push("g.VictoryType IS NOT NULL AND g.VictorTeamId IS NOT NULL".to_string());
+        }
+        if !self.victories.is_empty() {
+            let (none, victories): (Vec<&String>, Vec<&String>) =
+                self.victories.iter().partition(|victory| victory.eq_ignore_ascii_case("none"));
+            let null = if none.is_empty() { "" } else { "g.VictoryType IS NULL OR " };
+            conditions.push(format!(
+                "({}g.VictoryType IN ({}))",
+                null,
+                vec!["?"; victories.len()].join(", ")
+            ));
+            values.extend(victories.iter().map(|value| key("VICTORY_", value)));
+        }
+        for (column, prefix, filter) in &[
+            ("lp.LeaderType", "LEADER_", &self.leaders),
+            ("lp.CivilizationType", "CIVILIZATION_", &self.civilizations),
+            ("g.MapSizeType", "MAPSIZE_", &self.map_sizes),
+            ("g.GameSpeedType", "GAMESPEED_", &self.speeds),
+        ] {
+            if !filter.is_empty() {
+                conditions.push(format!("{} IN ({})", column, vec!["?"; filter.len()].join(", ")));
+                values.extend(filter.iter().map(|value| key(prefix, value)));
+            }
+        }
+        let mut stmt = con.prepare(&format!(
+            "SELECT g.GameId, g.Map FROM Games g {} WHERE {}",
+            LOCAL_PLAYER_JOIN,
+            conditions.join(" AND ")
+        ))?;
+        let maps: Vec<String> = self.maps.iter().map(|map| maps::canonical(map)).collect();
+        let mut games = HashSet::new();
+        let mut rows = stmt.query(&values)?;
+        while let Some(row) = rows.next()? {
+            if maps.is_empty() || maps.contains(&maps::canonical(&row.get::<_, String>(1)?)) {
+                games.insert(row.get(0)?);
+            }
+        }
+        Ok(games)
+    }
+}
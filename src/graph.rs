@@ -0,0 +1,210 @@
+use std::fmt::Write as _;
+use std::io::Write as _;
+use std::path::Path;
+
+use rusqlite::{params, Connection};
+
+use crate::report::PALETTE;
+use crate::GameId;
+
+const BLOCKS: [char; 8] = ['▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+const ASCII: [char; 8] = ['_', '.', '-', ':', '=', '+', '*', '#'];
+/// Turns are averaged into at most this many characters.
+const WIDTH: usize = 60;
+/// Size of an SVG chart, the plot area leaving room for the title, axis labels & legend.
+const SVG_WIDTH: f64 = 800.0;
+const SVG_HEIGHT: f64 = 400.0;
+const PLOT_LEFT: f64 = 60.0;
+const PLOT_RIGHT: f64 = 640.0;
+const PLOT_TOP: f64 = 40.0;
+const PLOT_BOTTOM: f64 = 360.0;
+/// Labeled values on each axis.
+const TICKS: usize = 5;
+
+/// Per-turn values of one player's data set.
+pub struct Series {
+    pub label: String,
+    pub points: Vec<(f64, f64)>,
+}
+
+/// The data sets named `stat` (case-insensitive) of a game, one per player.
+pub fn series(con: &Connection, game_id: GameId, stat: &str) -> rusqlite::Result<Vec<Series>> {
+    let mut sets = con.prepare(
+        "SELECT ds.DataSetId, COALESCE(gp.LeaderName, go.Name, ds.DataSet) FROM DataSets ds \
+         LEFT JOIN GamePlayers gp ON gp.PlayerObjectId = ds.ObjectId \
+         LEFT JOIN GameObjects go ON go.ObjectId = ds.ObjectId \
+         WHERE ds.GameId = ? AND ds.DataSet = ? COLLATE NOCASE ORDER BY ds.DataSetId",
+    )?;
+    let mut values = con.prepare("SELECT X, Y FROM DataSetValues WHERE DataSetId = ? ORDER BY X")?;
+
+    let sets = sets
+        .query_map(params![game_id, stat], |row| Ok((row.get::<_, i64>(0)?, row.get::<_, String>(1)?)))?
+        .collect::<rusqlite::Result<Vec<_>>>()?;
+    let mut series = Vec::new();
+    for (data_set_id, label) in sets {
+        let points = values
+            .query_map(params![data_set_id], |row| Ok((row.get(0)?, row.get(1)?)))?
+            .collect::<rusqlite::Result<Vec<(f64, f64)>>>()?;
+        series.push(Series { label, points });
+    }
+    Ok(series)
+}
+
+/// Names of the data sets a game has.
+pub fn stats(con: &Connection, game_id: GameId) -> rusqlite::Result<Vec<String>> {
+    let mut stmt = con.prepare("SELECT DISTINCT DataSet FROM DataSets WHERE GameId = ? ORDER BY 1")?;
+    let stats = stmt.query_map(params![game_id], |row| row.get(0))?;
+    stats.collect()
+}
+
+/// One sparkline per series, all on the same scale, followed by the last value.
+pub fn render_sparklines(series: &[Series], ascii: bool) -> String {
+    let levels = if ascii { &ASCII } else { &BLOCKS };
+    let values = series.iter().flat_map(|s| s.points.iter().map(|(_, y)| *y));
+    let min = values.clone().fold(f64::INFINITY, f64::min);
+    let max = values.fold(f64::NEG_INFINITY, f64::max);
+    let label_width = series.iter().map(|s| s.label.chars().count()).max().unwrap_or(0);
+
+    let mut text = String::new();
+    for s in series {
+        let ys: Vec<f64> = s.points.iter().map(|(_, y)| *y).collect();
+        let columns = ys.len().min(WIDTH);
+        let line: String = (0..columns)
+            .map(|column| {
+                let start = column * ys.len() / columns;
+                let end = ((column + 1) * ys.len() / columns).max(start + 1);
+                let y = ys[start..end].iter().sum::<f64>() / (end - start) as f64;
+                let level = if max > min { (y - min) / (max - min) * (levels.len() - 1) as f64 } else { 0.0 };
+                levels[level.round() as usize]
+            })
+            .collect();
+        let last = ys.last().map_or_else(String::new, |y| format!("{}", y));
+        text.push_str(&format!("{:width$}  {}  {}\n", s.label, line, last, width = label_width));
+    }
+    text
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;")
+}
+
+/// A standalone SVG line chart of the series - one colored line per player with a legend - titled `title`.
+pub fn render_svg(series: &[Series], title: &str) -> String {
+    let points = series.iter().flat_map(|s| s.points.iter());
+    let (min_x, max_x, min_y, max_y) = points.fold(
+        (f64::INFINITY, f64::NEG_INFINITY, 0.0f64, f64::NEG_INFINITY),
+        |(min_x, max_x, min_y, max_y), &(x, y)| (min_x.min(x), max_x.max(x), min_y.min(y), max_y.max(y)),
+    );
+    let (min_x, max_x) = if min_x < max_x { (min_x, max_x) } else { (0.0, min_x.max(1.0)) };
+    let max_y = if max_y > min_y { max_y } else { min_y + 1.0 };
+    let scale = |(x, y): (f64, f64)| {
+        (
+            PLOT_LEFT + (PLOT_RIGHT - PLOT_LEFT) * (x - min_x) / (max_x - min_x),
+            PLOT_BOTTOM - (PLOT_BOTTOM - PLOT_TOP) * (y - min_y) / (max_y - min_y),
+        )
+    };
+
+    let mut svg = format!(
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" viewBox=\"0 0 {0} {1}\" width=\"{0}\" height=\"{1}\" \
+         font-family=\"sans-serif\" font-size=\"12\">\n<rect width=\"100%\" height=\"100%\" fill=\"white\"/>\n",
+        SVG_WIDTH, SVG_HEIGHT
+    );
+    let _ = writeln!(
+        svg,
+        "<text x=\"{}\" y=\"24\" font-size=\"16\" text-anchor=\"middle\">{}</text>",
+        (PLOT_LEFT + PLOT_RIGHT) / 2.0,
+        escape(title)
+    );
+    for tick in 0..=TICKS {
+        let fraction = tick as f64 / TICKS as f64;
+        let (x, _) = scale((min_x + fraction * (max_x - min_x), min_y));
+        let (_, y) = scale((min_x, min_y + fraction * (max_y - min_y)));
+        let _ = writeln!(
+            svg,
+            "<line x1=\"{0:.1}\" y1=\"{1}\" x2=\"{0:.1}\" y2=\"{2}\" stroke=\"#eee\"/>\
+             <text x=\"{0:.1}\" y=\"{3}\" text-anchor=\"middle\">{4:.0}</text>",
+            x,
+            PLOT_TOP,
+            PLOT_BOTTOM,
+            PLOT_BOTTOM + 18.0,
+            min_x + fraction * (max_x - min_x)
+        );
+        let _ = writeln!(
+            svg,
+            "<line x1=\"{0}\" y1=\"{2:.1}\" x2=\"{1}\" y2=\"{2:.1}\" stroke=\"#eee\"/>\
+             <text x=\"{3}\" y=\"{4:.1}\" text-anchor=\"end\">{5:.0}</text>",
+            PLOT_LEFT,
+            PLOT_RIGHT,
+            y,
+            PLOT_LEFT - 6.0,
+            y + 4.0,
+            min_y + fraction * (max_y - min_y)
+        );
+    }
+    let _ = writeln!(
+        svg,
+        "<polyline fill=\"none\" stroke=\"#888\" points=\"{0},{1} {0},{2} {3},{2}\"/>\
+         <text x=\"{4}\" y=\"{5}\" text-anchor=\"middle\">Turn</text>",
+        PLOT_LEFT,
+        PLOT_TOP,
+        PLOT_BOTTOM,
+        PLOT_RIGHT,
+        (PLOT_LEFT + PLOT_RIGHT) / 2.0,
+        SVG_HEIGHT - 6.0
+    );
+    for (index, s) in series.iter().enumerate() {
+        let color = PALETTE[index % PALETTE.len()];
+        let path: Vec<String> = s
+            .points
+            .iter()
+            .map(|&point| {
+                let (x, y) = scale(point);
+                format!("{:.1},{:.1}", x, y)
+            })
+            .collect();
+        let _ = writeln!(
+            svg,
+            "<polyline fill=\"none\" stroke=\"{}\" stroke-width=\"2\" points=\"{}\"/>",
+            color,
+            path.join(" ")
+        );
+        let y = PLOT_TOP + 20.0 * index as f64;
+        let _ = writeln!(
+            svg,
+            "<rect x=\"{}\" y=\"{}\" width=\"12\" height=\"12\" fill=\"{}\"/><text x=\"{}\" y=\"{}\">{}</text>",
+            PLOT_RIGHT + 20.0,
+            y,
+            color,
+            PLOT_RIGHT + 38.0,
+            y + 11.0,
+            escape(&s.label)
+        );
+    }
+    svg.push_str("</svg>\n");
+    svg
+}
+
+/// Writes the chart as SVG or - by the extension of `path` - as PNG, converted with `rsvg-convert`.
+pub fn write_chart(path: &Path, svg: &str) -> Result<(), Box<dyn std::error::Error>> {
+    let extension = path.extension().map(|ext| ext.to_string_lossy().to_lowercase());
+    match extension.as_deref() {
+        Some("svg") => Ok(std::fs::write(path, svg)?),
+        Some("png") => {
+            let mut convert = std::process::Command::new("rsvg-convert")
+                .args(["--format", "png", "--output"])
+                .arg(path)
+                .stdin(std::process::Stdio::piped())
+                .spawn()
+                .map_err(|e| format!("Writing PNG needs rsvg-convert (librsvg) on the PATH - or write .svg: {}", e))?;
+            if let Some(mut stdin) = convert.stdin.take() {
+                stdin.write_all(svg.as_bytes())?;
+            }
+            let status = convert.wait()?;
+            if !status.success() {
+                return Err(format!("Converting the chart to {:?} failed: {}", path, status).into());
+            }
+            Ok(())
+        }
+        _ => Err(format!("Can't tell the chart format of {:?}, expected a .svg or .png file", path).into()),
+    }
+}
@@ -0,0 +1,164 @@
+use log::info;
+use rusqlite::{params, Connection, OptionalExtension, NO_PARAMS};
+
+use crate::table::Table;
+use crate::{dates, GameId};
+
+/// Every merge into the target is a run on a host; `MergeProvenance` records
+/// which run copied a game from which source, & `MergeReplaced` the target
+/// games it replaced or dropped - as `export --format json` writes a game -
+/// so a run can be undone.
+const TABLES: &str = "CREATE TABLE IF NOT EXISTS MergeRuns (RunId INTEGER PRIMARY KEY AUTOINCREMENT, \
+    MergedAt INTEGER NOT NULL, Host TEXT);
+CREATE TABLE IF NOT EXISTS MergeProvenance (GameId INTEGER PRIMARY KEY NOT NULL, RunId INTEGER NOT NULL, \
+    Source TEXT NOT NULL, SourceGameId INTEGER NOT NULL);
+CREATE TABLE IF NOT EXISTS MergeReplaced (RunId INTEGER NOT NULL, GameId INTEGER NOT NULL, Game TEXT NOT NULL);";
+
+pub type RunId = i64;
+
+/// An undone run with the number of games removed & restored.
+pub type Undone = (RunId, usize, usize);
+
+/// Name of the machine running the merge.
+fn hostname() -> Option<String> {
+    let name = std::env::var("COMPUTERNAME")
+        .ok()
+        .or_else(|| std::fs::read_to_string("/proc/sys/kernel/hostname").ok())
+        .or_else(|| {
+            let output = std::process::Command::new("hostname").output().ok()?;
+            Some(String::from_utf8_lossy(&output.stdout).into_owned()).filter(|_| output.status.success())
+        })?;
+    Some(name.trim().to_string()).filter(|name| !name.is_empty())
+}
+
+/// Starts a run in the target's history, creating the tables if needed.
+pub fn start_run(con: &Connection) -> rusqlite::Result<RunId> {
+    con.execute_batch(TABLES)?;
+    if !crate::schema::columns(con, "MergeRuns")?.iter().any(|column| column == "Host") {
+        // Created by an older version
+        con.execute("ALTER TABLE MergeRuns ADD COLUMN Host TEXT", NO_PARAMS)?;
+    }
+    let now = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_secs() as i64)
+        .unwrap_or_default();
+    con.execute(
+        "INSERT INTO MergeRuns (MergedAt, Host) VALUES (?, ?)",
+        params![now, hostname()],
+    )?;
+    Ok(con.last_insert_rowid())
+}
+
+/// Records that `run` copied `source_game_id` of `source` as `game_id`.
+pub fn record_game(
+    con: &Connection,
+    run: RunId,
+    game_id: GameId,
+    source: &std::path::Path,
+    source_game_id: GameId,
+) -> rusqlite::Result<()> {
+    con.execute(
+        "INSERT OR REPLACE INTO MergeProvenance (GameId, RunId, Source, SourceGameId) VALUES (?, ?, ?, ?)",
+        params![game_id, run, source.to_string_lossy(), source_game_id],
+    )?;
+    Ok(())
+}
+
+/// Keeps all rows of the target's `game_id` before `run` replaces or drops it.
+pub fn record_replaced(con: &Connection, run: RunId, game_id: GameId) -> Result<(), Box<dyn std::error::Error>> {
+    let document = crate::export::json(con, &[game_id])?;
+    con.execute(
+        "INSERT INTO MergeReplaced (RunId, GameId, Game) VALUES (?, ?, ?)",
+        params![run, game_id, document["games"][0].to_string()],
+    )?;
+    Ok(())
+}
+
+/// The target games `run` replaced or dropped, as kept by `record_replaced`.
+pub fn replaced_games(con: &Connection, run: RunId) -> Result<Vec<serde_json::Value>, Box<dyn std::error::Error>> {
+    if crate::schema::columns(con, "MergeReplaced")?.is_empty() {
+        return Ok(Vec::new());
+    }
+    let mut stmt = con.prepare("SELECT Game FROM MergeReplaced WHERE RunId = ? ORDER BY GameId")?;
+    let games = stmt
+        .query_map(params![run], |row| row.get::<_, String>(0))?
+        .collect::<rusqlite::Result<Vec<String>>>()?;
+    Ok(games
+        .iter()
+        .map(|game| serde_json::from_str(game))
+        .collect::<serde_json::Result<_>>()?)
+}
+
+/// Removes a run from the history, leaving its games be.
+pub fn forget_run(con: &Connection, run: RunId) -> rusqlite::Result<()> {
+    con.execute("DELETE FROM MergeProvenance WHERE RunId = ?", params![run])?;
+    if !crate::schema::columns(con, "MergeReplaced")?.is_empty() {
+        con.execute("DELETE FROM MergeReplaced WHERE RunId = ?", params![run])?;
+    }
+    con.execute("DELETE FROM MergeRuns WHERE RunId = ?", params![run])?;
+    Ok(())
+}
+
+/// A run - the most recent one unless given - with its games, if the target has it.
+pub fn run_games(con: &Connection, run: Option<RunId>) -> rusqlite::Result<Option<(RunId, Vec<GameId>)>> {
+    if crate::schema::columns(con, "MergeRuns")?.is_empty() {
+        return Ok(None);
+    }
+    let run: Option<RunId> = con
+        .query_row(
+            "SELECT MAX(RunId) FROM MergeRuns WHERE ?1 IS NULL OR RunId = ?1",
+            params![run],
+            |row| row.get(0),
+        )
+        .optional()?
+        .flatten();
+    let run = match run {
+        Some(run) => run,
+        None => return Ok(None),
+    };
+
+    let mut stmt = con.prepare("SELECT GameId FROM MergeProvenance WHERE RunId = ? ORDER BY GameId")?;
+    let games = stmt
+        .query_map(params![run], |row| row.get(0))?
+        .collect::<rusqlite::Result<Vec<GameId>>>()?;
+    Ok(Some((run, games)))
+}
+
+/// Removes the `games` of `run` - as `run_games` lists them - with all their
+/// rows, & the run itself. Games of the run replaced since are gone already.
+pub fn undo(con: &Connection, run: RunId, games: &[GameId]) -> Result<(), Box<dyn std::error::Error>> {
+    for game_id in games {
+        crate::delete_game(con, *game_id)?;
+    }
+    forget_run(con, run)?;
+    info!("Undid run {}: Removed {} game(s)", run, games.len());
+    Ok(())
+}
+
+/// The merged games with the run, host & source they came from - optionally
+/// only those merged on `host` or from sources whose path contains `source`.
+pub fn provenance(con: &Connection, host: Option<&str>, source: Option<&str>) -> rusqlite::Result<Table> {
+    let mut table = Table::new(&["Game", "Run", "Merged", "Host", "Source", "Source game"]);
+    if crate::schema::columns(con, "MergeProvenance")?.is_empty() {
+        return Ok(table);
+    }
+    let has_host = crate::schema::columns(con, "MergeRuns")?.iter().any(|column| column == "Host");
+    let mut stmt = con.prepare(&format!(
+        "SELECT mp.GameId, mp.RunId, mr.MergedAt, {}, mp.Source, mp.SourceGameId FROM MergeProvenance mp \
+         JOIN MergeRuns mr ON mr.RunId = mp.RunId \
+         WHERE (?1 IS NULL OR mr.Host = ?1) AND (?2 IS NULL OR instr(mp.Source, ?2) > 0) ORDER BY mp.GameId",
+        if has_host { "mr.Host" } else { "NULL AS Host" }
+    ))?;
+    let mut rows = stmt.query(params![host, source])?;
+    while let Some(row) = rows.next()? {
+        table.push(vec![
+            row.get::<_, GameId>(0)?.to_string(),
+            row.get::<_, RunId>(1)?.to_string(),
+            dates::format_date_time(row.get(2)?),
+            row.get::<_, Option<String>>(3)?.unwrap_or_else(|| "-".to_string()),
+            row.get(4)?,
+            row.get::<_, GameId>(5)?.to_string(),
+        ]);
+    }
+    Ok(table)
+}
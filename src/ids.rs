@@ -0,0 +1,59 @@
+//! Remapping of ids between a source & the target: Rows copied into the
+//! target get new ids, so references between copied rows are rewritten
+//! through the map of the ids copied so far.
+
+use std::collections::HashMap;
+
+/// Tables whose ids are assigned anew when rows are copied into the target.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Table {
+    Games,
+    GameObjects,
+    GamePlayers,
+    DataSets,
+}
+
+/// The ids copied rows got in the target, by table & source id. Filled by the
+/// copy functions as they insert rows & consulted whenever a copied row
+/// references another one.
+#[derive(Debug, Default)]
+pub struct IdMap {
+    ids: HashMap<(Table, i64), i64>,
+}
+
+impl IdMap {
+    /// Records that the row `old` of `table` was copied as `new`.
+    pub fn insert(&mut self, table: Table, old: impl Into<i64>, new: i64) {
+        self.ids.insert((table, old.into()), new);
+    }
+
+    /// The target id of a copied row.
+    pub fn get(&self, table: Table, old: impl Into<i64>) -> Option<i64> {
+        self.ids.get(&(table, old.into())).copied()
+    }
+
+    /// Rewrites a nullable reference - references to rows that weren't copied become NULL.
+    pub fn remap(&self, table: Table, old: Option<impl Into<i64>>) -> Option<i64> {
+        old.and_then(|old| self.get(table, old))
+    }
+
+    /// Source ids of the copied rows of a table, ascending.
+    pub fn copied(&self, table: Table) -> Vec<i64> {
+        let mut ids: Vec<i64> = self
+            .ids
+            .keys()
+            .filter(|(t, _)| *t == table)
+            .map(|(_, old)| *old)
+            .collect();
+        ids.sort_unstable();
+        ids
+    }
+
+    /// Source & target ids of the copied rows of a table, ascending by source id.
+    pub fn pairs(&self, table: Table) -> Vec<(i64, i64)> {
+        self.copied(table)
+            .into_iter()
+            .map(|old| (old, self.ids[&(table, old)]))
+            .collect()
+    }
+}
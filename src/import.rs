@@ -0,0 +1,98 @@
+//! `import`: Games of a JSON export - as `export --format json` writes them -
+//! loaded into a scratch database, from which they are merged like the games
+//! of any other source.
+
+use rusqlite::types::Value;
+use rusqlite::Connection;
+
+use crate::schema;
+
+/// Tables of a game in the document, besides its `Game`, referenced rows first.
+const TABLES: [&str; 5] = [
+    "GameObjects",
+    "GamePlayers",
+    "GameDataPointValues",
+    "ObjectDataPointValues",
+    "DataSets",
+];
+
+/// A column value of the document, the inverse of `export::json_value`.
+fn sql_value(value: &serde_json::Value) -> Result<Value, String> {
+    Ok(match value {
+        serde_json::Value::Null => Value::Null,
+        serde_json::Value::Bool(boolean) => Value::Integer(*boolean as i64),
+        serde_json::Value::Number(number) => match number.as_i64() {
+            Some(integer) => Value::Integer(integer),
+            None => Value::Real(number.as_f64().unwrap_or_default()),
+        },
+        serde_json::Value::String(text) => Value::Text(text.clone()),
+        serde_json::Value::Object(object) => {
+            let hex = object
+                .get("hex")
+                .and_then(serde_json::Value::as_str)
+                .ok_or_else(|| format!("Unexpected value {}", value))?;
+            let blob = (0..hex.len())
+                .step_by(2)
+                .map(|i| u8::from_str_radix(hex.get(i..i + 2).unwrap_or("?"), 16))
+                .collect::<Result<Vec<u8>, _>>()
+                .map_err(|_| format!("Invalid hex blob {:?}", hex))?;
+            Value::Blob(blob)
+        }
+        serde_json::Value::Array(_) => return Err(format!("Unexpected value {}", value)),
+    })
+}
+
+/// Inserts a row of the document into `table`, leaving out members the table has no column for.
+fn insert(con: &Connection, table: &str, row: &serde_json::Value) -> Result<(), Box<dyn std::error::Error>> {
+    let row = row
+        .as_object()
+        .ok_or_else(|| format!("Expected a row of {} but got {}", table, row))?;
+    let columns = schema::columns(con, table)?;
+    let mut names = Vec::new();
+    let mut values = Vec::new();
+    for (column, value) in row {
+        if columns.contains(column) {
+            names.push(format!("\"{}\"", column));
+            values.push(sql_value(value)?);
+        }
+    }
+    con.execute(
+        &format!(
+            "INSERT INTO \"{}\" ({}) VALUES ({})",
+            table,
+            names.join(", "),
+            vec!["?"; names.len()].join(", ")
+        ),
+        &values,
+    )?;
+    Ok(())
+}
+
+fn rows<'a>(parent: &'a serde_json::Value, table: &str) -> &'a [serde_json::Value] {
+    parent.get(table).and_then(serde_json::Value::as_array).map_or(&[], Vec::as_slice)
+}
+
+/// Loads the games of an export into `con`, an empty database, returning their number.
+pub fn load(con: &Connection, document: &serde_json::Value) -> Result<usize, Box<dyn std::error::Error>> {
+    let games = document
+        .get("games")
+        .and_then(serde_json::Value::as_array)
+        .ok_or("Not a civ6-hof-merge JSON export: No \"games\" array")?;
+    for row in rows(document, "RulesetDataPointValues") {
+        insert(con, "RulesetDataPointValues", row)?;
+    }
+    for game in games {
+        insert(con, "Games", game.get("Game").ok_or("A game without \"Game\" row")?)?;
+        for table in &TABLES {
+            for row in rows(game, table) {
+                insert(con, table, row)?;
+                if *table == "DataSets" {
+                    for value in rows(row, "DataSetValues") {
+                        insert(con, "DataSetValues", value)?;
+                    }
+                }
+            }
+        }
+    }
+    Ok(games.len())
+}
@@ -0,0 +1,215 @@
+//! Referential integrity of a Hall of Fame: The game doesn't declare foreign
+//! keys, so rows pointing at games, objects or players that don't exist -
+//! e.g. left by a crashed game or a faulty merge - go unnoticed until a merge
+//! or the game's Hall-of-Fame screen stumbles over them.
+
+use rusqlite::{Connection, NO_PARAMS};
+
+use crate::schema;
+
+/// Ids of the orphans listed, the rest is only counted.
+const LISTED_IDS: usize = 20;
+
+/// Passes of `repair` at most - deleting rows orphans the rows referencing them.
+const REPAIR_PASSES: usize = 10;
+
+/// What `repair` does with an orphaned row.
+#[derive(Clone, Copy)]
+enum Fix {
+    /// Deletes the row
+    Delete,
+    /// Sets the reference to NULL - the row means something without it
+    Clear,
+    /// Links the row to the game of the object in this column, deleting it
+    /// if that doesn't exist either
+    Relink(&'static str),
+}
+
+/// A column referencing rows of another table.
+struct Reference {
+    table: &'static str,
+    column: &'static str,
+    referenced: &'static str,
+    referenced_column: &'static str,
+    fix: Fix,
+}
+
+const fn reference(
+    table: &'static str,
+    column: &'static str,
+    referenced: &'static str,
+    referenced_column: &'static str,
+    fix: Fix,
+) -> Reference {
+    Reference {
+        table,
+        column,
+        referenced,
+        referenced_column,
+        fix,
+    }
+}
+
+const REFERENCES: [Reference; 10] = [
+    reference("GameObjects", "GameId", "Games", "GameId", Fix::Relink("PlayerObjectId")),
+    reference("GameObjects", "PlayerObjectId", "GamePlayers", "PlayerObjectId", Fix::Clear),
+    reference("GamePlayers", "PlayerObjectId", "GameObjects", "ObjectId", Fix::Delete),
+    reference("GameDataPointValues", "GameId", "Games", "GameId", Fix::Relink("ValueObjectId")),
+    reference("GameDataPointValues", "ValueObjectId", "GameObjects", "ObjectId", Fix::Delete),
+    reference("ObjectDataPointValues", "ObjectId", "GameObjects", "ObjectId", Fix::Delete),
+    reference("ObjectDataPointValues", "ValueObjectId", "GameObjects", "ObjectId", Fix::Delete),
+    reference("DataSets", "GameId", "Games", "GameId", Fix::Relink("ObjectId")),
+    reference("DataSets", "ObjectId", "GameObjects", "ObjectId", Fix::Delete),
+    reference("DataSetValues", "DataSetId", "DataSets", "DataSetId", Fix::Delete),
+];
+
+impl Reference {
+    fn exists(&self, con: &Connection) -> rusqlite::Result<bool> {
+        Ok(has_column(con, self.table, self.column)? && has_column(con, self.referenced, self.referenced_column)?)
+    }
+
+    /// Condition on the rows of the table aliased `t` that are orphans.
+    fn dangling(&self) -> String {
+        format!(
+            "t.{0} IS NOT NULL AND NOT EXISTS (SELECT 1 FROM {1} r WHERE r.{2} = t.{0})",
+            self.column, self.referenced, self.referenced_column
+        )
+    }
+}
+
+/// Rows of a table whose reference points at no row.
+pub struct Orphans {
+    pub table: &'static str,
+    pub column: &'static str,
+    pub referenced: &'static str,
+    pub rows: i64,
+    /// The distinct dangling ids
+    pub ids: Vec<i64>,
+}
+
+impl Orphans {
+    /// The dangling ids, at most `LISTED_IDS` of them.
+    pub fn ids_text(&self) -> String {
+        let listed: Vec<String> = self.ids.iter().take(LISTED_IDS).map(|id| id.to_string()).collect();
+        if self.ids.len() > LISTED_IDS {
+            format!("{} & {} more", listed.join(", "), self.ids.len() - LISTED_IDS)
+        } else {
+            listed.join(", ")
+        }
+    }
+}
+
+fn has_column(con: &Connection, table: &str, column: &str) -> rusqlite::Result<bool> {
+    Ok(schema::columns(con, table)?.iter().any(|c| c == column))
+}
+
+/// The orphaned rows of each reference, skipping references to columns the
+/// database lacks.
+pub fn orphans(con: &Connection) -> rusqlite::Result<Vec<Orphans>> {
+    let mut found = Vec::new();
+    for reference in REFERENCES.iter() {
+        if !reference.exists(con)? {
+            continue;
+        }
+        let mut stmt = con.prepare(&format!(
+            "SELECT t.{1}, COUNT(*) FROM {0} t WHERE {2} GROUP BY t.{1} ORDER BY t.{1}",
+            reference.table,
+            reference.column,
+            reference.dangling()
+        ))?;
+        let dangling = stmt
+            .query_map(NO_PARAMS, |row| Ok((row.get::<_, i64>(0)?, row.get::<_, i64>(1)?)))?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+        if !dangling.is_empty() {
+            found.push(Orphans {
+                table: reference.table,
+                column: reference.column,
+                referenced: reference.referenced,
+                rows: dangling.iter().map(|(_, rows)| rows).sum(),
+                ids: dangling.into_iter().map(|(id, _)| id).collect(),
+            });
+        }
+    }
+    Ok(found)
+}
+
+/// Rows `repair` changed for one reference.
+pub struct Repair {
+    pub table: &'static str,
+    pub column: &'static str,
+    pub referenced: &'static str,
+    pub relinked: usize,
+    pub cleared: usize,
+    pub deleted: usize,
+}
+
+/// Fixes the orphaned rows of one reference, returning what it changed.
+fn repair_reference(con: &Connection, reference: &Reference) -> rusqlite::Result<Repair> {
+    let mut repair = Repair {
+        table: reference.table,
+        column: reference.column,
+        referenced: reference.referenced,
+        relinked: 0,
+        cleared: 0,
+        deleted: 0,
+    };
+    let (table, column, dangling) = (reference.table, reference.column, reference.dangling());
+    match reference.fix {
+        Fix::Clear => {
+            repair.cleared = con.execute(
+                &format!("UPDATE {0} AS t SET {1} = NULL WHERE {2}", table, column, dangling),
+                NO_PARAMS,
+            )?;
+        }
+        Fix::Relink(via) if has_column(con, table, via)? => {
+            let game = format!(
+                "(SELECT o.GameId FROM GameObjects o JOIN Games g ON g.GameId = o.GameId WHERE o.ObjectId = t.{})",
+                via
+            );
+            repair.relinked = con.execute(
+                &format!(
+                    "UPDATE {0} AS t SET {1} = {3} WHERE {2} AND {3} IS NOT NULL",
+                    table, column, dangling, game
+                ),
+                NO_PARAMS,
+            )?;
+        }
+        Fix::Relink(_) | Fix::Delete => {}
+    }
+    repair.deleted = con.execute(&format!("DELETE FROM {0} AS t WHERE {1}", table, dangling), NO_PARAMS)?;
+    Ok(repair)
+}
+
+/// Re-links, clears or deletes orphaned rows until none are left, returning
+/// the changes per reference.
+pub fn repair(con: &Connection) -> rusqlite::Result<Vec<Repair>> {
+    let mut repairs: Vec<Repair> = Vec::new();
+    for _ in 0..REPAIR_PASSES {
+        let mut changed = false;
+        for reference in REFERENCES.iter() {
+            if !reference.exists(con)? {
+                continue;
+            }
+            let repair = repair_reference(con, reference)?;
+            if repair.relinked + repair.cleared + repair.deleted == 0 {
+                continue;
+            }
+            changed = true;
+            match repairs
+                .iter_mut()
+                .find(|r| r.table == repair.table && r.column == repair.column)
+            {
+                Some(existing) => {
+                    existing.relinked += repair.relinked;
+                    existing.cleared += repair.cleared;
+                    existing.deleted += repair.deleted;
+                }
+                None => repairs.push(repair),
+            }
+        }
+        if !changed {
+            break;
+        }
+    }
+    Ok(repairs)
+}
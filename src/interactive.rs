@@ -0,0 +1,68 @@
+//! `--interactive`: Asks before copying each game of the sources, showing a
+//! one-line summary of it - picking games without knowing their GameIds.
+
+use std::cell::Cell;
+use std::io::{BufRead, Write};
+
+use rusqlite::Connection;
+
+use crate::names::Names;
+use crate::{show, Game};
+
+/// What was answered so far, for the games still to come.
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum State {
+    Asking,
+    /// `a`: Copies the remaining games without asking
+    All,
+    /// `q`: Copies no more games
+    Quit,
+}
+
+pub struct Prompt {
+    names: Names,
+    state: Cell<State>,
+}
+
+impl Prompt {
+    pub fn new(names: Names) -> Self {
+        Prompt {
+            names,
+            state: Cell::new(State::Asking),
+        }
+    }
+
+    /// Asks whether to copy a source game, unless all or none of the
+    /// remaining games were chosen already.
+    pub fn confirm(&self, source: &Connection, game: &Game) -> Result<bool, Box<dyn std::error::Error>> {
+        match self.state.get() {
+            State::All => return Ok(true),
+            State::Quit => return Ok(false),
+            State::Asking => {}
+        }
+        let description = show::describe(source, &self.names, game)?;
+        let stdin = std::io::stdin();
+        loop {
+            print!("{} - copy? [y/n/a/q] ", description);
+            std::io::stdout().flush()?;
+            let mut answer = String::new();
+            if stdin.lock().read_line(&mut answer)? == 0 {
+                self.state.set(State::Quit);
+                return Ok(false);
+            }
+            match answer.trim() {
+                "y" | "Y" => return Ok(true),
+                "n" | "N" => return Ok(false),
+                "a" | "A" => {
+                    self.state.set(State::All);
+                    return Ok(true);
+                }
+                "q" | "Q" => {
+                    self.state.set(State::Quit);
+                    return Ok(false);
+                }
+                _ => println!("Answer y(es), n(o), a(ll remaining games) or q(uit)"),
+            }
+        }
+    }
+}
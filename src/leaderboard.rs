@@ -0,0 +1,177 @@
+//! `leaderboard`: A shareable page of the archive's best games - the top
+//! scores, the fastest victory of each type & each leader's bests - in
+//! Markdown for wikis or as a self-contained HTML page.
+
+use std::str::FromStr;
+
+use rusqlite::{Connection, NO_PARAMS};
+
+use crate::names::Names;
+use crate::table::Table;
+use crate::{dates, stats, LOCAL_PLAYER_JOIN};
+
+/// Condition of games the local player (`lp`) won.
+const WON: &str = "g.VictorTeamId = lp.TeamId";
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Format {
+    Markdown,
+    Html,
+}
+
+impl FromStr for Format {
+    type Err = String;
+
+    fn from_str(format: &str) -> Result<Self, Self::Err> {
+        match format {
+            "markdown" => Ok(Format::Markdown),
+            "html" => Ok(Format::Html),
+            _ => Err(format!("Unknown leaderboard format {:?}, expected markdown or html", format)),
+        }
+    }
+}
+
+const STYLE: &str = "<style>
+body { font-family: sans-serif; margin: 2em; }
+table { border-collapse: collapse; margin-bottom: 2em; }
+th, td { border: 1px solid #ccc; padding: 0.2em 0.6em; }
+th { background: #eee; }
+</style>
+";
+
+fn display(names: &Names, key: Option<String>) -> String {
+    key.map_or_else(|| "-".to_string(), |key| names.display(&key).to_string())
+}
+
+/// The games with the highest scores of the local player.
+fn top_scores(con: &Connection, names: &Names, limit: i64) -> rusqlite::Result<Table> {
+    let mut stmt = con.prepare(&format!(
+        "SELECT g.GameId, lp.LeaderType, lp.LeaderName, g.VictoryType, {}, g.GameSpeedType, lp.DifficultyType, \
+         g.TurnCount, lp.Score, g.LastPlayed FROM Games g {} WHERE lp.LeaderType IS NOT NULL \
+         ORDER BY lp.Score DESC, g.LastPlayed LIMIT {}",
+        WON, LOCAL_PLAYER_JOIN, limit
+    ))?;
+    let mut table = Table::new(&["#", "Leader", "Score", "Victory", "Speed", "Difficulty", "Turns", "Game", "Date"]);
+    let mut rows = stmt.query(NO_PARAMS)?;
+    let mut rank = 0;
+    while let Some(row) = rows.next()? {
+        rank += 1;
+        let victory = match (row.get::<_, Option<String>>(3)?, row.get::<_, Option<bool>>(4)?) {
+            (Some(victory), Some(true)) => format!("{} (won)", names.display(&victory)),
+            (victory, _) => display(names, victory),
+        };
+        table.push(vec![
+            rank.to_string(),
+            names.leader(&row.get::<_, String>(1)?, row.get::<_, Option<String>>(2)?.as_deref()),
+            row.get::<_, i64>(8)?.to_string(),
+            victory,
+            display(names, row.get(5)?),
+            display(names, row.get(6)?),
+            row.get::<_, i64>(7)?.to_string(),
+            row.get::<_, i64>(0)?.to_string(),
+            dates::format_date(row.get(9)?),
+        ]);
+    }
+    Ok(table)
+}
+
+/// The local player's fastest win of each victory type, in turns at Standard speed.
+fn fastest_victories(con: &Connection, names: &Names) -> rusqlite::Result<Table> {
+    let mut stmt = con.prepare(&format!(
+        "SELECT g.VictoryType, lp.LeaderType, lp.LeaderName, g.GameSpeedType, lp.DifficultyType, g.TurnCount, \
+         g.GameId, g.LastPlayed FROM Games g {} WHERE {} AND g.VictoryType IS NOT NULL",
+        LOCAL_PLAYER_JOIN, WON
+    ))?;
+    let mut fastest: Vec<(String, f64, Vec<String>)> = Vec::new();
+    let mut rows = stmt.query(NO_PARAMS)?;
+    while let Some(row) = rows.next()? {
+        let victory: String = row.get(0)?;
+        let speed: String = row.get(3)?;
+        let turns: i64 = row.get(5)?;
+        let standard = stats::standard_turns(&speed, turns);
+        if fastest.iter().any(|(known, best, _)| *known == victory && *best <= standard) {
+            continue;
+        }
+        fastest.retain(|(known, _, _)| *known != victory);
+        let cells = vec![
+            names.display(&victory).to_string(),
+            names.leader(&row.get::<_, String>(1)?, row.get::<_, Option<String>>(2)?.as_deref()),
+            turns.to_string(),
+            format!("{:.0}", standard),
+            names.display(&speed).to_string(),
+            display(names, row.get(4)?),
+            row.get::<_, i64>(6)?.to_string(),
+            dates::format_date(row.get(7)?),
+        ];
+        fastest.push((victory, standard, cells));
+    }
+    fastest.sort_by(|a, b| a.0.cmp(&b.0));
+
+    let mut table = Table::new(&[
+        "Victory",
+        "Leader",
+        "Turns",
+        "Standard turns",
+        "Speed",
+        "Difficulty",
+        "Game",
+        "Date",
+    ]);
+    for (_, _, cells) in fastest {
+        table.push(cells);
+    }
+    Ok(table)
+}
+
+/// Games, wins, best score & fastest win of each leader the local player played.
+fn leader_bests(con: &Connection, names: &Names) -> rusqlite::Result<Table> {
+    let mut stmt = con.prepare(&format!(
+        "SELECT lp.LeaderType, MAX(lp.LeaderName), COUNT(*), SUM({0}), MAX(lp.Score), \
+         MIN(CASE WHEN {0} THEN g.TurnCount END) FROM Games g {1} WHERE lp.LeaderType IS NOT NULL \
+         GROUP BY lp.LeaderType ORDER BY SUM({0}) DESC, MAX(lp.Score) DESC",
+        WON, LOCAL_PLAYER_JOIN
+    ))?;
+    let mut table = Table::new(&["Leader", "Games", "Wins", "Best score", "Fastest win (turns)"]);
+    let mut rows = stmt.query(NO_PARAMS)?;
+    while let Some(row) = rows.next()? {
+        table.push(vec![
+            names.leader(&row.get::<_, String>(0)?, row.get::<_, Option<String>>(1)?.as_deref()),
+            row.get::<_, i64>(2)?.to_string(),
+            row.get::<_, Option<i64>>(3)?.unwrap_or(0).to_string(),
+            row.get::<_, i64>(4)?.to_string(),
+            row.get::<_, Option<i64>>(5)?.map_or_else(|| "-".to_string(), |turns| turns.to_string()),
+        ]);
+    }
+    Ok(table)
+}
+
+/// The tables of the leaderboard with their titles, listing the `limit` highest scores.
+pub fn sections(con: &Connection, names: &Names, limit: i64) -> rusqlite::Result<[(&'static str, Table); 3]> {
+    Ok([
+        ("Top scores", top_scores(con, names, limit)?),
+        ("Fastest victories", fastest_victories(con, names)?),
+        ("Leader bests", leader_bests(con, names)?),
+    ])
+}
+
+/// Renders the leaderboard page with the `limit` highest scores.
+pub fn render(con: &Connection, names: &Names, format: Format, limit: i64) -> rusqlite::Result<String> {
+    let sections = sections(con, names, limit)?;
+    let mut page = match format {
+        Format::Markdown => String::from("# Hall of Fame leaderboard\n"),
+        Format::Html => format!(
+            "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>Hall of Fame leaderboard</title>\n{}</head>\n<body>\n<h1>Hall of Fame leaderboard</h1>\n",
+            STYLE
+        ),
+    };
+    for (title, table) in &sections {
+        match format {
+            Format::Markdown => page.push_str(&format!("\n## {}\n\n{}", title, table.render_markdown())),
+            Format::Html => page.push_str(&format!("<h2>{}</h2>\n{}", title, table.render_html())),
+        }
+    }
+    if format == Format::Html {
+        page.push_str("</body>\n</html>\n");
+    }
+    Ok(page)
+}
@@ -0,0 +1,68 @@
+//! Detection of a database held by another program: Civilization VI keeps its
+//! Hall of Fame open while running, & a merge writing it then either corrupts
+//! it or has its changes overwritten by the game.
+
+use std::path::Path;
+use std::time::Duration;
+
+use rusqlite::{Connection, ErrorCode};
+
+use crate::cipher;
+
+/// How long `--wait` lets SQLite retry the lock before checking again.
+const WAIT_STEP: Duration = Duration::from_secs(5);
+
+fn is_busy(error: &rusqlite::Error) -> bool {
+    matches!(
+        error,
+        rusqlite::Error::SqliteFailure(e, _) if e.code == ErrorCode::DatabaseBusy || e.code == ErrorCode::DatabaseLocked
+    )
+}
+
+/// Takes & releases an exclusive lock, failing with busy or locked while
+/// another connection reads or writes - in WAL mode while one writes.
+fn try_exclusive(con: &Connection) -> rusqlite::Result<()> {
+    con.execute_batch("BEGIN EXCLUSIVE")?;
+    con.execute_batch("ROLLBACK")
+}
+
+/// Whether another program holds a lock on the database right now.
+pub fn is_locked(path: &Path) -> rusqlite::Result<bool> {
+    let con = cipher::open(path)?;
+    con.busy_timeout(Duration::from_secs(0))?;
+    match try_exclusive(&con) {
+        Ok(()) => Ok(false),
+        Err(e) if is_busy(&e) => Ok(true),
+        Err(e) => Err(e),
+    }
+}
+
+/// Refuses a database another program - typically the running game - holds a
+/// lock on, or with `wait` waits until it is released.
+pub fn ensure_unlocked(path: &Path, wait: bool) -> Result<(), Box<dyn std::error::Error>> {
+    if !path.is_file() || !is_locked(path)? {
+        return Ok(());
+    }
+    if !wait {
+        let mut wal = path.as_os_str().to_owned();
+        wal.push("-wal");
+        let hint = if Path::new(&wal).exists() { " with a -wal file next to it" } else { "" };
+        return Err(format!(
+            "{:?} is locked by another program{} - is Civilization VI running? Close it, or pass --wait to wait until it is released",
+            path, hint
+        )
+        .into());
+    }
+
+    eprintln!("Waiting for {:?} to be released - close Civilization VI to go on", path);
+    let con = cipher::open(path)?;
+    con.busy_timeout(WAIT_STEP)?;
+    loop {
+        match try_exclusive(&con) {
+            Ok(()) => return Ok(()),
+            Err(e) if is_busy(&e) => continue,
+            Err(e) => return Err(e.into()),
+        }
+    }
+}
+
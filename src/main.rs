@@ -1,25 +1,805 @@
 use rusqlite::{params, Connection, Result, NO_PARAMS};
 use serde::{Deserialize, Serialize};
+use serde_json::json;
 use serde_rusqlite::*;
-use std::fs::File;
+use std::collections::HashSet;
 
-use log::{debug, info};
-use maplit::hashset;
+use log::{debug, info, warn};
+use structopt::clap::AppSettings;
 use structopt::StructOpt;
 
+use copier::Copier;
+use ids::{IdMap, Table};
+
+mod aggregates;
+mod archive;
+mod cache;
+mod career;
+mod cipher;
+mod conflicts;
+mod content;
+mod copier;
+mod dates;
+mod debug_graph;
+mod dedupe;
+mod diff;
+mod doctor;
+mod events;
+mod explain;
+mod export;
+mod extra_tables;
+mod filters;
+#[cfg(feature = "charts")]
+mod graph;
+mod hash;
+mod history;
+mod ids;
+mod import;
+mod integrity;
+mod interactive;
+mod leaderboard;
+mod lock;
+mod maps;
+mod matrix;
+mod merge_log;
+mod milestones;
+#[cfg(feature = "charts")]
+mod minimap;
+mod names;
+mod output;
+mod pack;
+mod profiles;
+mod ratings;
+#[cfg(feature = "charts")]
+mod report;
+mod saves;
+mod schema;
+mod search;
+#[cfg(feature = "service")]
+mod service;
+#[cfg(feature = "shell")]
+mod shell;
+mod show;
+mod sources;
+mod stats;
+mod table;
+mod top;
+mod views;
+
 #[derive(StructOpt)]
-#[structopt(name = "civ6-hof-merge", about = "Merges two HallOfFame-SQLite database files into one")]
+#[structopt(
+    name = "civ6-hof-merge",
+    about = "Merges HallOfFame-SQLite database files into one",
+    usage = "civ6-hof-merge [merge] [OPTIONS] <sources>... <target>\n    civ6-hof-merge [merge] [OPTIONS] -o <target> <sources>...\n    civ6-hof-merge <SUBCOMMAND>",
+    setting = AppSettings::SubcommandsNegateReqs
+)]
 struct Cli {
-    #[structopt(parse(from_os_str))]
-    source1: std::path::PathBuf,
-    #[structopt(parse(from_os_str))]
-    source2: std::path::PathBuf,
-    #[structopt(parse(from_os_str))]
-    target: std::path::PathBuf,
+    #[structopt(subcommand)]
+    command: Option<Command>,
+    /// CSV (`KEY,Name`) or TOML (`KEY = "Name"`) file with display names for leader, civilization & other types
+    #[structopt(long, global = true, parse(from_os_str))]
+    names: Option<std::path::PathBuf>,
+    /// Passphrase of SQLCipher-encrypted databases - merge targets get encrypted with it
+    /// (needs the `sqlcipher` feature)
+    #[structopt(long, global = true, env = "CIV6_HOF_PASSPHRASE", hide_env_values = true)]
+    passphrase: Option<String>,
+    /// Treatment of databases lacking Hall-of-Fame tables: `strict` refuses them, `lenient` warns & uses
+    /// what they have - e.g. pared-down or very old ones - & `off` doesn't check
+    #[structopt(long, global = true, default_value = "strict", possible_values = &["strict", "lenient", "off"])]
+    schema_check: schema::Check,
+    #[structopt(flatten)]
+    merge: MergeArgs,
+}
+
+// The sources & target of a merge & how to merge them - the arguments of the
+// `merge` subcommand, also taken without one. No doc comment, as it would
+// replace the about of the command line.
+#[derive(StructOpt)]
+struct MergeArgs {
+    /// Database files, URLs or directories to search for HallofFame.sqlite files, followed by the target.
+    /// The first source file found is the base of the target
+    #[structopt(parse(from_os_str), required = true)]
+    paths: Vec<std::path::PathBuf>,
+    /// Reads additional sources from a file (`-` for stdin), one per line
+    #[structopt(long, parse(from_os_str))]
+    sources_from: Option<std::path::PathBuf>,
+    /// Accepted SHA-256 checksum of URL sources - downloads not matching any are rejected
+    #[structopt(long, number_of_values = 1)]
+    sha256: Vec<String>,
+    /// Verifies each URL source against the checksum published at `<url>.sha256`
+    #[structopt(long)]
+    sha256_sidecar: bool,
+    /// Keeps the hashes of merged games in a `<target>.gamecache` file to skip known games on the next run
+    #[structopt(long)]
+    game_cache: bool,
+    /// Maintains per-leader & per-map summary tables in the target, updated with every merged game
+    #[structopt(long)]
+    summary_tables: bool,
+    /// Logs the games of each source copied or matched in a `MergeLog` table of the target, so merging
+    /// the same source again skips them right away
+    #[structopt(long)]
+    merge_log: bool,
+    /// Explains for each game why it was skipped as a duplicate or copied
+    #[structopt(long)]
+    explain_dups: bool,
+    /// Asks for each game of the sources whether to copy it: y(es), n(o), a(ll remaining) or q(uit)
+    #[structopt(long)]
+    interactive: bool,
+    /// When a source game is a duplicate: `exact` - all Games columns match, `fingerprint` - same setup &
+    /// players, last played within 30 days, so also continued games - or `game-id` - same GameId, for copies
+    /// of one Hall of Fame
+    #[structopt(long, default_value = "exact", possible_values = &["exact", "fingerprint", "game-id"])]
+    dedupe_by: dedupe::Strategy,
+    /// For sources sharing most of their history with the target, e.g. started from one Hall of Fame:
+    /// Finds the games both have in one pass up front & only checks the others one by one
+    #[structopt(long)]
+    shared_history: bool,
+    /// Version kept of a game the target has too: `keep-target`, `keep-source` - replacing the target's,
+    /// `keep-latest` - the one played last, or `keep-both` - as separate games
+    #[structopt(
+        long,
+        default_value = "keep-target",
+        possible_values = &["keep-target", "keep-source", "keep-latest", "keep-both"]
+    )]
+    on_conflict: conflicts::Keep,
+    /// Common ancestor of the sources - games it has that a source lacks were deleted on purpose & are
+    /// dropped from the target instead of being resurrected
+    #[structopt(long = "base", parse(from_os_str))]
+    ancestor: Option<std::path::PathBuf>,
+    /// Appends the full data of games the merge discarded in favor of another version to this JSON file
+    #[structopt(long, parse(from_os_str))]
+    conflicts: Option<std::path::PathBuf>,
+    /// Writes the merge result as a new generation into this archive directory instead of a target,
+    /// based on the archive's latest generation. All paths are sources then
+    #[structopt(long, parse(from_os_str))]
+    archive: Option<std::path::PathBuf>,
+    /// The target, making all paths sources - e.g. `-o out.sqlite a.sqlite b.sqlite c.sqlite`
+    #[structopt(short = "o", long, parse(from_os_str), conflicts_with = "archive")]
+    target: Option<std::path::PathBuf>,
+    /// Runs the merge against a throwaway copy & only reports how many games & rows it would copy -
+    /// the target isn't written
+    #[structopt(long)]
+    dry_run: bool,
+    /// Merges the other sources directly into the first one - e.g. the live HallofFame.sqlite - after
+    /// backing it up next to itself. A failed merge restores the backup
+    #[structopt(long, conflicts_with_all = &["archive", "target"])]
+    in_place: bool,
+    /// Waits for the game to release a target it holds open instead of refusing to merge into it
+    #[structopt(long)]
+    wait: bool,
+    /// Size ceiling of the target (e.g. `64M`, `1G`) - the game gets sluggish loading an oversized Hall of Fame.
+    /// A merge estimated to exceed it asks for confirmation
+    #[structopt(long, parse(try_from_str = parse_size))]
+    max_db_size: Option<u64>,
+    /// Leaves out games with leaders or rulesets outside the official content
+    #[structopt(long)]
+    exclude_modded: bool,
+    /// Leaves out games without a local human player - AI benchmarks & autoplay - including the base's
+    #[structopt(long, alias = "only-human")]
+    skip_ai_only: bool,
+    /// Only merges games ending in this victory type, e.g. `VICTORY_SCIENCE` or just `science`, or `none` for
+    /// games without a victory (may be repeated)
+    #[structopt(long, number_of_values = 1)]
+    victory: Vec<String>,
+    /// Only merges games the local player played as this leader, e.g. `LEADER_GANDHI` (may be repeated)
+    #[structopt(long, number_of_values = 1)]
+    leader: Vec<String>,
+    /// Only merges games the local player played as this civilization, e.g. `CIVILIZATION_INDIA` (may be repeated)
+    #[structopt(long, number_of_values = 1)]
+    civ: Vec<String>,
+    /// Only merges games on this map script, e.g. `Continents` (may be repeated)
+    #[structopt(long, number_of_values = 1)]
+    map: Vec<String>,
+    /// Only merges games of this map size, e.g. `MAPSIZE_HUGE` (may be repeated)
+    #[structopt(long, number_of_values = 1)]
+    map_size: Vec<String>,
+    /// Only merges games of this speed, e.g. `GAMESPEED_ONLINE` (may be repeated)
+    #[structopt(long, number_of_values = 1)]
+    speed: Vec<String>,
+    /// Only merges these GameIds of the sources, comma separated or repeated
+    #[structopt(long, use_delimiter = true, require_delimiter = true)]
+    only_games: Vec<GameId>,
+    /// Leaves out these GameIds of the sources, comma separated or repeated
+    #[structopt(long, use_delimiter = true, require_delimiter = true)]
+    exclude_games: Vec<GameId>,
+    /// Only merges completed games - leaves out abandoned & unfinished ones without a victory
+    #[structopt(long)]
+    completed_only: bool,
+    /// Only merges games last played since a date (`YYYY-MM-DD`), Unix timestamp or - with `auto` - the
+    /// newest game already in the target
+    #[structopt(long)]
+    since: Option<dates::Since>,
+    /// Only merges games last played until a date (`YYYY-MM-DD`, included) or Unix timestamp (excluded)
+    #[structopt(long, parse(try_from_str = dates::parse_until))]
+    until: Option<i64>,
+    /// Copies the tables mods like Better Report Screen add, with the rows belonging to copied games
+    #[structopt(long)]
+    copy_extra_tables: bool,
+    /// Rolls back a game failing to copy - e.g. malformed - & goes on with the next instead of aborting
+    /// the merge; the skipped GameIds are listed at the end
+    #[structopt(long)]
+    skip_errors: bool,
+    /// Renames a map script in the target, e.g. `OldScript.lua=Continents` (may be repeated)
+    #[structopt(long, number_of_values = 1)]
+    rename_map: Vec<maps::Rename>,
+    /// Creates the target from the bundled schema & merges all sources into it, instead of building on a
+    /// copy of the first source with its bloat & possible corruption
+    #[structopt(long)]
+    fresh_target: bool,
+    /// Streams newline-delimited JSON events of the merge's progress to a file (`-` for stdout)
+    #[structopt(long, parse(from_os_str))]
+    events: Option<std::path::PathBuf>,
+    /// Writes the summary printed at the end of the merge as JSON to a file (`-` for stdout)
+    #[structopt(long, parse(from_os_str))]
+    summary_json: Option<std::path::PathBuf>,
+    /// Writes an HTML report with interactive charts of the merged target, `-` for stdout
+    #[cfg(feature = "charts")]
+    #[structopt(long, parse(from_os_str))]
+    report: Option<std::path::PathBuf>,
+}
+
+// Parsed once - the size of `Merge` doesn't matter
+#[allow(clippy::large_enum_variant)]
+#[derive(StructOpt)]
+enum Command {
+    /// Merges the sources into the target - the same as without a subcommand
+    Merge(MergeArgs),
+    /// Checks Hall-of-Fame databases like doctor - tables, references between rows, schema, locks & integrity -
+    /// failing if one doesn't pass
+    Validate {
+        #[structopt(parse(from_os_str), required = true)]
+        paths: Vec<std::path::PathBuf>,
+    },
+    /// Copies a database consistently, also while the game has it open
+    Backup {
+        #[structopt(parse(from_os_str))]
+        db: std::path::PathBuf,
+        /// The copy - by default `<db>.<epoch seconds>.bak` next to it
+        #[structopt(long, parse(from_os_str))]
+        out: Option<std::path::PathBuf>,
+    },
+    /// Compares the games of two databases: Those only in either - which a merge of it into the other
+    /// would copy - & those in both that differ
+    Diff {
+        #[structopt(parse(from_os_str))]
+        a: std::path::PathBuf,
+        #[structopt(parse(from_os_str))]
+        b: std::path::PathBuf,
+        /// When games are the same, as for the merge: exact, fingerprint or game-id
+        #[structopt(long, default_value = "exact", possible_values = &["exact", "fingerprint", "game-id"])]
+        dedupe_by: dedupe::Strategy,
+    },
+    /// Installs SQL views for analysis: v_GamePlayers, v_GameResults & v_LeaderStats
+    CreateViews {
+        #[structopt(parse(from_os_str))]
+        db: std::path::PathBuf,
+    },
+    /// Shows which leader & victory type combinations the local player has won
+    Matrix {
+        #[structopt(parse(from_os_str))]
+        db: std::path::PathBuf,
+        #[structopt(long, default_value = "text", possible_values = &["text", "tsv", "markdown", "svg"])]
+        format: String,
+        /// Writes the matrix to a file instead of stdout (`-`)
+        #[structopt(long, alias = "output", parse(from_os_str))]
+        out: Option<std::path::PathBuf>,
+        /// Background color of leaders won with every victory type in SVG output
+        #[structopt(long, default_value = "#fff59d")]
+        highlight: String,
+        /// Doesn't highlight leaders won with every victory type
+        #[structopt(long)]
+        no_highlight: bool,
+        /// Only counts games of this profile
+        #[structopt(long)]
+        profile: Option<String>,
+    },
+    /// Checks game installations, Hall-of-Fame files, permissions, schemas & disk space
+    Doctor {
+        /// Additional database files to check
+        #[structopt(parse(from_os_str))]
+        paths: Vec<std::path::PathBuf>,
+    },
+    /// Writes the reference graph of a game's rows in Graphviz' dot format
+    DebugGraph {
+        #[structopt(parse(from_os_str))]
+        db: std::path::PathBuf,
+        #[structopt(long = "game")]
+        game_id: GameId,
+        /// Writes the graph to a file instead of stdout (`-`)
+        #[structopt(long, alias = "output", parse(from_os_str))]
+        out: Option<std::path::PathBuf>,
+    },
+    /// Charts a per-turn statistic of a game's players in the terminal, or as SVG or PNG file
+    #[cfg(feature = "charts")]
+    Graph {
+        #[structopt(parse(from_os_str))]
+        db: std::path::PathBuf,
+        #[structopt(long = "game")]
+        game_id: GameId,
+        /// The game's data set to chart, e.g. SCORE
+        #[structopt(long, alias = "dataset", default_value = "SCORE")]
+        stat: String,
+        /// Draws with ASCII characters instead of Unicode blocks
+        #[structopt(long)]
+        ascii: bool,
+        /// Writes a line chart to this file instead, SVG or - with `rsvg-convert` installed - PNG by its extension
+        #[structopt(long, alias = "output", parse(from_os_str), conflicts_with = "ascii")]
+        out: Option<std::path::PathBuf>,
+    },
+    /// Dumps games with all their rows as portable SQL, applicable to another database with sqlite3, as JSON or
+    /// as CSV
+    Export {
+        #[structopt(parse(from_os_str))]
+        db: std::path::PathBuf,
+        /// File to write, the same as `--out`
+        #[structopt(parse(from_os_str), conflicts_with = "out")]
+        file: Option<std::path::PathBuf>,
+        /// GameIds to export, comma separated or repeated - all games if missing
+        #[structopt(long = "game-ids", use_delimiter = true, require_delimiter = true)]
+        game_ids: Vec<GameId>,
+        /// Export format: sql, json, csv - one file per table into the directory given as file - or games-csv,
+        /// the games with their local player
+        #[structopt(long, default_value = "sql")]
+        format: export::Format,
+        /// File to write, stdout if missing or `-`
+        #[structopt(long, alias = "output", parse(from_os_str))]
+        out: Option<std::path::PathBuf>,
+    },
+    /// Merges the games of a JSON export into a database - skipping those it has - after backing it up
+    Import {
+        #[structopt(parse(from_os_str))]
+        db: std::path::PathBuf,
+        /// The export, as `export --format json` writes it
+        #[structopt(parse(from_os_str))]
+        file: std::path::PathBuf,
+        /// When games are the same, as for the merge: exact, fingerprint or game-id
+        #[structopt(long, default_value = "exact", possible_values = &["exact", "fingerprint", "game-id"])]
+        dedupe_by: dedupe::Strategy,
+        /// Waits for the game to release the database instead of refusing to change it
+        #[structopt(long)]
+        wait: bool,
+    },
+    /// Writes a game into a new Hall-of-Fame database of its own - with its players, objects, data points,
+    /// graph data & the rulesets - for sending a single game
+    Extract {
+        #[structopt(parse(from_os_str))]
+        db: std::path::PathBuf,
+        #[structopt(long = "game")]
+        game_id: GameId,
+        /// The database to create
+        #[structopt(parse(from_os_str))]
+        out: std::path::PathBuf,
+    },
+    /// Bundles games with all their rows & a manifest into one gzip-compressed file, to send them to someone
+    Pack {
+        #[structopt(parse(from_os_str))]
+        db: std::path::PathBuf,
+        /// The pack to write, e.g. `games.hofpack.gz`
+        #[structopt(parse(from_os_str))]
+        out: std::path::PathBuf,
+        /// GameIds to pack, comma separated or repeated - all games if missing
+        #[structopt(long = "game-ids", use_delimiter = true, require_delimiter = true)]
+        game_ids: Vec<GameId>,
+    },
+    /// Merges the games of a pack into a database - skipping those it has - after backing it up
+    Unpack {
+        #[structopt(parse(from_os_str))]
+        db: std::path::PathBuf,
+        #[structopt(parse(from_os_str))]
+        pack: std::path::PathBuf,
+        /// Only lists the games of the pack
+        #[structopt(long)]
+        list: bool,
+        /// When games are the same, as for the merge: exact, fingerprint or game-id
+        #[structopt(long, default_value = "exact", possible_values = &["exact", "fingerprint", "game-id"])]
+        dedupe_by: dedupe::Strategy,
+        /// Waits for the game to release the database instead of refusing to change it
+        #[structopt(long)]
+        wait: bool,
+    },
+    /// Writes a self-contained HTML page of a database: Summary, leaderboard, victory & win-rate charts,
+    /// the score over turns of each game & a table of all games
+    #[cfg(feature = "charts")]
+    Report {
+        #[structopt(parse(from_os_str))]
+        db: std::path::PathBuf,
+        /// The page to write, `-` for stdout
+        #[structopt(parse(from_os_str))]
+        out: std::path::PathBuf,
+    },
+    /// Draws a minimap of a game's cities, wonders & other landmarks as SVG
+    #[cfg(feature = "charts")]
+    Map {
+        #[structopt(parse(from_os_str))]
+        db: std::path::PathBuf,
+        #[structopt(long = "game")]
+        game_id: GameId,
+        /// File to write, stdout if missing or `-`
+        #[structopt(long, alias = "output", parse(from_os_str))]
+        out: Option<std::path::PathBuf>,
+    },
+    /// Lists the games - GameId, date, local leader & civilization, map, speed, turns & victory - e.g. to
+    /// pick them with `--only-games`
+    List {
+        #[structopt(parse(from_os_str))]
+        db: std::path::PathBuf,
+        /// Order of the games: date - most recent first -, game, leader, map, turns - longest first - or victory
+        #[structopt(
+            long,
+            default_value = "date",
+            possible_values = &["date", "game", "leader", "map", "turns", "victory"]
+        )]
+        sort: show::Sort,
+        /// Lists only the first games in this order
+        #[structopt(long, alias = "count")]
+        limit: Option<i64>,
+        /// Output format: text, tsv, csv or json
+        #[structopt(long, default_value = "text")]
+        format: table::Format,
+    },
+    /// Finds games by leader, civilization, map or victory - by the game's keys like `LEADER_TRAJAN` or
+    /// names like `trajan`, ignoring case - printing their GameIds for `--only-games`
+    Search {
+        #[structopt(parse(from_os_str))]
+        db: std::path::PathBuf,
+        /// Terms each matching the local player's leader or civilization, the map or the victory
+        terms: Vec<String>,
+        /// Only games the local player played as this leader (may be repeated)
+        #[structopt(long, number_of_values = 1)]
+        leader: Vec<String>,
+        /// Only games the local player played as this civilization (may be repeated)
+        #[structopt(long, number_of_values = 1)]
+        civ: Vec<String>,
+        /// Only games on this map script (may be repeated)
+        #[structopt(long, number_of_values = 1)]
+        map: Vec<String>,
+        /// Only games ending in this victory type (may be repeated)
+        #[structopt(long, number_of_values = 1)]
+        victory: Vec<String>,
+        /// Prints only the GameIds, comma separated
+        #[structopt(long)]
+        ids: bool,
+    },
+    /// Shows a game with its seeds, setup & save file
+    Show {
+        #[structopt(parse(from_os_str))]
+        db: std::path::PathBuf,
+        game_id: GameId,
+    },
+    /// Finds the save files belonging to the games & records them in the database
+    LinkSaves {
+        #[structopt(parse(from_os_str))]
+        db: std::path::PathBuf,
+        /// The game's Saves directory, searched recursively
+        #[structopt(parse(from_os_str))]
+        saves_dir: std::path::PathBuf,
+        /// Maximum hours between a save's modification & the game's LastPlayed
+        #[structopt(long, default_value = "24")]
+        window_hours: i64,
+    },
+    /// Records of the archive - fastest victories, highest score, first Deity win & more - as Markdown
+    Milestones {
+        #[structopt(parse(from_os_str))]
+        db: std::path::PathBuf,
+    },
+    /// A shareable page ranking the games: Top scores, the fastest victory of each type & each leader's bests
+    Leaderboard {
+        #[structopt(parse(from_os_str))]
+        db: std::path::PathBuf,
+        #[structopt(long, default_value = "markdown", possible_values = &["markdown", "html"])]
+        format: leaderboard::Format,
+        /// Number of top scores listed
+        #[structopt(long, default_value = "10")]
+        limit: i64,
+        /// Writes the page to a file instead of stdout (`-`)
+        #[structopt(long, alias = "output", parse(from_os_str))]
+        out: Option<std::path::PathBuf>,
+    },
+    /// The local player's fastest victories - fewest turns first - by victory type, speed, difficulty & ruleset
+    Top {
+        #[structopt(parse(from_os_str))]
+        db: std::path::PathBuf,
+        /// Only wins of this victory type, e.g. `VICTORY_SCIENCE` or `science` (may be repeated)
+        #[structopt(long, number_of_values = 1)]
+        victory: Vec<String>,
+        /// Only games at this speed, e.g. `GAMESPEED_QUICK` or `quick` (may be repeated)
+        #[structopt(long, number_of_values = 1)]
+        speed: Vec<String>,
+        /// Only games at this difficulty, e.g. `DIFFICULTY_DEITY` or `deity` (may be repeated)
+        #[structopt(long, number_of_values = 1)]
+        difficulty: Vec<String>,
+        /// Only games with this ruleset, e.g. `RULESET_EXPANSION_2` or `expansion_2` (may be repeated)
+        #[structopt(long, number_of_values = 1)]
+        ruleset: Vec<String>,
+        /// Number of wins listed
+        #[structopt(long, alias = "count", default_value = "10")]
+        limit: i64,
+        /// Output format: text, tsv, csv or json
+        #[structopt(long, default_value = "text")]
+        format: table::Format,
+    },
+    /// A leader's games, wins, average & best score, fastest win & games per difficulty across all machines
+    Career {
+        #[structopt(parse(from_os_str))]
+        db: std::path::PathBuf,
+        /// The leader, e.g. `LEADER_TRAJAN`, `trajan` or the leader's name
+        #[structopt(long)]
+        leader: String,
+        /// Output format: text, tsv, csv or json
+        #[structopt(long, default_value = "text")]
+        format: table::Format,
+    },
+    /// Copies the games missing in either database into the other, after backing up both
+    Sync {
+        #[structopt(parse(from_os_str))]
+        a: std::path::PathBuf,
+        #[structopt(parse(from_os_str))]
+        b: std::path::PathBuf,
+        /// Finds the games both databases have in one pass up front - for databases started from one
+        /// Hall of Fame - & only checks the others one by one
+        #[structopt(long)]
+        shared_history: bool,
+        /// Waits for the game to release a database it holds open instead of refusing to sync
+        #[structopt(long)]
+        wait: bool,
+    },
+    /// Lists the merged games with the source, host & time of the run that copied them
+    Provenance {
+        #[structopt(parse(from_os_str))]
+        db: std::path::PathBuf,
+        /// Only games merged on this host
+        #[structopt(long)]
+        host: Option<String>,
+        /// Only games from sources whose path contains this
+        #[structopt(long)]
+        source: Option<String>,
+        /// Output format: text, tsv, csv or json
+        #[structopt(long, default_value = "text")]
+        format: table::Format,
+    },
+    /// Deletes the duplicate games within a database - with all their rows - keeping one of each, after
+    /// backing it up
+    Dedupe {
+        #[structopt(parse(from_os_str))]
+        db: std::path::PathBuf,
+        /// When games are duplicates: `fingerprint` - versions of one game, the one played last is kept - or
+        /// `exact` - identical copies, the first is kept
+        #[structopt(long, default_value = "fingerprint", possible_values = &["exact", "fingerprint"])]
+        dedupe_by: dedupe::Strategy,
+        /// Only lists the duplicates
+        #[structopt(long)]
+        dry_run: bool,
+        /// Waits for the game to release the database instead of refusing to change it
+        #[structopt(long)]
+        wait: bool,
+    },
+    /// Fixes rows referencing games, objects or players that don't exist - re-linking them to the game of
+    /// an object they reference, clearing the reference or deleting them - after backing up the database
+    Repair {
+        #[structopt(parse(from_os_str))]
+        db: std::path::PathBuf,
+        /// Only reports what would change
+        #[structopt(long)]
+        dry_run: bool,
+        /// Waits for the game to release the database instead of refusing to change it
+        #[structopt(long)]
+        wait: bool,
+    },
+    /// Deletes the games matching all criteria given - with all their rows - after backing up the database
+    Prune {
+        #[structopt(parse(from_os_str))]
+        db: std::path::PathBuf,
+        /// Games last played longer ago than an age - e.g. `30d`, `6w`, `3m` or `2y` - or before a date
+        #[structopt(long, parse(try_from_str = dates::parse_older_than))]
+        older_than: Option<i64>,
+        /// Games ending in this victory type, e.g. `science`, or `none` for those without a victory (may be
+        /// repeated)
+        #[structopt(long, number_of_values = 1)]
+        victory: Vec<String>,
+        /// Games the local player played as this leader, e.g. `LEADER_GANDHI` (may be repeated)
+        #[structopt(long, number_of_values = 1)]
+        leader: Vec<String>,
+        /// Games on this map script, e.g. `Continents` (may be repeated)
+        #[structopt(long, number_of_values = 1)]
+        map: Vec<String>,
+        /// Games without a local human player - AI benchmarks & autoplay
+        #[structopt(long)]
+        ai_only: bool,
+        /// These GameIds, comma separated or repeated
+        #[structopt(long, use_delimiter = true, require_delimiter = true)]
+        games: Vec<GameId>,
+        /// Shrinks the file afterwards by rebuilding it without the freed pages
+        #[structopt(long)]
+        vacuum: bool,
+        /// Only lists the games that would be deleted
+        #[structopt(long)]
+        dry_run: bool,
+        /// Waits for the game to release the database instead of refusing to change it
+        #[structopt(long)]
+        wait: bool,
+    },
+    /// Removes the games a merge into the database copied - with all their rows - & restores the
+    /// games it replaced or dropped
+    Undo {
+        #[structopt(parse(from_os_str))]
+        db: std::path::PathBuf,
+        /// The merge run to undo, as listed by `provenance` - the most recent one unless given
+        #[structopt(long)]
+        run: Option<history::RunId>,
+        /// Waits for the game to release the database instead of refusing to change it
+        #[structopt(long)]
+        wait: bool,
+    },
+    /// Games & win rates of the local player, broken down by start era, leader & DLC content, and a
+    /// leaderboard of the wins normalized by game speed & difficulty
+    Stats {
+        /// Several databases are counted as one, each game found in more than one counted once
+        #[structopt(parse(from_os_str), required = true)]
+        dbs: Vec<std::path::PathBuf>,
+        /// When games of several databases are the same, as for the merge: exact, fingerprint or game-id
+        #[structopt(long, default_value = "exact", possible_values = &["exact", "fingerprint", "game-id"])]
+        dedupe_by: dedupe::Strategy,
+        /// Only this breakdown (may be repeated): start-era, leader, victory, speed, difficulty, map or content
+        #[structopt(long, number_of_values = 1)]
+        by: Vec<stats::Breakdown>,
+        /// Output format: text, tsv, csv or json
+        #[structopt(long, default_value = "text")]
+        format: table::Format,
+        /// Leaves out games with leaders or rulesets outside the official content
+        #[structopt(long)]
+        exclude_modded: bool,
+        /// Only the Elo ratings of the leaders - played by you or the AI - evolving over all games, weighing
+        /// wins against the AI by difficulty
+        #[structopt(long, conflicts_with = "by")]
+        ratings: bool,
+        /// Only the games played & won per month, with a histogram in text format
+        #[structopt(long, conflicts_with_all = &["by", "ratings"])]
+        timeline: bool,
+    },
+    /// Interactive prompt to list, show, chart & merge games, keeping the database open between commands
+    #[cfg(feature = "shell")]
+    Shell {
+        #[structopt(parse(from_os_str))]
+        db: std::path::PathBuf,
+    },
+    /// Runs the merge given after `--` periodically in the background - as systemd user timer,
+    /// launchd agent or Windows scheduled task
+    #[cfg(feature = "service")]
+    InstallService {
+        /// Minutes between two merges
+        #[structopt(long, default_value = "60")]
+        every: u64,
+        /// Arguments of the merge, e.g. `-- --archive ~/hof ~/Shared/HallofFame.sqlite`
+        #[structopt(raw(true), required = true)]
+        merge_args: Vec<String>,
+    },
+    /// Removes the background merge registered by install-service
+    #[cfg(feature = "service")]
+    UninstallService,
+    /// Lists the optional capabilities & whether this build includes them
+    Features,
+    /// Attributes games to the people sharing one local player on a PC
+    Profile {
+        #[structopt(subcommand)]
+        action: ProfileAction,
+    },
+}
+
+/// Optional capabilities - cargo features - with whether they were compiled in.
+const FEATURES: &[(&str, bool, &str)] = &[
+    ("remote", cfg!(feature = "remote"), "URL sources, downloaded with curl & verified by SHA-256"),
+    ("charts", cfg!(feature = "charts"), "HTML report (--report & report), the graph & map subcommands"),
+    ("shell", cfg!(feature = "shell"), "Interactive shell subcommand"),
+    ("service", cfg!(feature = "service"), "install-service & uninstall-service subcommands"),
+    ("sqlcipher", cfg!(feature = "sqlcipher"), "SQLCipher-encrypted databases (--passphrase)"),
+];
+
+#[derive(StructOpt)]
+enum ProfileAction {
+    /// Assigns the games matching all given criteria to a profile
+    Assign {
+        #[structopt(parse(from_os_str))]
+        db: std::path::PathBuf,
+        profile: String,
+        /// GameIds to assign, comma separated or repeated
+        #[structopt(long = "game", use_delimiter = true, require_delimiter = true)]
+        game_ids: Vec<GameId>,
+        /// Only games the local player played with this LeaderType or leader name
+        #[structopt(long)]
+        leader: Option<String>,
+        /// Only games last played on or after this date (YYYY-MM-DD)
+        #[structopt(long, parse(try_from_str = dates::parse_date))]
+        since: Option<i64>,
+        /// Only games last played before this date (YYYY-MM-DD)
+        #[structopt(long, parse(try_from_str = dates::parse_date))]
+        until: Option<i64>,
+        /// Asks for each matching game before assigning it
+        #[structopt(long)]
+        interactive: bool,
+    },
+    /// Lists the profiles with their number of games
+    List {
+        #[structopt(parse(from_os_str))]
+        db: std::path::PathBuf,
+        /// Output format: text, tsv, csv or json
+        #[structopt(long, default_value = "text")]
+        format: table::Format,
+    },
+}
+
+/// What happened to the games of a single source during the merge.
+#[derive(Clone, Debug, Default)]
+struct SourceSummary {
+    games_scanned: i32,
+    games_copied: i32,
+    games_skipped: i32,
+    games_replaced: i32,
+    games_older: i32,
+    games_newer: i32,
+    /// Duplicates found up front with `--shared-history`, also counted as skipped
+    games_shared: i32,
+    /// Games rolled back on an error with `--skip-errors`
+    games_failed: Vec<GameId>,
+    games_excluded: i32,
+    /// Games without a local human player left out with `--skip-ai-only`
+    games_ai_only: i32,
+    /// Games not passing `--victory`, `--completed-only` & the other filters
+    games_filtered: i32,
+    /// Games answered with no - or left after quitting - with `--interactive`
+    games_declined: i32,
+    rulesets_added: usize,
+    /// Rows copied of the tables mods added, with `--copy-extra-tables`
+    extra_rows_copied: i64,
+    /// Rows copied of the `COUNTED_TABLES`
+    rows_copied: [i64; 3],
+}
+
+/// How games are selected & what is recorded while merging a source.
+#[derive(Default)]
+struct MergeOptions {
+    summary_tables: bool,
+    merge_log: bool,
+    /// LastPlayed of the oldest games considered
+    since: Option<i64>,
+    /// LastPlayed the games considered were played before
+    until: Option<i64>,
+    skip_errors: bool,
+    copy_extra_tables: bool,
+    explain_dups: bool,
+    exclude_modded: bool,
+    skip_ai_only: bool,
+    filters: filters::Filters,
+    interactive: Option<interactive::Prompt>,
+    rename_maps: Vec<maps::Rename>,
+    dedupe_by: dedupe::Strategy,
+    shared_history: bool,
+    on_conflict: conflicts::Keep,
+    /// Journaled as the database of replaced target games
+    target_path: std::path::PathBuf,
+    events: events::Events,
 }
 
 type GameId = i64;
 
+/// Tables every Hall-of-Fame database has.
+const EXPECTED_TABLES: [&str; 11] = [
+    "Migrations",
+    "Rulesets",
+    "RulesetTypes",
+    "Games",
+    "GamePlayers",
+    "GameObjects",
+    "RulesetDataPointValues",
+    "GameDataPointValues",
+    "ObjectDataPointValues",
+    "DataSets",
+    "DataSetValues",
+];
+
+/// Joins the local player (`lp`) to the games (`g`) through the player's GameObject.
+const LOCAL_PLAYER_JOIN: &str = "LEFT JOIN (SELECT go.GameId, gp.* FROM GamePlayers gp \
+    JOIN GameObjects go ON go.ObjectId = gp.PlayerObjectId WHERE gp.IsLocal GROUP BY go.GameId) lp ON lp.GameId = g.GameId";
+
 #[derive(Deserialize, Serialize, Debug)]
 struct GameDataPointValue {
     #[serde(alias = "DataPoint")]
@@ -36,54 +816,6 @@ struct GameDataPointValue {
     value_numeric: Option<i32>,
 }
 
-#[derive(Deserialize, Serialize, Debug)]
-struct GamePlayer {
-    #[serde(alias = "PlayerObjectId")]
-    player_object_id: i32,
-    #[serde(alias = "IsLocal")]
-    is_local: bool,
-    #[serde(alias = "IsAI")]
-    is_ai: bool,
-    #[serde(alias = "IsMajor")]
-    is_major: bool,
-    #[serde(alias = "LeaderType")]
-    leader_type: String,
-    #[serde(alias = "LeaderName")]
-    leader_name: Option<String>,
-    #[serde(alias = "CivilizationType")]
-    civilization_type: Option<String>,
-    #[serde(alias = "CivilizationName")]
-    civilization_name: Option<String>,
-    #[serde(alias = "DifficultyType")]
-    difficulty_type: Option<String>,
-    #[serde(alias = "Score")]
-    score: i32,
-    #[serde(alias = "PlayerId")]
-    player_id: i32,
-    #[serde(alias = "TeamId")]
-    team_id: i32,
-}
-
-#[derive(Deserialize, Serialize, Debug)]
-struct GameObject {
-    #[serde(alias = "ObjectId")]
-    object_id: i32,
-    #[serde(alias = "GameId")]
-    game_id: GameId,
-    #[serde(alias = "PlayerObjectId")]
-    player_object_id: Option<i32>,
-    #[serde(alias = "Type")]
-    _type: String,
-    #[serde(alias = "Name")]
-    name: Option<String>,
-    #[serde(alias = "PlotIndex")]
-    plot_index: Option<i32>,
-    #[serde(alias = "ExtraData")]
-    extra_data: Option<String>,
-    #[serde(alias = "Icon")]
-    icon: Option<String>,
-}
-
 #[derive(Deserialize, Serialize, Debug)]
 struct Game {
     #[serde(alias = "GameId")]
@@ -112,8 +844,11 @@ struct Game {
     last_played: i32,
 }
 
-fn open_db(path: &std::path::PathBuf) -> Result<Connection> {
-    let con = Connection::open(path)?;
+fn open_db(path: &std::path::PathBuf) -> std::result::Result<Connection, Box<dyn std::error::Error>> {
+    let con = cipher::open(path)?;
+    if schema::check() == schema::Check::Off {
+        return Ok(con);
+    }
 
     let mut stmt = con.prepare("SELECT name FROM sqlite_master where type='table'")?;
     let tables = stmt.query_map(NO_PARAMS, |row| {
@@ -121,8 +856,7 @@ fn open_db(path: &std::path::PathBuf) -> Result<Connection> {
         Ok(x)
     })?;
 
-    let mut expected_tables = hashset! {"Migrations", "Rulesets", "RulesetTypes", "Games", "GamePlayers",
-    "GameObjects", "RulesetDataPointValues", "GameDataPointValues", "ObjectDataPointValues", "DataSets", "DataSetValues"};
+    let mut expected_tables: HashSet<&str> = EXPECTED_TABLES.iter().copied().collect();
 
     for table in tables {
         let t: &str = &table?;
@@ -131,67 +865,137 @@ fn open_db(path: &std::path::PathBuf) -> Result<Connection> {
     }
     drop(stmt);
 
-    if expected_tables.len() > 0 {
-        panic!("Didn't find expected table(s) {:?}", expected_tables);
+    if !expected_tables.is_empty() {
+        let mut missing: Vec<&str> = expected_tables.into_iter().collect();
+        missing.sort_unstable();
+        if schema::check() == schema::Check::Strict {
+            return Err(format!(
+                "{:?} lacks the table(s) {:?} - pass --schema-check lenient to use what it has",
+                path, missing
+            )
+            .into());
+        }
+        warn!("{:?} lacks the table(s) {:?}, using what it has", path, missing);
+        schema::create_missing(&con, true)?;
     }
 
     info!("Verification of {:?} successful", &path);
     Ok(con)
 }
 
-fn insert_game_if_not_exists(con: &Connection, game: &Game) -> Result<i64> {
-    let mut stmt = con.prepare("INSERT INTO Games (Ruleset, GameMode, TurnCount, GameSpeedType, MapSizeType, Map, StartEraType, StartTurn, VictorTeamId, VictoryType, LastPlayed)\
-    SELECT ?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11\
-    WHERE NOT EXISTS(SELECT 1 FROM Games WHERE Ruleset = ?1 AND GameMode = ?2 AND TurnCount = ?3 AND GameSpeedType = ?4 AND MapSizeType = ?5 AND Map = ?6 AND StartEraType = ?7 AND StartTurn = ?8 AND VictorTeamId = ?9 AND VictoryType = ?10 AND LastPlayed = ?11)")?;
-
-    let params = params![
-        game.rule_set,
-        game.game_mode,
-        game.turn_count,
-        game.game_speed_type,
-        game.map_size_type,
-        game.map,
-        game.start_era_type,
-        game.start_turn,
-        game.victor_team_id,
-        game.victory_type,
-        game.last_played,
-    ];
-    debug!("SQL: {:?}", stmt);
-    let row_id = stmt.insert(params)?;
-    debug!("{}", row_id);
+fn load_game(con: &Connection, game_id: GameId) -> std::result::Result<Game, Box<dyn std::error::Error>> {
+    let mut stmt = con.prepare("SELECT * FROM Games WHERE GameId = ?")?;
+    let game = from_rows::<Game>(stmt.query(params![game_id])?)
+        .next()
+        .ok_or(rusqlite::Error::QueryReturnedNoRows)??;
+    Ok(game)
+}
+
+/// Games without a local human player - AI benchmarks & autoplay.
+fn ai_only_games(con: &Connection) -> Result<HashSet<GameId>> {
+    let mut stmt = con.prepare(
+        "SELECT g.GameId FROM Games g WHERE NOT EXISTS (SELECT 1 FROM GamePlayers gp \
+         JOIN GameObjects go ON go.ObjectId = gp.PlayerObjectId WHERE go.GameId = g.GameId AND gp.IsLocal AND NOT gp.IsAI)",
+    )?;
+    let games = stmt.query_map(NO_PARAMS, |row| row.get(0))?;
+    games.collect()
+}
+
+/// Copies a game's Games row under a new GameId - with the game's map, as
+/// it may have been renamed.
+fn insert_game(source_connection: &Connection, target_connection: &Connection, game: &Game) -> Result<i64> {
+    let mut copier = Copier::new(source_connection, target_connection, "Games", Some("GameId"), "WHERE t.GameId = ?")?;
+    let mut row = copier
+        .rows(params![game.game_id])?
+        .pop()
+        .ok_or(rusqlite::Error::QueryReturnedNoRows)?;
+    row.set("Map", game.map.clone());
+    let row_id = copier.insert(&row)?;
+    debug!("Inserted game {} as {}", game.game_id, row_id);
     Ok(row_id)
 }
 
+/// Removes a game with all its objects, players, data points & data sets
+/// as well as the rows the merge tables hold for it.
+fn delete_game(con: &Connection, game_id: GameId) -> Result<()> {
+    extra_tables::delete_game(con, game_id)?;
+    for table in &["MergeProfiles", "MergeSaveLinks", "MergeProvenance", "MergeLog"] {
+        if !schema::columns(con, table)?.is_empty() {
+            con.execute(&format!("DELETE FROM {} WHERE GameId = ?1", table), params![game_id])?;
+        }
+    }
+    // Referencing rows first, the game itself last
+    for table in export::TABLES.iter().rev() {
+        con.execute(&format!("DELETE FROM {} WHERE {}", table.name, table.filter), params![game_id])?;
+    }
+    debug!("Deleted game {}", game_id);
+    Ok(())
+}
+
+/// Deletes the target's games that the ancestor has but at least one source
+/// lacks - those were deleted there on purpose, not just never synced.
+fn drop_deleted_games(
+    ancestor: &std::path::PathBuf,
+    sources: &[&std::path::PathBuf],
+    target_connection: &Connection,
+    target_path: &std::path::Path,
+    run: history::RunId,
+    mut journal: Option<&mut conflicts::Journal>,
+) -> std::result::Result<usize, Box<dyn std::error::Error>> {
+    let mut deleted = HashSet::new();
+    let ancestor_games = cache::game_hashes(&open_db(ancestor)?)?;
+    for source in sources {
+        let games = cache::game_hashes(&open_db(source)?)?;
+        deleted.extend(ancestor_games.difference(&games).copied());
+    }
+    if deleted.is_empty() {
+        return Ok(0);
+    }
+
+    let mut stmt = target_connection.prepare("SELECT * FROM Games")?;
+    let doomed: Vec<GameId> = from_rows::<Game>(stmt.query(NO_PARAMS)?)
+        .filter_map(|game| game.ok())
+        .filter(|game| deleted.contains(&cache::game_hash(game)))
+        .map(|game| game.game_id)
+        .collect();
+    for game_id in &doomed {
+        info!("Dropping game {}: Deleted since {:?}", game_id, &ancestor);
+        if let Some(journal) = journal.as_mut() {
+            let reason = format!("Dropped: Deleted from a source since {:?}", &ancestor);
+            journal.record(target_connection, target_path, *game_id, &reason)?;
+        }
+        history::record_replaced(target_connection, run, *game_id)?;
+        delete_game(target_connection, *game_id)?;
+    }
+    Ok(doomed.len())
+}
+
 fn copy_game_object(
     source_connection: &Connection,
     game_id: GameId,
     target_connection: &Connection,
-    new_game_id: GameId,
-    void: &i32,
+    ids: &mut IdMap,
+    object_id: i64,
 ) -> Result<i64, Box<dyn std::error::Error>> {
-    debug!("Copying GameObject {} for game {}", &void, &game_id);
-
-    let mut stmt = source_connection.prepare("SELECT ObjectId, GameId, PlayerObjectId, Type, Name, PlotIndex, ExtraData, Icon FROM GameObjects WHERE GameId = ? AND ObjectId = ?")?;
-
-    let go = stmt
-        .query_and_then(params![game_id, void], from_row::<GameObject>)?
-        .next()
-        .ok_or(rusqlite::Error::QueryReturnedNoRows)??;
+    debug!("Copying GameObject {} for game {}", &object_id, &game_id);
 
-    let mut stmt = target_connection.prepare("INSERT INTO GameObjects (GameId, PlayerObjectId, Type, Name, PlotIndex, ExtraData, Icon) VALUES (?, ?, ?, ?, ?, ?, ?)")?;
+    let mut copier = Copier::new(
+        source_connection,
+        target_connection,
+        "GameObjects",
+        Some("ObjectId"),
+        "WHERE t.GameId = ? AND t.ObjectId = ?",
+    )?;
+    let mut go = copier
+        .rows(params![game_id, object_id])?
+        .pop()
+        .ok_or(rusqlite::Error::QueryReturnedNoRows)?;
+    go.set("GameId", ids.get(Table::Games, game_id));
+    go.set("PlayerObjectId", ids.remap(Table::GameObjects, go.id("PlayerObjectId")));
+    let row_id = copier.insert(&go)?;
 
-    let row_id = stmt.insert(params![
-        new_game_id,
-        go.player_object_id,
-        go._type,
-        go.name,
-        go.plot_index,
-        go.extra_data,
-        go.icon,
-    ])?;
-
-    info!("Copied GameObject {} as {}", &void, &row_id);
+    ids.insert(Table::GameObjects, object_id, row_id);
+    info!("Copied GameObject {} as {}", &object_id, &row_id);
     Ok(row_id)
 }
 
@@ -199,175 +1003,2197 @@ fn copy_game_objects(
     source_connection: &Connection,
     game_id: GameId,
     target_connection: &Connection,
-    new_game_id: GameId,
-    exclude_object_ids: Vec<i32>,
+    ids: &mut IdMap,
 ) -> Result<i32, Box<dyn std::error::Error>> {
+    let exclude_object_ids = ids.copied(Table::GameObjects);
     debug!("Copying GameObjects for game {} skipping {:?}", &game_id, &exclude_object_ids);
 
-    let mut stmt = source_connection.prepare("SELECT ObjectId, GameId, PlayerObjectId, Type, Name, PlotIndex, ExtraData, Icon FROM GameObjects WHERE GameId = ? AND ObjectId NOT IN (?)")?;
+    // One placeholder per excluded id - SQLite accepts the empty `NOT IN ()` of no exclusions
+    let mut copier = Copier::new(
+        source_connection,
+        target_connection,
+        "GameObjects",
+        Some("ObjectId"),
+        &format!(
+            "WHERE t.GameId = ? AND t.ObjectId NOT IN ({})",
+            vec!["?"; exclude_object_ids.len()].join(", ")
+        ),
+    )?;
     let mut go_counter = 0;
-    let excluded_ids: Vec<String> = exclude_object_ids.iter().map(|&x| x.to_string()).collect();
-    let rows_iter = from_rows::<GameObject>(stmt.query(params![game_id, excluded_ids.join(",")])?);
+    let values = std::iter::once(game_id).chain(exclude_object_ids.iter().copied());
 
-    for game_object in rows_iter {
+    for mut go in copier.rows(values)? {
         go_counter += 1;
-        let go = game_object?;
-
-        let mut stmt = target_connection.prepare("INSERT INTO GameObjects (GameId, PlayerObjectId, Type, Name, PlotIndex, ExtraData, Icon) VALUES (?, ?, ?, ?, ?, ?, ?)")?;
-
-        let goid: Option<i64>;
-        if go.player_object_id.is_some() {
-            goid = Some(copy_game_players(
-                &source_connection,
-                go.player_object_id.unwrap(),
-                &target_connection,
-            )?);
-        } else {
-            goid = None;
-        }
-
-        let row_id = stmt.insert(params![
-            new_game_id,
-            goid,
-            go._type,
-            go.name,
-            go.plot_index,
-            go.extra_data,
-            go.icon,
-        ])?;
+        let object_id = go.key("ObjectId")?;
+        go.set("GameId", ids.get(Table::Games, game_id));
+        go.set("PlayerObjectId", ids.remap(Table::GameObjects, go.id("PlayerObjectId")));
+        let row_id = copier.insert(&go)?;
 
+        ids.insert(Table::GameObjects, object_id, row_id);
         debug!("Inserted GameObject {:?} under {}", &go, &row_id);
     }
 
     info!(
-        "Copied {} GameObjects from game {} to {}",
-        &go_counter, &game_id, &new_game_id
+        "Copied {} GameObjects from game {} to {:?}",
+        &go_counter, &game_id, ids.get(Table::Games, game_id)
     );
     Ok(go_counter)
 }
 
+/// Copies the GamePlayers of a game's player objects under the players' new
+/// ObjectIds & links the copied objects to their owners - objects may be
+/// copied before the player owning them.
 fn copy_game_players(
     source_connection: &Connection,
-    player_object_id: i32,
+    game_id: GameId,
     target_connection: &Connection,
-) -> std::result::Result<i64, Box<dyn std::error::Error>> {
-    debug!("Copying GamePlayer {}", &player_object_id);
-    let mut stmt = source_connection.prepare("SELECT PlayerObjectId,IsLocal,IsAI,IsMajor,LeaderType,LeaderName,CivilizationType,CivilizationName,DifficultyType,Score,PlayerId,TeamId FROM GamePlayers WHERE PlayerObjectId = ?")?;
+    ids: &mut IdMap,
+) -> std::result::Result<i32, Box<dyn std::error::Error>> {
+    debug!("Copying GamePlayers for game {}", &game_id);
+    let mut stmt = source_connection.prepare("SELECT ObjectId, PlayerObjectId FROM GameObjects WHERE GameId = ? AND PlayerObjectId IS NOT NULL")?;
+    let mut update = target_connection.prepare("UPDATE GameObjects SET PlayerObjectId = ? WHERE ObjectId = ?")?;
+    let owners = stmt
+        .query_map(params![game_id], |row| Ok((row.get::<_, i32>(0)?, row.get::<_, i32>(1)?)))?
+        .collect::<Result<Vec<_>>>()?;
+    for (object_id, player_object_id) in owners {
+        if let Some(new_object_id) = ids.get(Table::GameObjects, object_id) {
+            update.execute(params![ids.get(Table::GameObjects, player_object_id), new_object_id])?;
+        }
+    }
 
-    let gp = stmt
-        .query_and_then(params![player_object_id], from_row::<GamePlayer>)?
-        .next()
-        .ok_or(rusqlite::Error::QueryReturnedNoRows)??;
+    let mut copier = Copier::new(
+        source_connection,
+        target_connection,
+        "GamePlayers",
+        None,
+        "JOIN GameObjects go ON go.ObjectId = t.PlayerObjectId WHERE go.GameId = ?",
+    )?;
 
-    let mut stmt = target_connection.prepare("INSERT INTO GamePlayers (IsLocal,IsAI,IsMajor,LeaderType,LeaderName,CivilizationType,CivilizationName,DifficultyType,Score,PlayerId,TeamId) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)")?;
-
-    let row_id = stmt.insert(params![
-        gp.is_local,
-        gp.is_ai,
-        gp.is_major,
-        gp.leader_type,
-        gp.leader_name,
-        gp.civilization_type,
-        gp.civilization_name,
-        gp.difficulty_type,
-        gp.score,
-        gp.player_id,
-        gp.team_id,
-    ])?;
-
-    info!("Copied GamePlayers {} as {}", &player_object_id, &row_id);
-    Ok(row_id)
+    let mut gp_counter = 0;
+    for mut gp in copier.rows(params![game_id])? {
+        let player_object_id = gp.key("PlayerObjectId")?;
+        let new_player_object_id = match ids.get(Table::GameObjects, player_object_id) {
+            Some(new_player_object_id) => new_player_object_id,
+            None => continue,
+        };
+
+        gp.set("PlayerObjectId", new_player_object_id);
+        copier.insert(&gp)?;
+        ids.insert(Table::GamePlayers, player_object_id, new_player_object_id);
+        debug!("Inserted GamePlayer {:?} under {}", &gp, &new_player_object_id);
+        gp_counter += 1;
+    }
+
+    info!(
+        "Copied {} GamePlayers from game {} to {:?}",
+        &gp_counter, &game_id, ids.get(Table::Games, game_id)
+    );
+    Ok(gp_counter)
 }
 
 fn copy_game_data_point_value(
     source_connection: &Connection,
     game_id: GameId,
     target_connection: &Connection,
-    new_game_id: GameId,
-) -> std::result::Result<Vec<i32>, Box<dyn std::error::Error>> {
+    ids: &mut IdMap,
+) -> std::result::Result<i32, Box<dyn std::error::Error>> {
     debug!("Copying GameDataPointValue for game {}", &game_id);
-    let mut already_copied_game_objects = Vec::new();
-    let mut stmt = source_connection.prepare("SELECT DataPoint, GameId, ValueObjectId, ValueType, ValueString, ValueNumeric FROM GameDataPointValues WHERE GameId = ?")?;
+    let mut copier = Copier::new(source_connection, target_connection, "GameDataPointValues", None, "WHERE t.GameId = ?")?;
 
     let mut gdpv_counter = 0;
-    let rows_iter = from_rows::<GameDataPointValue>(stmt.query(params![game_id])?);
-
-    for game_data_point_value in rows_iter {
+    for mut gdpv in copier.rows(params![game_id])? {
         gdpv_counter += 1;
-        let gdpv = game_data_point_value?;
 
-        let mut stmt = target_connection.prepare("INSERT INTO GameDataPointValues (DataPoint, GameId, ValueObjectId, ValueType, ValueString, ValueNumeric) VALUES (?, ?, ?, ?, ?, ?)")?;
+        let new_value_object_id = match gdpv.id("ValueObjectId") {
+            Some(voi) => match ids.get(Table::GameObjects, voi) {
+                Some(new_object_id) => Some(new_object_id),
+                None => Some(copy_game_object(source_connection, game_id, target_connection, ids, voi)?),
+            },
+            None => None,
+        };
 
-        let new_value_object_id;
-        if gdpv.value_object_id.is_some() {
-            let voi = gdpv.value_object_id.unwrap();
-            new_value_object_id = Some(copy_game_object(source_connection, game_id, target_connection, new_game_id, &voi)?);
-            already_copied_game_objects.push(voi);
-        } else {
-            new_value_object_id = None;
+        gdpv.set("GameId", ids.get(Table::Games, game_id));
+        gdpv.set("ValueObjectId", new_value_object_id);
+        let row_id = copier.insert(&gdpv)?;
+
+        debug!("Inserted GameDataPointValue {:?} under {}", &gdpv, &row_id);
+    }
+
+    info!(
+        "Copied {} GameDataPointValue from game {} to {:?}",
+        &gdpv_counter, &game_id, ids.get(Table::Games, game_id)
+    );
+    Ok(gdpv_counter)
+}
+
+/// Copies the definitions - Rulesets, RulesetTypes & RulesetDataPointValues
+/// rows - of the source's rulesets the target doesn't know, e.g. of a mod.
+fn copy_missing_rulesets(
+    source_connection: &Connection,
+    target_connection: &Connection,
+) -> std::result::Result<usize, Box<dyn std::error::Error>> {
+    let mut stmt = target_connection.prepare("SELECT Ruleset FROM Rulesets")?;
+    let known = stmt
+        .query_map(NO_PARAMS, |row| row.get(0))?
+        .collect::<Result<HashSet<String>>>()?;
+    let mut stmt = source_connection.prepare("SELECT Ruleset FROM Rulesets")?;
+    let missing: Vec<String> = stmt
+        .query_map(NO_PARAMS, |row| row.get(0))?
+        .collect::<Result<Vec<String>>>()?
+        .into_iter()
+        .filter(|ruleset| !known.contains(ruleset))
+        .collect();
+
+    for table in &["Rulesets", "RulesetTypes", "RulesetDataPointValues"] {
+        let target_columns = schema::columns(target_connection, table)?;
+        let columns: Vec<String> = schema::columns(source_connection, table)?
+            .into_iter()
+            .filter(|column| target_columns.contains(column))
+            .collect();
+        let mut select = source_connection.prepare(&format!(
+            "SELECT {} FROM {} WHERE Ruleset = ?",
+            columns.join(", "),
+            table
+        ))?;
+        let mut insert = target_connection.prepare(&format!(
+            "INSERT OR IGNORE INTO {} ({}) VALUES ({})",
+            table,
+            columns.join(", "),
+            vec!["?"; columns.len()].join(", ")
+        ))?;
+        for ruleset in &missing {
+            let mut rows = select.query(params![ruleset])?;
+            while let Some(row) = rows.next()? {
+                let values = (0..columns.len())
+                    .map(|index| row.get::<_, rusqlite::types::Value>(index))
+                    .collect::<Result<Vec<_>>>()?;
+                insert.execute(values)?;
+            }
         }
+    }
 
-        let row_id = stmt.insert(params![
-            gdpv.data_point,
-            new_game_id,
-            new_value_object_id,
-            gdpv.value_type,
-            gdpv.value_string,
-            gdpv.value_numeric,
-        ])?;
+    for ruleset in &missing {
+        info!("Added ruleset {} to the target", ruleset);
+    }
+    Ok(missing.len())
+}
 
-        debug!("Inserted GameDataPointValue {:?} under {}", &gdpv, &row_id);
+fn copy_object_data_point_values(
+    source_connection: &Connection,
+    target_connection: &Connection,
+    ids: &IdMap,
+) -> std::result::Result<i32, Box<dyn std::error::Error>> {
+    let mut copier = Copier::new(source_connection, target_connection, "ObjectDataPointValues", None, "WHERE t.ObjectId = ?")?;
+
+    let mut odpv_counter = 0;
+    let object_ids = ids.pairs(Table::GameObjects);
+    for (object_id, new_object_id) in &object_ids {
+        for mut odpv in copier.rows(params![object_id])? {
+            // Values pointing to objects the game doesn't reference lose their object
+            let new_value_object_id = ids.remap(Table::GameObjects, odpv.id("ValueObjectId"));
+            odpv.set("ObjectId", *new_object_id);
+            odpv.set("ValueObjectId", new_value_object_id);
+            copier.insert(&odpv)?;
+            debug!("Inserted ObjectDataPointValue {:?} for {}", &odpv, &new_object_id);
+            odpv_counter += 1;
+        }
+    }
+
+    info!("Copied {} ObjectDataPointValues of {} GameObjects", &odpv_counter, object_ids.len());
+    Ok(odpv_counter)
+}
+
+fn copy_data_sets(
+    source_connection: &Connection,
+    game_id: GameId,
+    target_connection: &Connection,
+    ids: &mut IdMap,
+) -> std::result::Result<i32, Box<dyn std::error::Error>> {
+    debug!("Copying DataSets for game {}", &game_id);
+    let mut copier = Copier::new(source_connection, target_connection, "DataSets", Some("DataSetId"), "WHERE t.GameId = ?")?;
+    let mut values_copier = Copier::new(source_connection, target_connection, "DataSetValues", None, "WHERE t.DataSetId = ?")?;
+
+    let mut ds_counter = 0;
+    for mut ds in copier.rows(params![game_id])? {
+        ds_counter += 1;
+        let data_set_id = ds.key("DataSetId")?;
+
+        // Data sets of objects the game doesn't reference lose their object
+        let new_object_id = ids.remap(Table::GameObjects, ds.id("ObjectId"));
+        ds.set("GameId", ids.get(Table::Games, game_id));
+        ds.set("ObjectId", new_object_id);
+        let row_id = copier.insert(&ds)?;
+        ids.insert(Table::DataSets, data_set_id, row_id);
+
+        let values = copy_data_set_values(&mut values_copier, data_set_id, ids)?;
+        debug!("Inserted DataSet {:?} with {} values under {}", &ds, values, &row_id);
     }
 
     info!(
-        "Copied {} GameDataPointValue from game {} to {}",
-        &gdpv_counter, &game_id, &new_game_id
+        "Copied {} DataSets from game {} to {:?}",
+        &ds_counter, &game_id, ids.get(Table::Games, game_id)
     );
-    Ok(already_copied_game_objects)
-// )
+    Ok(ds_counter)
 }
 
-fn main() -> std::result::Result<(), Box<dyn std::error::Error>> {
-    env_logger::init();
-    let args = Cli::from_args();
+fn copy_data_set_values(
+    copier: &mut Copier,
+    data_set_id: i64,
+    ids: &IdMap,
+) -> std::result::Result<i32, Box<dyn std::error::Error>> {
+    let mut dsv_counter = 0;
+    for mut dsv in copier.rows(params![data_set_id])? {
+        dsv.set("DataSetId", ids.get(Table::DataSets, data_set_id));
+        copier.insert(&dsv)?;
+        dsv_counter += 1;
+    }
+    Ok(dsv_counter)
+}
+
+/// Creates an empty target with the base's schema, taking over its applied
+/// migrations & ruleset definitions.
+fn create_fresh_target(
+    base_path: &std::path::PathBuf,
+    target_path: &std::path::Path,
+) -> std::result::Result<Connection, Box<dyn std::error::Error>> {
+    // Refuses a base that is no Hall-of-Fame database before replacing the target
+    open_db(base_path)?;
+    if target_path.exists() {
+        std::fs::remove_file(target_path)?;
+    }
+    let con = Connection::open(target_path)?;
+    schema::create(&con)?;
+
+    cipher::attach(&con, base_path, "base")?;
+    for table in &["Migrations", "Rulesets", "RulesetTypes", "RulesetDataPointValues"] {
+        let base_columns = con
+            .prepare(&format!("PRAGMA base.table_info({})", table))?
+            .query_map(NO_PARAMS, |row| row.get::<_, String>(1))?
+            .collect::<Result<Vec<String>>>()?;
+        let columns: Vec<String> = schema::columns(&con, table)?
+            .into_iter()
+            .filter(|column| base_columns.contains(column))
+            .collect();
+        con.execute(
+            &format!(
+                "INSERT INTO main.{0} ({1}) SELECT {1} FROM base.{0}",
+                table,
+                columns.join(", ")
+            ),
+            NO_PARAMS,
+        )?;
+    }
+    con.execute("DETACH DATABASE base", NO_PARAMS)?;
+
+    info!("Created {:?} from schema {}", &target_path, schema::SCHEMA.name);
+    Ok(con)
+}
+
+/// Records in a `MergeMeta` key/value table which tool version produced the
+/// target, against which HoF schema (number of applied `Migrations`) and when.
+fn stamp_merge_metadata(con: &Connection) -> std::result::Result<(), Box<dyn std::error::Error>> {
+    con.execute(
+        "CREATE TABLE IF NOT EXISTS MergeMeta (Key TEXT PRIMARY KEY NOT NULL, Value TEXT)",
+        NO_PARAMS,
+    )?;
 
-    //TODO use path
-    let source_path = args.source1;//"/Users/sebastian/Library/Application Support/Sid Meier's Civilization VI/HallofFame.sqlite";
-    let enrich_path = args.source2;//"HallofFame.sqlite";
-    let target_path = args.target;//"target.sqlite";
+    let schema_version: i64 = con.query_row("SELECT COUNT(*) FROM Migrations", NO_PARAMS, |row| row.get(0))?;
+    let merged_at = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)?
+        .as_secs();
 
-    let mut source_file = File::open(&source_path)?;
-    let mut target_file = File::create(&target_path)?;
-    let copy_bytes = std::io::copy(&mut source_file, &mut target_file)?;
+    let mut stmt = con.prepare("INSERT OR REPLACE INTO MergeMeta (Key, Value) VALUES (?, ?)")?;
+    stmt.execute(params!["ToolVersion", env!("CARGO_PKG_VERSION")])?;
+    stmt.execute(params!["SchemaVersion", schema_version.to_string()])?;
+    stmt.execute(params!["LastMerged", merged_at.to_string()])?;
 
     info!(
-        "Created {:?} with {}b based of {:?}",
-        &target_path, copy_bytes, &source_file
+        "Stamped target with version {}, schema {} at {}",
+        env!("CARGO_PKG_VERSION"),
+        &schema_version,
+        &merged_at
     );
+    Ok(())
+}
+
+/// Journals a skipped duplicate whose players differ from the target's copy
+/// `existing` - the source's version is dropped although it isn't the same.
+fn journal_divergent(
+    source_connection: &Connection,
+    source_path: &std::path::Path,
+    target_connection: &Connection,
+    game: &Game,
+    existing: GameId,
+    journal: &mut conflicts::Journal,
+) -> std::result::Result<(), Box<dyn std::error::Error>> {
+    if conflicts::players(source_connection, game.game_id)? != conflicts::players(target_connection, existing)? {
+        let reason = format!("Skipped: Duplicate of target game {}, but its players or scores differ", existing);
+        journal.record(source_connection, source_path, game.game_id, &reason)?;
+    }
+    Ok(())
+}
+
+fn merge_source(
+    source_path: &std::path::PathBuf,
+    target_connection: &Connection,
+    mut game_cache: Option<&mut cache::GameCache>,
+    run: history::RunId,
+    options: &MergeOptions,
+    mut journal: Option<&mut conflicts::Journal>,
+) -> std::result::Result<SourceSummary, Box<dyn std::error::Error>> {
+    let source_connection = open_db(source_path)?;
+    let mut summary = SourceSummary::default();
+    let source = source_path.to_string_lossy();
+    let games: i64 = source_connection.query_row("SELECT COUNT(*) FROM Games", NO_PARAMS, |row| row.get(0))?;
+    options
+        .events
+        .emit("source_opened", json!({ "source": source, "games": games }));
+
+    // Games must not reference rulesets the target doesn't define
+    summary.rulesets_added = copy_missing_rulesets(&source_connection, target_connection)?;
+    let extra_tables = if options.copy_extra_tables {
+        let tables = extra_tables::discover(&source_connection, target_connection)?;
+        summary.extra_rows_copied += extra_tables::copy_global(&source_connection, target_connection, &tables)?;
+        tables
+    } else {
+        Vec::new()
+    };
 
-    let source_connection2 = open_db(&enrich_path)?;
+    let modded = if options.exclude_modded {
+        content::modded_games(&source_connection)?
+    } else {
+        Default::default()
+    };
+    let ai_only = if options.skip_ai_only {
+        ai_only_games(&source_connection)?
+    } else {
+        HashSet::new()
+    };
+    let passing = if options.filters.is_empty() {
+        None
+    } else {
+        Some(options.filters.matching(&source_connection)?)
+    };
+    let mut merge_log = if options.merge_log {
+        Some(merge_log::MergeLog::load(&source_connection, target_connection)?)
+    } else {
+        None
+    };
 
-    let target_connection = Connection::open(&target_path)?;
+    // Games played outside of the window aren't even read
+    let since = options.since.unwrap_or(i64::MIN);
+    let until = options.until.unwrap_or(i64::MAX);
+    summary.games_older = source_connection.query_row(
+        "SELECT COUNT(*) FROM Games WHERE LastPlayed < ?",
+        params![since],
+        |row| row.get(0),
+    )?;
+    summary.games_newer = source_connection.query_row(
+        "SELECT COUNT(*) FROM Games WHERE LastPlayed >= ?",
+        params![until],
+        |row| row.get(0),
+    )?;
+    // Source games whose version the target has are skipped without checking them one by one
+    let shared: HashSet<GameId> = if options.shared_history {
+        let target_versions: HashSet<u64> = dedupe::version_fingerprints(target_connection)?.into_values().collect();
+        dedupe::version_fingerprints(&source_connection)?
+            .into_iter()
+            .filter(|(_, fingerprint)| target_versions.contains(fingerprint))
+            .map(|(game_id, _)| game_id)
+            .collect()
+    } else {
+        HashSet::new()
+    };
+    if options.shared_history {
+        info!("{} game(s) of {:?} are in the shared history", shared.len(), &source_path);
+    }
 
-    let mut stmt = source_connection2.prepare("SELECT * FROM Games")?;
-    let rows_iter = from_rows::<Game>(stmt.query(NO_PARAMS)?);
+    let mut stmt = source_connection.prepare("SELECT * FROM Games WHERE LastPlayed >= ? AND LastPlayed < ?")?;
+    let rows_iter = from_rows::<Game>(stmt.query(params![since, until])?);
 
-    info!("Synchronizing games:");
+    info!("Synchronizing games of {:?}:", &source_path);
     for game in rows_iter {
         //debug!("Loaded: {:?}", &game);
 
-        let g = &game?;
+        let mut game = game?;
+        summary.games_scanned += 1;
+        if let Some(map) = maps::renamed(&options.rename_maps, &game.map) {
+            debug!("Renaming map {} of game {} to {}", &game.map, &game.game_id, map);
+            game.map = map.to_string();
+        }
+        let g = &game;
+
+        if let Some(traces) = modded.get(&g.game_id) {
+            summary.games_excluded += 1;
+            info!("Excluding modded game {}: {}", &g.game_id, traces.join(", "));
+            options.events.emit(
+                "game_skipped",
+                json!({ "source": source, "gameId": g.game_id, "reason": "modded" }),
+            );
+            continue;
+        }
 
-        let row_id = insert_game_if_not_exists(&target_connection, &g)?;
+        if passing.as_ref().is_some_and(|passing| !passing.contains(&g.game_id)) {
+            summary.games_filtered += 1;
+            debug!("Skipping game {}: Filtered out", &g.game_id);
+            options.events.emit(
+                "game_skipped",
+                json!({ "source": source, "gameId": g.game_id, "reason": "filtered" }),
+            );
+            continue;
+        }
 
-        if row_id == 0 {
-            info!("-")
+        if ai_only.contains(&g.game_id) {
+            summary.games_ai_only += 1;
+            info!("Skipping game {}: No local human player", &g.game_id);
+            options.events.emit(
+                "game_skipped",
+                json!({ "source": source, "gameId": g.game_id, "reason": "ai-only" }),
+            );
+            continue;
+        }
+
+        if shared.contains(&g.game_id) {
+            summary.games_skipped += 1;
+            summary.games_shared += 1;
+            options.events.emit(
+                "game_skipped",
+                json!({ "source": source, "gameId": g.game_id, "reason": "shared" }),
+            );
+            continue;
+        }
+
+        if merge_log.as_ref().is_some_and(|log| log.contains(g)) {
+            summary.games_skipped += 1;
+            debug!("Game {} is logged as merged before", &g.game_id);
+            options.events.emit(
+                "game_skipped",
+                json!({ "source": source, "gameId": g.game_id, "reason": "logged" }),
+            );
+            continue;
+        }
+
+        // Keeping both or the source's version, a known game is resolved like any other duplicate
+        let cached = !matches!(options.on_conflict, conflicts::Keep::Source | conflicts::Keep::Both)
+            && game_cache.as_ref().is_some_and(|cache| cache.contains(g));
+        if cached {
+            summary.games_skipped += 1;
+            if options.explain_dups {
+                explain::explain_skip(target_connection, g)?;
+            }
+            debug!("Game {} is cached as known", &g.game_id);
+            options.events.emit(
+                "game_skipped",
+                json!({ "source": source, "gameId": g.game_id, "reason": "cached" }),
+            );
+            continue;
+        }
+
+        // With --skip-errors a failing game is rolled back alone & the merge goes on
+        let checkpoint = (summary.clone(), journal.as_ref().map_or(0, |journal| journal.len()));
+        if options.skip_errors {
+            target_connection.execute_batch("SAVEPOINT merge_game")?;
+        }
+        let merged = (|| -> std::result::Result<(), Box<dyn std::error::Error>> {
+            let mut existing = dedupe::duplicate_of(&source_connection, target_connection, g, options.dedupe_by)?;
+            if let Some(target_game) = existing {
+                match options.on_conflict.resolve(g, &load_game(target_connection, target_game)?) {
+                    conflicts::Resolution::Skip => {}
+                    conflicts::Resolution::Replace => {
+                        info!("Replacing target game {} with game {}", &target_game, &g.game_id);
+                        if let Some(journal) = journal.as_mut() {
+                            let reason = format!("Replaced by game {} of {:?}", g.game_id, &source_path);
+                            journal.record(target_connection, &options.target_path, target_game, &reason)?;
+                        }
+                        history::record_replaced(target_connection, run, target_game)?;
+                        delete_game(target_connection, target_game)?;
+                        summary.games_replaced += 1;
+                        existing = None;
+                    }
+                    conflicts::Resolution::Copy => existing = None,
+                }
+            }
+
+            if let Some(existing) = existing {
+                summary.games_skipped += 1;
+                info!("-");
+                options.events.emit(
+                    "game_matched",
+                    json!({ "source": source, "gameId": g.game_id, "targetGameId": existing }),
+                );
+                if let Some(log) = merge_log.as_mut() {
+                    log.record(target_connection, g, existing)?;
+                }
+                if options.explain_dups {
+                    match options.dedupe_by {
+                        dedupe::Strategy::Exact => explain::explain_skip(target_connection, g)?,
+                        strategy => explain::explain_match(target_connection, g, existing, strategy)?,
+                    }
+                }
+                if let Some(journal) = journal.as_mut() {
+                    journal_divergent(&source_connection, source_path, target_connection, g, existing, journal)?;
+                }
+            } else if !options
+                .interactive
+                .as_ref()
+                .map_or(Ok(true), |prompt| prompt.confirm(&source_connection, g))?
+            {
+                summary.games_declined += 1;
+                debug!("Skipping game {}: Declined", &g.game_id);
+                options.events.emit(
+                    "game_skipped",
+                    json!({ "source": source, "gameId": g.game_id, "reason": "declined" }),
+                );
+            } else {
+                let row_id = insert_game(&source_connection, target_connection, g)?;
+                if options.explain_dups {
+                    explain::explain_copy(target_connection, g, row_id)?;
+                }
+                let mut ids = IdMap::default();
+                ids.insert(Table::Games, g.game_id, row_id);
+                let data_points = copy_game_data_point_value(&source_connection, g.game_id, target_connection, &mut ids)?;
+                let table_finished = |table: &str| {
+                    options.events.emit(
+                        "table_finished",
+                        json!({ "source": source, "gameId": g.game_id, "table": table }),
+                    )
+                };
+                table_finished("GameDataPointValues");
+                copy_game_objects(&source_connection, g.game_id, target_connection, &mut ids)?;
+                table_finished("GameObjects");
+                let players = copy_game_players(&source_connection, g.game_id, target_connection, &mut ids)?;
+                table_finished("GamePlayers");
+                copy_object_data_point_values(&source_connection, target_connection, &ids)?;
+                table_finished("ObjectDataPointValues");
+                copy_data_sets(&source_connection, g.game_id, target_connection, &mut ids)?;
+                table_finished("DataSets");
+                summary.extra_rows_copied +=
+                    extra_tables::copy_game(&source_connection, target_connection, &extra_tables, g.game_id, &ids)?;
+                history::record_game(target_connection, run, row_id, source_path, g.game_id)?;
+                if options.summary_tables {
+                    aggregates::record_game(target_connection, row_id)?;
+                }
+                if let Some(log) = merge_log.as_mut() {
+                    log.record(target_connection, g, row_id)?;
+                }
+                summary.games_copied += 1;
+                summary.rows_copied[0] += i64::from(players);
+                summary.rows_copied[1] += ids.copied(Table::GameObjects).len() as i64;
+                summary.rows_copied[2] += i64::from(data_points);
+                info!("Copied game {} to {}", &g.game_id, &row_id);
+                options.events.emit(
+                    "game_copied",
+                    json!({ "source": source, "gameId": g.game_id, "targetGameId": row_id }),
+                );
+            }
+            Ok(())
+        })();
+        match merged {
+            Ok(()) => {
+                if options.skip_errors {
+                    target_connection.execute_batch("RELEASE merge_game")?;
+                }
+                if let Some(cache) = game_cache.as_mut() {
+                    cache.insert(g);
+                }
+            }
+            Err(error) if options.skip_errors => {
+                target_connection.execute_batch("ROLLBACK TO merge_game; RELEASE merge_game")?;
+                summary = checkpoint.0;
+                if let Some(journal) = journal.as_mut() {
+                    journal.truncate(checkpoint.1);
+                }
+                warn!("Skipping game {} of {:?}: {}", &g.game_id, &source_path, &error);
+                summary.games_failed.push(g.game_id);
+                options.events.emit(
+                    "game_skipped",
+                    json!({ "source": source, "gameId": g.game_id, "reason": "error", "error": error.to_string() }),
+                );
+            }
+            Err(error) => return Err(error),
+        }
+    }
+
+    options.events.emit(
+        "source_finished",
+        json!({
+            "source": source,
+            "copied": summary.games_copied,
+            "skipped": summary.games_skipped,
+            "excluded": summary.games_excluded,
+        }),
+    );
+    Ok(summary)
+}
+
+fn main() -> std::result::Result<(), Box<dyn std::error::Error>> {
+    env_logger::init();
+    let args = Cli::from_args();
+    cipher::set_passphrase(args.passphrase.clone())?;
+    schema::set_check(args.schema_check);
+    let names = match &args.names {
+        Some(path) => names::Names::load(path)?,
+        None => names::Names::default(),
+    };
+
+    match &args.command {
+        Some(Command::CreateViews { db }) => Ok(views::create_views(&open_db(db)?)?),
+        Some(Command::Matrix {
+            db,
+            format,
+            out,
+            highlight,
+            no_highlight,
+            profile,
+        }) => {
+            let m = matrix::load(&open_db(db)?, profile.as_deref())?;
+            let highlight = if *no_highlight { None } else { Some(highlight.as_str()) };
+            let rendered = match format.as_str() {
+                "markdown" => matrix::render_markdown(&m, &names, highlight.is_some()),
+                "svg" => matrix::render_svg(&m, &names, highlight),
+                "tsv" => matrix::to_table(&m, &names).render(table::Format::Tsv),
+                _ => matrix::render_text(&m, &names),
+            };
+            Ok(output::write(out.as_deref(), &rendered)?)
+        }
+        Some(Command::Doctor { paths }) => {
+            doctor::run(paths);
+            Ok(())
+        }
+        Some(Command::Validate { paths }) => {
+            let failed = paths.iter().filter(|path| !doctor::check_database(path)).count();
+            if failed > 0 {
+                return Err(format!("{} of {} database(s) failed validation", failed, paths.len()).into());
+            }
+            Ok(())
+        }
+        Some(Command::Diff { a, b, dedupe_by }) => {
+            let (a_connection, b_connection) = (open_db(a)?, open_db(b)?);
+            let diff = diff::compare(&a_connection, &b_connection, *dedupe_by)?;
+            Ok(diff::print((a, &a_connection), (b, &b_connection), &diff, &names)?)
+        }
+        Some(Command::Backup { db, out }) => {
+            let backup = match out {
+                Some(out) => {
+                    cipher::backup(db, out)?;
+                    out.clone()
+                }
+                None => backup(db)?,
+            };
+            println!("Backed up {:?} to {:?}", db, &backup);
+            Ok(())
+        }
+        Some(Command::DebugGraph { db, game_id, out }) => {
+            let dot = debug_graph::render(&open_db(db)?, *game_id)?;
+            Ok(output::write(out.as_deref(), &dot)?)
+        }
+        #[cfg(feature = "charts")]
+        Some(Command::Graph {
+            db,
+            game_id,
+            stat,
+            ascii,
+            out,
+        }) => {
+            let con = open_db(db)?;
+            let series = graph::series(&con, *game_id, stat)?;
+            if series.is_empty() {
+                let stats = graph::stats(&con, *game_id)?;
+                return Err(format!("Game {} has no data set {}, only: {}", game_id, stat, stats.join(", ")).into());
+            }
+            if let Some(out) = out {
+                graph::write_chart(out, &graph::render_svg(&series, &format!("Game {}: {}", game_id, stat)))?;
+                println!("Wrote the {} chart of game {} to {:?}", stat, game_id, out);
+                return Ok(());
+            }
+            let sparklines = graph::render_sparklines(&series, *ascii);
+            Ok(output::print(&format!("Game {}: {}\n{}", game_id, stat, sparklines))?)
+        }
+        Some(Command::Export {
+            db,
+            file,
+            game_ids,
+            format,
+            out,
+        }) => {
+            let con = open_db(db)?;
+            let game_ids = export::selected(&con, game_ids)?;
+            let out = out.as_deref().or(file.as_deref());
+            let dump = match format {
+                export::Format::Sql => export::sql(&con, &game_ids)?,
+                export::Format::Json => format!("{:#}\n", export::json(&con, &game_ids)?),
+                export::Format::GamesCsv => export::games_csv(&con, &game_ids)?,
+                export::Format::Csv => {
+                    let dir = out
+                        .filter(|out| !output::is_stdout(Some(out)))
+                        .ok_or("--format csv writes a file per table - give the directory to write them to")?;
+                    for (path, rows) in export::csv_tables(&con, &game_ids, dir)? {
+                        println!("Wrote {} row(s) to {:?}", rows, path);
+                    }
+                    return Ok(());
+                }
+            };
+            Ok(output::write(out, &dump)?)
+        }
+        Some(Command::Import {
+            db,
+            file,
+            dedupe_by,
+            wait,
+        }) => import_json(db, file, &read_json(file)?, *dedupe_by, *wait),
+        Some(Command::Pack { db, out, game_ids }) => {
+            let con = open_db(db)?;
+            let game_ids = export::selected(&con, game_ids)?;
+            let pack = pack::pack(&con, &names, db, &game_ids)?;
+            output::compress(out, &pack.to_string())?;
+            println!(
+                "Packed {} game(s) into {:?} ({})",
+                game_ids.len(),
+                out,
+                human_size(std::fs::metadata(out)?.len())
+            );
+            Ok(())
+        }
+        Some(Command::Unpack {
+            db,
+            pack,
+            list,
+            dedupe_by,
+            wait,
+        }) => {
+            let document = read_json(pack)?;
+            pack::print_manifest(pack::manifest(&document)?);
+            if *list {
+                return Ok(());
+            }
+            import_json(db, pack, &document, *dedupe_by, *wait)
+        }
+        Some(Command::Extract { db, game_id, out }) => extract(db, *game_id, out),
+        #[cfg(feature = "charts")]
+        Some(Command::Report { db, out }) => report::write_report(&open_db(db)?, out, &names),
+        #[cfg(feature = "charts")]
+        Some(Command::Map { db, game_id, out }) => {
+            let svg = minimap::render_svg(&open_db(db)?, *game_id, &names)?;
+            Ok(output::write(out.as_deref(), &svg)?)
+        }
+        Some(Command::List { db, sort, limit, format }) => {
+            Ok(output::print(&show::list(&open_db(db)?, &names, *sort, *limit)?.render(*format))?)
+        }
+        Some(Command::Search {
+            db,
+            terms,
+            leader,
+            civ,
+            map,
+            victory,
+            ids,
+        }) => {
+            let query = search::Query {
+                terms: terms.clone(),
+                leaders: leader.clone(),
+                civilizations: civ.clone(),
+                maps: map.clone(),
+                victories: victory.clone(),
+            };
+            let con = open_db(db)?;
+            let found = search::search(&con, &names, &query)?;
+            if *ids {
+                return Ok(output::print(&format!("{}\n", search::ids(&found)))?);
+            }
+            search::print(&con, &names, &found)
+        }
+        Some(Command::Show { db, game_id }) => show::print_game(&open_db(db)?, &names, *game_id),
+        Some(Command::LinkSaves {
+            db,
+            saves_dir,
+            window_hours,
+        }) => {
+            let links = saves::link_saves(&open_db(db)?, saves_dir, window_hours * 3600)?;
+            for link in &links {
+                println!("Game {}: {:?} (score {})", link.game_id, &link.path, link.score);
+            }
+            println!("Linked {} game(s) to save files", links.len());
+            Ok(())
+        }
+        Some(Command::Profile {
+            action:
+                ProfileAction::Assign {
+                    db,
+                    profile,
+                    game_ids,
+                    leader,
+                    since,
+                    until,
+                    interactive,
+                },
+        }) => {
+            let rule = profiles::Rule {
+                game_ids: game_ids.clone(),
+                leader: leader.clone(),
+                since: *since,
+                until: *until,
+            };
+            let assigned = profiles::assign(&open_db(db)?, profile, &rule, *interactive)?;
+            println!("Assigned {} game(s) to {}", assigned, profile);
+            Ok(())
+        }
+        Some(Command::Profile {
+            action: ProfileAction::List { db, format },
+        }) => {
+            let mut listing = table::Table::new(&["Profile", "Games"]);
+            for (profile, games) in profiles::list(&open_db(db)?)? {
+                listing.push(vec![profile.unwrap_or_else(|| "(unassigned)".to_string()), games.to_string()]);
+            }
+            Ok(output::print(&listing.render(*format))?)
+        }
+        Some(Command::Stats {
+            dbs,
+            dedupe_by,
+            by,
+            format,
+            exclude_modded,
+            ratings,
+            timeline,
+        }) => {
+            with_combined(dbs, *dedupe_by, |con| {
+                if *timeline {
+                    let table = stats::timeline(con, *exclude_modded, *format == table::Format::Text)?;
+                    if *format == table::Format::Json {
+                        return Ok(output::print(&format!("{:#}\n", json!({ "Timeline": table.to_json() })))?);
+                    }
+                    return Ok(output::print(&table.render(*format))?);
+                }
+                if *ratings {
+                    let table = ratings::ratings(con, &names, *exclude_modded)?;
+                    if *format == table::Format::Json {
+                        return Ok(output::print(&format!("{:#}\n", json!({ "Ratings": table.to_json() })))?);
+                    }
+                    return Ok(output::print(&table.render(*format))?);
+                }
+                let breakdowns = if by.is_empty() { stats::BREAKDOWNS } else { by.as_slice() };
+                if *format == table::Format::Json {
+                    // One document with a member per table
+                    let mut document = serde_json::Map::new();
+                    for breakdown in breakdowns {
+                        let table = stats::breakdown(con, *breakdown, &names, *exclude_modded)?;
+                        document.insert(breakdown.title().to_string(), table.to_json());
+                    }
+                    if by.is_empty() {
+                        document.insert("Leaderboard".to_string(), stats::leaderboard(con, &names, *exclude_modded)?.to_json());
+                    }
+                    return Ok(output::print(&format!("{:#}\n", serde_json::Value::Object(document)))?);
+                }
+                let mut tables = Vec::new();
+                for breakdown in breakdowns {
+                    tables.push(stats::breakdown(con, *breakdown, &names, *exclude_modded)?.render(*format));
+                }
+                if by.is_empty() {
+                    tables.push(stats::leaderboard(con, &names, *exclude_modded)?.render(*format));
+                }
+                Ok(output::print(&tables.join("\n"))?)
+            })
+        }
+        Some(Command::Milestones { db }) => {
+            Ok(output::print(&milestones::render(&milestones::compute(&open_db(db)?, &names)?))?)
+        }
+        Some(Command::Leaderboard { db, format, limit, out }) => {
+            let page = leaderboard::render(&open_db(db)?, &names, *format, *limit)?;
+            Ok(output::write(out.as_deref(), &page)?)
+        }
+        Some(Command::Top {
+            db,
+            victory,
+            speed,
+            difficulty,
+            ruleset,
+            limit,
+            format,
+        }) => {
+            let query = top::Query {
+                victories: victory.clone(),
+                speeds: speed.clone(),
+                difficulties: difficulty.clone(),
+                rulesets: ruleset.clone(),
+            };
+            Ok(output::print(&top::fastest(&open_db(db)?, &names, &query, *limit)?.render(*format))?)
+        }
+        Some(Command::Career { db, leader, format }) => {
+            let career = career::career(&open_db(db)?, &names, leader)?;
+            let rendered = if *format == table::Format::Json {
+                format!(
+                    "{:#}\n",
+                    json!({ "Career": career.summary.to_json(), "Difficulty": career.difficulties.to_json() })
+                )
+            } else {
+                format!("{}\n{}", career.summary.render(*format), career.difficulties.render(*format))
+            };
+            Ok(output::print(&rendered)?)
+        }
+        Some(Command::Sync { a, b, shared_history, wait }) => sync(a, b, *shared_history, *wait),
+        #[cfg(feature = "shell")]
+        Some(Command::Shell { db }) => shell::run(&open_db(db)?, &names),
+        #[cfg(feature = "service")]
+        Some(Command::InstallService { every, merge_args }) => {
+            if *every == 0 {
+                return Err("--every must be at least 1 minute".into());
+            }
+            println!("Installed {}", service::install(*every, merge_args)?);
+            Ok(())
+        }
+        #[cfg(feature = "service")]
+        Some(Command::UninstallService) => {
+            println!("Removed {}", service::uninstall()?);
+            Ok(())
+        }
+        Some(Command::Features) => {
+            for (feature, included, description) in FEATURES {
+                println!("{} {:<10} {}", if *included { "+" } else { "-" }, feature, description);
+            }
+            Ok(())
+        }
+        Some(Command::Provenance {
+            db,
+            host,
+            source,
+            format,
+        }) => {
+            let listing = history::provenance(&open_db(db)?, host.as_deref(), source.as_deref())?;
+            Ok(output::print(&listing.render(*format))?)
+        }
+        Some(Command::Dedupe {
+            db,
+            dedupe_by,
+            dry_run,
+            wait,
+        }) => dedupe_games(db, *dedupe_by, *dry_run, *wait),
+        Some(Command::Prune {
+            db,
+            older_than,
+            victory,
+            leader,
+            map,
+            ai_only,
+            games,
+            vacuum,
+            dry_run,
+            wait,
+        }) => {
+            let filters = filters::Filters {
+                victories: victory.clone(),
+                leaders: leader.clone(),
+                maps: map.clone(),
+                only_games: games.clone(),
+                ..filters::Filters::default()
+            };
+            if filters.is_empty() && older_than.is_none() && !ai_only {
+                return Err("Prune needs at least one criterion - --older-than, --victory, --ai-only, ...".into());
+            }
+            let con = open_db(db)?;
+            let mut doomed = filters.matching(&con)?;
+            if let Some(cutoff) = older_than {
+                let older = games_older_than(&con, *cutoff)?;
+                doomed.retain(|game_id| older.contains(game_id));
+            }
+            if *ai_only {
+                let ai_only = ai_only_games(&con)?;
+                doomed.retain(|game_id| ai_only.contains(game_id));
+            }
+            let mut doomed: Vec<GameId> = doomed.into_iter().collect();
+            doomed.sort_unstable();
+            drop(con);
+            prune(db, &doomed, &names, *vacuum, *dry_run, *wait)
+        }
+        Some(Command::Repair { db, dry_run, wait }) => repair_database(db, *dry_run, *wait),
+        Some(Command::Undo { db, run, wait }) => undo(db, *run, *wait),
+        Some(Command::Merge(_)) | None => {
+            let args = match args.command {
+                Some(Command::Merge(args)) => args,
+                _ => args.merge,
+            };
+            if args.in_place {
+                merge_in_place(args, &names)
+            } else {
+                merge(args, &names)
+            }
+        }
+    }
+}
+
+/// Refuses a target that is the same file as one of the databases `read` -
+/// copying into it while reading it leaves a corrupted database.
+fn ensure_not_read(target: &std::path::Path, read: &[(&str, &std::path::Path)]) -> std::result::Result<(), Box<dyn std::error::Error>> {
+    for (role, path) in read {
+        if sources::same_file(target, path)? {
+            return Err(format!(
+                "The target {:?} is the same file as {} {:?} - pass another target, or --in-place to merge into it",
+                target, role, path
+            )
+            .into());
+        }
+    }
+    Ok(())
+}
+
+/// Merges into the first source itself, with its backup as base - restoring
+/// the backup if the merge fails partway through.
+fn merge_in_place(mut args: MergeArgs, names: &names::Names) -> std::result::Result<(), Box<dyn std::error::Error>> {
+    if args.paths.len() < 2 {
+        return Err("--in-place needs the database to merge into & at least one source".into());
+    }
+    let target = args.paths.remove(0);
+    if !target.is_file() {
+        return Err(format!("{:?} is no database file to merge into", &target).into());
+    }
+    for path in &args.paths {
+        if sources::same_file(&target, path)? {
+            return Err(format!("{:?} is the same file as the database merged into {:?}", path, &target).into());
+        }
+    }
+    args.target = Some(target.clone());
+    lock::ensure_unlocked(&target, args.wait)?;
+    if args.dry_run {
+        args.paths.insert(0, target);
+        return merge(args, names);
+    }
+    let backup = backup(&target)?;
+    args.paths.insert(0, backup.clone());
+    match merge(args, names) {
+        Ok(()) => {
+            eprintln!("Backup of {:?} kept at {:?}", &target, &backup);
+            Ok(())
+        }
+        Err(error) => {
+            cipher::backup(&backup, &target)?;
+            warn!("Merge failed, restored {:?} from {:?}", &target, &backup);
+            Err(error)
+        }
+    }
+}
+
+/// Copies `path` to `<path>.<epoch seconds>.bak` next to it - or, if a backup of that second exists
+/// already, `<path>.<epoch seconds>-<n>.bak` - never overwriting an earlier backup.
+fn backup(path: &std::path::Path) -> std::result::Result<std::path::PathBuf, Box<dyn std::error::Error>> {
+    let now = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or_default();
+    let mut n = 0;
+    let backup = loop {
+        let mut backup = path.as_os_str().to_owned();
+        match n {
+            0 => backup.push(format!(".{}.bak", now)),
+            n => backup.push(format!(".{}-{}.bak", now, n)),
+        }
+        let backup = std::path::PathBuf::from(backup);
+        // Claimed by creating it, so a concurrent run picks another name
+        match std::fs::OpenOptions::new().write(true).create_new(true).open(&backup) {
+            Ok(_) => break backup,
+            Err(e) if e.kind() == std::io::ErrorKind::AlreadyExists => n += 1,
+            Err(e) => return Err(e.into()),
+        }
+    };
+    cipher::backup(path, &backup)?;
+    info!("Backed up {:?} to {:?}", &path, &backup);
+    Ok(backup)
+}
+
+/// Makes both databases contain the union of their games: `b`'s games are
+/// merged into `a` and the games `a` had before into `b`.
+fn sync(
+    a: &std::path::PathBuf,
+    b: &std::path::PathBuf,
+    shared_history: bool,
+    wait: bool,
+) -> std::result::Result<(), Box<dyn std::error::Error>> {
+    if sources::same_file(a, b)? {
+        return Err(format!("{:?} & {:?} are the same file - nothing to sync", a, b).into());
+    }
+    lock::ensure_unlocked(a, wait)?;
+    lock::ensure_unlocked(b, wait)?;
+    let a_backup = backup(a)?;
+    let b_backup = backup(b)?;
+    let options = MergeOptions {
+        shared_history,
+        ..MergeOptions::default()
+    };
+
+    let a_connection = open_db(a)?;
+    let transaction = a_connection.unchecked_transaction()?;
+    let run = history::start_run(&a_connection)?;
+    let into_a = merge_source(b, &a_connection, None, run, &options, None)?;
+    stamp_merge_metadata(&a_connection)?;
+    transaction.commit()?;
+    drop(a_connection);
+
+    // The backup still holds `a`'s own games only
+    let b_connection = open_db(b)?;
+    let transaction = b_connection.unchecked_transaction()?;
+    let run = history::start_run(&b_connection)?;
+    let into_b = merge_source(&a_backup, &b_connection, None, run, &options, None)?;
+    stamp_merge_metadata(&b_connection)?;
+    transaction.commit()?;
+    drop(b_connection);
+
+    println!("Backups: {:?}, {:?}", &a_backup, &b_backup);
+    for (from, to, summary) in &[(b, a, into_a), (a, b, into_b)] {
+        let shared = if shared_history {
+            format!(" - {} of them in the shared history", summary.games_shared)
+        } else {
+            String::new()
+        };
+        println!(
+            "{:?} -> {:?}: {} game(s) copied, {} already present{}",
+            from, to, summary.games_copied, summary.games_skipped, shared
+        );
+    }
+    Ok(())
+}
+
+/// Reads the games of all `dbs` as one database: A single one directly, several from a throwaway copy of
+/// the first the others are merged into - so games in more than one of them are there once.
+fn with_combined<T>(
+    dbs: &[std::path::PathBuf],
+    dedupe_by: dedupe::Strategy,
+    read: impl FnOnce(&Connection) -> std::result::Result<T, Box<dyn std::error::Error>>,
+) -> std::result::Result<T, Box<dyn std::error::Error>> {
+    if let [db] = dbs {
+        return read(&open_db(db)?);
+    }
+    let scratch = std::env::temp_dir().join(format!("civ6-hof-merge-combined-{}.sqlite", std::process::id()));
+    let result = combine(dbs, dedupe_by, &scratch).and_then(|con| read(&con));
+    if scratch.exists() {
+        std::fs::remove_file(&scratch)?;
+    }
+    result
+}
+
+/// Merges the databases into `scratch`, reporting the duplicates left out on stderr.
+fn combine(
+    dbs: &[std::path::PathBuf],
+    dedupe_by: dedupe::Strategy,
+    scratch: &std::path::Path,
+) -> std::result::Result<Connection, Box<dyn std::error::Error>> {
+    cipher::backup(&dbs[0], scratch)?;
+    let con = cipher::open(scratch)?;
+    let run = history::start_run(&con)?;
+    let options = MergeOptions {
+        dedupe_by,
+        ..MergeOptions::default()
+    };
+    let mut duplicates = 0;
+    for db in &dbs[1..] {
+        duplicates += merge_source(db, &con, None, run, &options, None)?.games_skipped;
+    }
+    eprintln!("Combined {} databases, counting {} duplicate game(s) once", dbs.len(), duplicates);
+    Ok(con)
+}
+
+/// Reads a JSON document, also gzip-compressed.
+fn read_json(file: &std::path::Path) -> std::result::Result<serde_json::Value, Box<dyn std::error::Error>> {
+    let document = serde_json::from_str(&output::read(file)?).map_err(|e| format!("{:?} is no valid JSON: {}", file, e))?;
+    Ok(document)
+}
+
+/// Merges the games of a JSON export into `db` through a scratch database holding them.
+fn import_json(
+    db: &std::path::PathBuf,
+    file: &std::path::Path,
+    document: &serde_json::Value,
+    dedupe_by: dedupe::Strategy,
+    wait: bool,
+) -> std::result::Result<(), Box<dyn std::error::Error>> {
+    let con = open_db(db)?;
+    lock::ensure_unlocked(db, wait)?;
+    let scratch = std::env::temp_dir().join(format!("civ6-hof-merge-import-{}.sqlite", std::process::id()));
+    let result = import_through(document, &scratch, db, &con, dedupe_by);
+    if scratch.exists() {
+        std::fs::remove_file(&scratch)?;
+    }
+    let (backup, summary) = result?;
+    println!(
+        "Imported {:?} into {:?}: {} game(s) copied, {} duplicate(s) skipped, backup at {:?}",
+        file, db, summary.games_copied, summary.games_skipped, &backup
+    );
+    Ok(())
+}
+
+/// Loads the export into `scratch` - with the schema of the database - & merges it into the database
+/// after backing it up.
+fn import_through(
+    document: &serde_json::Value,
+    scratch: &std::path::PathBuf,
+    db: &std::path::Path,
+    con: &Connection,
+    dedupe_by: dedupe::Strategy,
+) -> std::result::Result<(std::path::PathBuf, SourceSummary), Box<dyn std::error::Error>> {
+    if scratch.exists() {
+        std::fs::remove_file(scratch)?;
+    }
+    let scratch_connection = Connection::open(scratch)?;
+    schema::create(&scratch_connection)?;
+    import::load(&scratch_connection, document)?;
+    drop(scratch_connection);
+
+    let backup = backup(db)?;
+    let options = MergeOptions {
+        dedupe_by,
+        ..MergeOptions::default()
+    };
+    let transaction = con.unchecked_transaction()?;
+    let run = history::start_run(con)?;
+    let summary = merge_source(scratch, con, None, run, &options, None)?;
+    stamp_merge_metadata(con)?;
+    transaction.commit()?;
+    Ok((backup, summary))
+}
+
+/// Removes the games a merge run copied into `db` & merges back the games it replaced or dropped, after
+/// backing it up.
+fn undo(db: &std::path::PathBuf, run: Option<history::RunId>, wait: bool) -> std::result::Result<(), Box<dyn std::error::Error>> {
+    let con = open_db(db)?;
+    if history::run_games(&con, run)?.is_none() {
+        match run {
+            Some(run) => println!("{:?} has no merge run {} to undo", db, run),
+            None => println!("{:?} has no merge history to undo", db),
+        }
+        return Ok(());
+    }
+    lock::ensure_unlocked(db, wait)?;
+    let backup = backup(db)?;
+    if let Some((run, removed, restored)) = undo_run(&con, run)? {
+        println!(
+            "Undid merge run {}: Removed {} game(s), restored {} replaced game(s), backup at {:?}",
+            run, removed, restored, &backup
+        );
+    }
+    Ok(())
+}
+
+/// Undoes a merge run - the most recent one unless given - returning it with the number of games
+/// removed & restored, if the database has it.
+fn undo_run(
+    con: &Connection,
+    run: Option<history::RunId>,
+) -> std::result::Result<Option<history::Undone>, Box<dyn std::error::Error>> {
+    let (run, games) = match history::run_games(con, run)? {
+        Some(run_games) => run_games,
+        None => return Ok(None),
+    };
+    let replaced = history::replaced_games(con, run)?;
+    let transaction = con.unchecked_transaction()?;
+    history::undo(con, run, &games)?;
+    let restored = if replaced.is_empty() {
+        0
+    } else {
+        let scratch = std::env::temp_dir().join(format!("civ6-hof-merge-undo-{}.sqlite", std::process::id()));
+        let result = restore_games(&replaced, &scratch, con);
+        if scratch.exists() {
+            std::fs::remove_file(&scratch)?;
+        }
+        result?
+    };
+    aggregates::rebuild(con)?;
+    transaction.commit()?;
+    Ok(Some((run, games.len(), restored)))
+}
+
+/// Loads the `games` kept by `history::record_replaced` into `scratch` & merges them into `con`, returning
+/// their number. They are no merge of their own, so no run of the history.
+fn restore_games(
+    games: &[serde_json::Value],
+    scratch: &std::path::PathBuf,
+    con: &Connection,
+) -> std::result::Result<usize, Box<dyn std::error::Error>> {
+    if scratch.exists() {
+        std::fs::remove_file(scratch)?;
+    }
+    let scratch_connection = Connection::open(scratch)?;
+    schema::create(&scratch_connection)?;
+    import::load(&scratch_connection, &json!({ "games": games }))?;
+    drop(scratch_connection);
+
+    let run = history::start_run(con)?;
+    let summary = merge_source(scratch, con, None, run, &MergeOptions::default(), None)?;
+    history::forget_run(con, run)?;
+    Ok(summary.games_copied as usize)
+}
+
+/// Creates `out` from the bundled schema & the rulesets of `db` and copies the game into it as the merge
+/// would, so it gets GameId 1 & all rows of it are remapped.
+fn extract(db: &std::path::PathBuf, game_id: GameId, out: &std::path::Path) -> std::result::Result<(), Box<dyn std::error::Error>> {
+    let exists: bool = open_db(db)?.query_row(
+        "SELECT COUNT(*) > 0 FROM Games WHERE GameId = ?",
+        params![game_id],
+        |row| row.get(0),
+    )?;
+    if !exists {
+        return Err(format!("Game {} not found in {:?}", game_id, db).into());
+    }
+    if out.exists() {
+        return Err(format!("{:?} exists already", out).into());
+    }
+    let con = create_fresh_target(db, out)?;
+    let options = MergeOptions {
+        filters: filters::Filters {
+            only_games: vec![game_id],
+            ..filters::Filters::default()
+        },
+        ..MergeOptions::default()
+    };
+    let transaction = con.unchecked_transaction()?;
+    let run = history::start_run(&con)?;
+    merge_source(db, &con, None, run, &options, None)?;
+    stamp_merge_metadata(&con)?;
+    transaction.commit()?;
+    println!("Extracted game {} of {:?} to {:?}", game_id, db, out);
+    Ok(())
+}
+
+/// Deletes the duplicates within a database, keeping one game of each.
+fn dedupe_games(
+    db: &std::path::PathBuf,
+    strategy: dedupe::Strategy,
+    dry_run: bool,
+    wait: bool,
+) -> std::result::Result<(), Box<dyn std::error::Error>> {
+    let duplicates = dedupe::duplicates(&open_db(db)?, strategy)?;
+    for (kept, deleted) in &duplicates {
+        let deleted: Vec<String> = deleted.iter().map(|game_id| game_id.to_string()).collect();
+        println!("Game {} kept, duplicate(s) {}", kept, deleted.join(", "));
+    }
+    let count: usize = duplicates.iter().map(|(_, deleted)| deleted.len()).sum();
+    if dry_run || count == 0 {
+        println!("{} duplicate game(s) found in {:?}", count, db);
+        return Ok(());
+    }
+
+    lock::ensure_unlocked(db, wait)?;
+    let backup = backup(db)?;
+    let con = open_db(db)?;
+    let tx = con.unchecked_transaction()?;
+    for (_, deleted) in &duplicates {
+        for game_id in deleted {
+            delete_game(&tx, *game_id)?;
+        }
+    }
+    aggregates::rebuild(&tx)?;
+    tx.commit()?;
+    println!("Deleted {} duplicate game(s) from {:?}, backup at {:?}", count, db, &backup);
+    Ok(())
+}
+
+fn games_older_than(con: &Connection, cutoff: i64) -> Result<HashSet<GameId>> {
+    let mut stmt = con.prepare("SELECT GameId FROM Games WHERE LastPlayed < ?")?;
+    let games = stmt.query_map(params![cutoff], |row| row.get(0))?;
+    games.collect()
+}
+
+/// Deletes games of a database, listing them first.
+fn prune(
+    db: &std::path::PathBuf,
+    game_ids: &[GameId],
+    names: &names::Names,
+    vacuum: bool,
+    dry_run: bool,
+    wait: bool,
+) -> std::result::Result<(), Box<dyn std::error::Error>> {
+    let con = open_db(db)?;
+    for game_id in game_ids {
+        println!("{}", show::describe(&con, names, &load_game(&con, *game_id)?)?);
+    }
+    if dry_run || game_ids.is_empty() {
+        println!("{} game(s) of {:?} match", game_ids.len(), db);
+        return Ok(());
+    }
+
+    lock::ensure_unlocked(db, wait)?;
+    let backup = backup(db)?;
+    let tx = con.unchecked_transaction()?;
+    for game_id in game_ids {
+        delete_game(&tx, *game_id)?;
+    }
+    aggregates::rebuild(&tx)?;
+    tx.commit()?;
+    println!("Deleted {} game(s) from {:?}, backup at {:?}", game_ids.len(), db, &backup);
+    if vacuum {
+        let before = std::fs::metadata(db)?.len();
+        con.execute_batch("VACUUM")?;
+        println!("Vacuumed {:?}: {} -> {}", db, human_size(before), human_size(std::fs::metadata(db)?.len()));
+    }
+    Ok(())
+}
+
+/// Fixes the orphaned rows of a database - a dry run in a transaction rolled back.
+fn repair_database(db: &std::path::PathBuf, dry_run: bool, wait: bool) -> std::result::Result<(), Box<dyn std::error::Error>> {
+    let con = open_db(db)?;
+    if integrity::orphans(&con)?.is_empty() {
+        println!("{:?} has no rows referencing missing rows", db);
+        return Ok(());
+    }
+    let backup = if dry_run {
+        None
+    } else {
+        lock::ensure_unlocked(db, wait)?;
+        Some(backup(db)?)
+    };
+
+    let tx = con.unchecked_transaction()?;
+    for repair in integrity::repair(&tx)? {
+        let mut changes = Vec::new();
+        if repair.relinked > 0 {
+            changes.push(format!("{} re-linked to the game of an object they reference", repair.relinked));
+        }
+        if repair.cleared > 0 {
+            changes.push(format!("{} cleared", repair.cleared));
+        }
+        if repair.deleted > 0 {
+            changes.push(format!("{} deleted", repair.deleted));
+        }
+        println!(
+            "{} row(s) with a {} of no {}: {}",
+            repair.table,
+            repair.column,
+            repair.referenced,
+            changes.join(", ")
+        );
+    }
+    match backup {
+        Some(backup) => {
+            tx.commit()?;
+            println!("Repaired {:?}, backup at {:?}", db, &backup);
+        }
+        None => println!("Dry run - {:?} is unchanged", db),
+    }
+    Ok(())
+}
+
+/// Parses a byte size with an optional `K`, `M` or `G` suffix (powers of 1024).
+fn parse_size(text: &str) -> std::result::Result<u64, String> {
+    let text = text.trim();
+    let (number, factor) = match text.char_indices().last() {
+        Some((i, 'k')) | Some((i, 'K')) => (&text[..i], 1 << 10),
+        Some((i, 'm')) | Some((i, 'M')) => (&text[..i], 1 << 20),
+        Some((i, 'g')) | Some((i, 'G')) => (&text[..i], 1 << 30),
+        _ => (text, 1),
+    };
+    number
+        .trim()
+        .parse::<u64>()
+        .ok()
+        .and_then(|n| n.checked_mul(factor))
+        .ok_or_else(|| format!("Invalid size {:?}, expected e.g. 512K, 64M or 1G", text))
+}
+
+fn human_size(bytes: u64) -> String {
+    match bytes {
+        0..=1023 => format!("{} B", bytes),
+        1024..=1_048_575 => format!("{:.1} KiB", bytes as f64 / 1024.0),
+        1_048_576..=1_073_741_823 => format!("{:.1} MiB", bytes as f64 / 1_048_576.0),
+        _ => format!("{:.1} GiB", bytes as f64 / 1_073_741_824.0),
+    }
+}
+
+/// Asks whether to merge although the target may exceed `--max-db-size`.
+fn confirm_size(estimate: u64, max_db_size: u64) -> std::io::Result<bool> {
+    use std::io::{BufRead, Write};
+
+    print!(
+        "The target may grow to {} (maximum {}) - merge anyway? [y/N] ",
+        human_size(estimate),
+        human_size(max_db_size)
+    );
+    std::io::stdout().flush()?;
+    let mut answer = String::new();
+    std::io::stdin().lock().read_line(&mut answer)?;
+    Ok(matches!(answer.trim(), "y" | "Y"))
+}
+
+/// Tables whose copied rows the summaries count, in the order of `SourceSummary::rows_copied`.
+const COUNTED_TABLES: [&str; 3] = ["GamePlayers", "GameObjects", "GameDataPointValues"];
+
+/// Prints what a merge would have done to `target`.
+fn dry_run_report(
+    target: &std::path::Path,
+    base: &sources::Source,
+    summaries: &[(&sources::Source, SourceSummary)],
+    dropped: usize,
+    fresh_target: bool,
+    ancestor: Option<&std::path::PathBuf>,
+) -> std::result::Result<(), Box<dyn std::error::Error>> {
+    println!("Dry run - {:?} was not written", target);
+    if fresh_target {
+        println!("Would create {:?} from the bundled schema", target);
+    } else {
+        println!("Would base {:?} on {:?} (from {:?})", target, &base.path, &base.origin);
+    }
+    for (source, summary) in summaries {
+        let tables: Vec<String> = COUNTED_TABLES
+            .iter()
+            .zip(&summary.rows_copied)
+            .map(|(table, rows)| format!("{} {}", rows, table))
+            .collect();
+        println!(
+            "Would merge {:?} (from {:?}): {} game(s) with {} copied, {} duplicate(s) skipped",
+            &source.path,
+            &source.origin,
+            summary.games_copied,
+            tables.join(", "),
+            summary.games_skipped
+        );
+    }
+    if let Some(ancestor) = ancestor {
+        println!("Would drop {} game(s) deleted since {:?}", dropped, ancestor);
+    }
+    Ok(())
+}
+
+#[cfg_attr(not(feature = "charts"), allow(unused_variables))]
+fn merge(args: MergeArgs, names: &names::Names) -> std::result::Result<(), Box<dyn std::error::Error>> {
+    let started = std::time::Instant::now();
+    let mut source_args = args.paths;
+    // Removed again unless the merge into it commits
+    let mut generation = None;
+    let target_path = match (&args.archive, args.target) {
+        (_, Some(target)) => target,
+        (Some(archive), None) => {
+            if let Some(latest) = archive::latest(archive)? {
+                source_args.insert(0, latest);
+            }
+            if args.dry_run {
+                archive::next_generation(archive)
+            } else {
+                let next = archive::Generation::start(archive)?;
+                let path = next.path().to_path_buf();
+                generation = Some(next);
+                path
+            }
+        }
+        (None, None) => source_args.pop().ok_or("No target given")?,
+    };
+    // A dry run merges into a throwaway file in place of the target
+    let (reported_target, target_path) = if args.dry_run {
+        let scratch = std::env::temp_dir().join(format!("civ6-hof-merge-dry-run-{}.sqlite", std::process::id()));
+        (target_path, scratch)
+    } else {
+        (target_path.clone(), target_path)
+    };
+    if let Some(list) = &args.sources_from {
+        source_args.extend(sources::read_list(list)?);
+    }
+    let verification = sources::Verification {
+        sha256: args.sha256.clone(),
+        sidecar: args.sha256_sidecar,
+    };
+    // Downloads & decompressed archives are removed when the merge returns, failed or not
+    let mut temp_files = sources::TempFiles::default();
+    let (mut sources, duplicates) = sources::dedupe(sources::discover(&source_args, &verification, &mut temp_files)?)?;
+    if sources.is_empty() {
+        return Err("No Hall-of-Fame database found in the given sources".into());
+    }
+    let base = sources.remove(0);
+    // In place, the base is the target itself (or its backup) by design
+    let mut read = if args.in_place { vec![] } else { vec![("the base", base.path.as_path())] };
+    read.extend(sources.iter().map(|source| ("the source", source.path.as_path())));
+    if let Some(ancestor) = &args.ancestor {
+        read.push(("the --base", ancestor.as_path()));
+    }
+    ensure_not_read(&reported_target, &read)?;
+    if !args.dry_run {
+        lock::ensure_unlocked(&target_path, args.wait)?;
+    }
+
+    if let Some(max_db_size) = args.max_db_size {
+        // Upper bound - duplicates aren't copied
+        let mut estimate = std::fs::metadata(&base.path)?.len();
+        for source in &sources {
+            estimate += std::fs::metadata(&source.path)?.len();
+        }
+        if estimate > max_db_size && !confirm_size(estimate, max_db_size)? {
+            return Err(format!("Aborted: {:?} could grow to {}", &target_path, human_size(estimate)).into());
+        }
+    }
+
+    let events = match &args.events {
+        Some(path) => events::Events::to(path)?,
+        None => events::Events::default(),
+    };
+    events.emit(
+        "merge_started",
+        json!({
+            "target": target_path.to_string_lossy(),
+            "base": base.path.to_string_lossy(),
+            "sources": sources.iter().map(|source| source.path.to_string_lossy()).collect::<Vec<_>>(),
+        }),
+    );
+
+    let target_connection = if args.fresh_target {
+        // All sources - the base included - are merged into an empty target
+        create_fresh_target(&base.path, &target_path)?
+    } else {
+        cipher::backup(&base.path, &target_path)?;
+
+        info!(
+            "Created {:?} with {}b based of {:?}",
+            &target_path,
+            std::fs::metadata(&target_path)?.len(),
+            &base.path
+        );
+        let con = cipher::open(&target_path)?;
+        if schema::check() == schema::Check::Lenient {
+            for table in schema::create_missing(&con, false)? {
+                warn!("Created the table {} the target lacked", table);
+            }
+        }
+        con
+    };
+    let merged: Vec<&sources::Source> = if args.fresh_target {
+        std::iter::once(&base).chain(&sources).collect()
+    } else {
+        sources.iter().collect()
+    };
+    // All changes to the target are committed at once - a failing merge leaves it as the base made it
+    let transaction = target_connection.unchecked_transaction()?;
+    // The base was copied as a whole
+    let mut base_excluded = 0;
+    if args.exclude_modded {
+        for (game_id, traces) in content::modded_games(&target_connection)? {
+            info!("Excluding modded game {}: {}", game_id, traces.join(", "));
+            delete_game(&target_connection, game_id)?;
+            base_excluded += 1;
+        }
+    }
+    let mut base_ai_only = 0;
+    if args.skip_ai_only {
+        for game_id in ai_only_games(&target_connection)? {
+            info!("Skipping game {} of the base: No local human player", game_id);
+            delete_game(&target_connection, game_id)?;
+            base_ai_only += 1;
+        }
+    }
+    if !args.rename_map.is_empty() {
+        let renamed = maps::rename_all(&target_connection, &args.rename_map)?;
+        info!("Renamed the maps of {} game(s) of the base", renamed);
+    }
+    let mut game_cache = if args.game_cache {
+        // Where it is found again by the next merge, which writes a new generation into an archive
+        let path = match &args.archive {
+            Some(archive) => archive.join("gamecache"),
+            None => cache::GameCache::path_of(&reported_target),
+        };
+        Some(cache::GameCache::load(path, &target_connection)?)
+    } else {
+        None
+    };
+
+    if args.summary_tables {
+        aggregates::ensure_tables(&target_connection)?;
+    }
+    let since = match args.since {
+        Some(dates::Since::Timestamp(timestamp)) => Some(timestamp),
+        // Fixed before merging, so the games copied from one source don't move it for the next
+        Some(dates::Since::Auto) => {
+            target_connection.query_row("SELECT MAX(LastPlayed) FROM Games", NO_PARAMS, |row| row.get(0))?
+        }
+        None => None,
+    };
+    if let Some(since) = since {
+        info!("Only merging games last played since {}", dates::format_date(since));
+    }
+    if let Some(until) = args.until {
+        info!("Only merging games last played before {}", dates::format_date_time(until));
+    }
+    let mut journal = args.conflicts.as_ref().map(|_| conflicts::Journal::default());
+    let run = history::start_run(&target_connection)?;
+    let options = MergeOptions {
+        summary_tables: args.summary_tables,
+        merge_log: args.merge_log,
+        since,
+        until: args.until,
+        skip_errors: args.skip_errors,
+        copy_extra_tables: args.copy_extra_tables,
+        explain_dups: args.explain_dups,
+        exclude_modded: args.exclude_modded,
+        skip_ai_only: args.skip_ai_only,
+        filters: filters::Filters {
+            victories: args.victory.clone(),
+            leaders: args.leader.clone(),
+            civilizations: args.civ.clone(),
+            maps: args.map.clone(),
+            map_sizes: args.map_size.clone(),
+            speeds: args.speed.clone(),
+            completed_only: args.completed_only,
+            only_games: args.only_games.clone(),
+            exclude_games: args.exclude_games.clone(),
+        },
+        interactive: if args.interactive { Some(interactive::Prompt::new(names.clone())) } else { None },
+        rename_maps: args.rename_map.clone(),
+        dedupe_by: args.dedupe_by,
+        shared_history: args.shared_history,
+        on_conflict: args.on_conflict,
+        target_path: target_path.clone(),
+        events,
+    };
+
+    let mut summaries = Vec::new();
+    for source in merged {
+        let summary = merge_source(
+            &source.path,
+            &target_connection,
+            game_cache.as_mut(),
+            run,
+            &options,
+            journal.as_mut(),
+        );
+        let summary = match summary {
+            Ok(summary) => summary,
+            Err(e) => {
+                options.events.emit(
+                    "error",
+                    json!({ "source": source.path.to_string_lossy(), "message": e.to_string() }),
+                );
+                return Err(e);
+            }
+        };
+        summaries.push((source, summary));
+    }
+
+    let dropped = match &args.ancestor {
+        Some(ancestor) => {
+            let mut paths = vec![&base.path];
+            paths.extend(sources.iter().map(|source| &source.path));
+            drop_deleted_games(ancestor, &paths, &target_connection, &target_path, run, journal.as_mut())?
+        }
+        None => 0,
+    };
+
+    if args.dry_run {
+        drop(transaction);
+        drop(target_connection);
+        std::fs::remove_file(&target_path)?;
+        return dry_run_report(&reported_target, &base, &summaries, dropped, args.fresh_target, args.ancestor.as_ref());
+    }
+
+    stamp_merge_metadata(&target_connection)?;
+    #[cfg(feature = "charts")]
+    if let Some(report) = &args.report {
+        report::write_report(&target_connection, report, names)?;
+    }
+    if let (Some(journal), Some(path)) = (&journal, &args.conflicts) {
+        journal.save(path)?;
+    }
+    if let Some(cache) = &game_cache {
+        cache.save(&target_connection)?;
+    }
+    transaction.commit()?;
+    drop(target_connection);
+    let encrypted = cipher::encrypt(&target_path)?;
+    if let Some(generation) = generation {
+        generation.commit()?;
+    }
+
+    // Keep stdout clean when the report is streamed there
+    let mut lines = if args.fresh_target {
+        vec![format!("Created {:?} from the bundled schema", &target_path)]
+    } else {
+        vec![format!("Base: {:?} (from {:?})", &base.path, &base.origin)]
+    };
+    if args.exclude_modded && !args.fresh_target {
+        lines.push(format!("Excluded {} modded game(s) of the base", base_excluded));
+    }
+    if args.skip_ai_only && !args.fresh_target {
+        lines.push(format!("Skipped {} AI-only game(s) of the base", base_ai_only));
+    }
+    for (source, summary) in &summaries {
+        let mut line = format!(
+            "Merged {:?} (from {:?}): {} game(s) copied, {} duplicate(s) skipped",
+            &source.path, &source.origin, summary.games_copied, summary.games_skipped
+        );
+        if args.exclude_modded {
+            line.push_str(&format!(", {} modded game(s) excluded", summary.games_excluded));
+        }
+        if args.skip_ai_only {
+            line.push_str(&format!(", {} AI-only game(s) skipped", summary.games_ai_only));
+        }
+        if summary.games_filtered > 0 {
+            line.push_str(&format!(", {} game(s) filtered out", summary.games_filtered));
+        }
+        if args.interactive {
+            line.push_str(&format!(", {} game(s) declined", summary.games_declined));
+        }
+        if args.shared_history {
+            line.push_str(&format!(" - {} of them in the shared history", summary.games_shared));
+        }
+        if summary.games_replaced > 0 {
+            line.push_str(&format!(", {} target game(s) replaced", summary.games_replaced));
+        }
+        if args.since.is_some() {
+            line.push_str(&format!(", {} older game(s) ignored", summary.games_older));
+        }
+        if args.until.is_some() {
+            line.push_str(&format!(", {} newer game(s) ignored", summary.games_newer));
+        }
+        if !summary.games_failed.is_empty() {
+            let failed: Vec<String> = summary.games_failed.iter().map(|game_id| game_id.to_string()).collect();
+            line.push_str(&format!(", game(s) {} skipped on errors", failed.join(", ")));
+        }
+        if summary.rulesets_added > 0 {
+            line.push_str(&format!(", {} ruleset(s) added", summary.rulesets_added));
+        }
+        if summary.extra_rows_copied > 0 {
+            line.push_str(&format!(", {} row(s) of extra tables copied", summary.extra_rows_copied));
+        }
+        lines.push(line);
+    }
+    if encrypted {
+        lines.push(format!("Encrypted {:?}", &target_path));
+    }
+    if let (Some(journal), Some(path)) = (&journal, &args.conflicts) {
+        lines.push(format!("Journaled {} conflict(s) to {:?}", journal.len(), path));
+    }
+    if let Some(ancestor) = &args.ancestor {
+        lines.push(format!("Dropped {} game(s) deleted since {:?}", dropped, ancestor));
+    }
+    for duplicate in &duplicates {
+        lines.push(format!(
+            "Skipped {:?} (from {:?}): Same database as {:?}",
+            &duplicate.source.path, &duplicate.source.origin, &duplicate.duplicate_of
+        ));
+    }
+
+    let mut totals = SourceSummary::default();
+    for (_, summary) in &summaries {
+        totals.games_scanned += summary.games_scanned;
+        totals.games_copied += summary.games_copied;
+        totals.games_skipped += summary.games_skipped;
+        for (total, rows) in totals.rows_copied.iter_mut().zip(&summary.rows_copied) {
+            *total += rows;
+        }
+    }
+    let elapsed = started.elapsed().as_secs_f64();
+    lines.push("Summary".to_string());
+    lines.push(format!("  Games scanned:               {}", totals.games_scanned));
+    lines.push(format!("  Games copied:                {}", totals.games_copied));
+    lines.push(format!("  Duplicates skipped:          {}", totals.games_skipped));
+    for (table, rows) in COUNTED_TABLES.iter().zip(&totals.rows_copied) {
+        lines.push(format!("  {:<28} {}", format!("{} copied:", table), rows));
+    }
+    lines.push(format!("  Elapsed:                     {:.2}s", elapsed));
+    if let Some(path) = &args.summary_json {
+        let rows: serde_json::Map<String, serde_json::Value> = COUNTED_TABLES
+            .iter()
+            .zip(&totals.rows_copied)
+            .map(|(table, rows)| (table.to_string(), json!(rows)))
+            .collect();
+        let summary = json!({
+            "target": target_path.to_string_lossy(),
+            "gamesScanned": totals.games_scanned,
+            "gamesCopied": totals.games_copied,
+            "gamesSkipped": totals.games_skipped,
+            "rowsCopied": rows,
+            "elapsedSeconds": elapsed,
+            "sources": summaries.iter().map(|(source, summary)| json!({
+                "path": source.path.to_string_lossy(),
+                "gamesScanned": summary.games_scanned,
+                "gamesCopied": summary.games_copied,
+                "gamesSkipped": summary.games_skipped,
+                "gamesFailed": summary.games_failed,
+            })).collect::<Vec<_>>(),
+        });
+        output::write(Some(path), &format!("{}\n", serde_json::to_string_pretty(&summary)?))?;
+    }
+    options.events.emit(
+        "merge_finished",
+        json!({
+            "target": target_path.to_string_lossy(),
+            "copied": summaries.iter().map(|(_, summary)| summary.games_copied).sum::<i32>(),
+            "skipped": summaries.iter().map(|(_, summary)| summary.games_skipped).sum::<i32>(),
+        }),
+    );
+    #[cfg(feature = "charts")]
+    let streamed_paths = [&args.report, &args.events, &args.summary_json];
+    #[cfg(not(feature = "charts"))]
+    let streamed_paths = [&args.events, &args.summary_json];
+    let streamed = streamed_paths
+        .iter()
+        .any(|path| path.as_deref().is_some_and(|path| output::is_stdout(Some(path))));
+    for line in lines {
+        if streamed {
+            eprintln!("{}", line);
         } else {
-            let copied_game_data_point_values = copy_game_data_point_value(&source_connection2, g.game_id, &target_connection, row_id)?;
-            copy_game_objects(&source_connection2, g.game_id, &target_connection, row_id, copied_game_data_point_values)?;
-            info!("Copied game {} to {}", &g.game_id, &row_id);
+            println!("{}", line);
         }
     }
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn database() -> Connection {
+        let con = Connection::open_in_memory().unwrap();
+        schema::create(&con).unwrap();
+        con
+    }
+
+    fn add_game(con: &Connection, game_id: GameId) {
+        con.execute(
+            "INSERT INTO Games (GameId, Ruleset, GameMode, TurnCount, GameSpeedType, MapSizeType, Map, StartEraType, \
+             StartTurn, LastPlayed) VALUES (?, 'RULESET_STANDARD', 0, 100, 'GAMESPEED_STANDARD', 'MAPSIZE_SMALL', \
+             'Continents.lua', 'ERA_ANCIENT', 1, 0)",
+            params![game_id],
+        )
+        .unwrap();
+    }
+
+    fn add_object(con: &Connection, object_id: i64, game_id: GameId, player_object_id: Option<i64>) {
+        con.execute(
+            "INSERT INTO GameObjects (ObjectId, GameId, PlayerObjectId, Type) VALUES (?, ?, ?, 'PLAYER')",
+            params![object_id, game_id, player_object_id],
+        )
+        .unwrap();
+    }
+
+    fn add_player(con: &Connection, player_object_id: i64, leader: &str) {
+        con.execute(
+            "INSERT INTO GamePlayers (PlayerObjectId, IsLocal, IsAI, IsMajor, LeaderType, Score, PlayerId, TeamId) \
+             VALUES (?, 1, 0, 1, ?, 100, 0, 0)",
+            params![player_object_id, leader],
+        )
+        .unwrap();
+    }
+
+    /// ObjectIds & PlayerObjectIds of the game's objects in `con`.
+    fn objects(con: &Connection, game_id: GameId) -> Vec<(i64, Option<i64>)> {
+        let mut stmt = con
+            .prepare("SELECT ObjectId, PlayerObjectId FROM GameObjects WHERE GameId = ? ORDER BY ObjectId")
+            .unwrap();
+        let rows = stmt.query_map(params![game_id], |row| Ok((row.get(0)?, row.get(1)?))).unwrap();
+        rows.collect::<Result<_>>().unwrap()
+    }
+
+    /// A source with game 1 of a player object 10 owning the objects 11 & 12, and a target whose game 1
+    /// has the objects 1 & 2 already - copied objects get new ObjectIds from 3 on. The source game is
+    /// mapped to the target's game 2.
+    fn setup() -> (Connection, Connection, IdMap) {
+        let source = database();
+        add_game(&source, 1);
+        add_object(&source, 10, 1, None);
+        add_object(&source, 11, 1, Some(10));
+        add_object(&source, 12, 1, Some(10));
+        let target = database();
+        add_game(&target, 1);
+        add_object(&target, 1, 1, None);
+        add_object(&target, 2, 1, Some(1));
+        add_game(&target, 2);
+        let mut ids = IdMap::default();
+        ids.insert(Table::Games, 1, 2);
+        (source, target, ids)
+    }
+
+    #[test]
+    fn copies_all_objects_without_exclusions() {
+        let (source, target, mut ids) = setup();
+        assert_eq!(copy_game_objects(&source, 1, &target, &mut ids).unwrap(), 3);
+        assert_eq!(ids.pairs(Table::GameObjects), vec![(10, 3), (11, 4), (12, 5)]);
+        assert_eq!(objects(&target, 2), vec![(3, None), (4, Some(3)), (5, Some(3))]);
+        assert_eq!(objects(&target, 1), vec![(1, None), (2, Some(1))]);
+    }
+
+    #[test]
+    fn skips_the_one_object_copied_already() {
+        let (source, target, mut ids) = setup();
+        add_object(&target, 3, 2, None);
+        ids.insert(Table::GameObjects, 10, 3);
+        assert_eq!(copy_game_objects(&source, 1, &target, &mut ids).unwrap(), 2);
+        assert_eq!(ids.pairs(Table::GameObjects), vec![(10, 3), (11, 4), (12, 5)]);
+        assert_eq!(objects(&target, 2), vec![(3, None), (4, Some(3)), (5, Some(3))]);
+    }
+
+    #[test]
+    fn skips_the_objects_copied_already() {
+        let (source, target, mut ids) = setup();
+        add_object(&target, 3, 2, None);
+        add_object(&target, 4, 2, Some(3));
+        ids.insert(Table::GameObjects, 10, 3);
+        ids.insert(Table::GameObjects, 12, 4);
+        assert_eq!(copy_game_objects(&source, 1, &target, &mut ids).unwrap(), 1);
+        assert_eq!(ids.pairs(Table::GameObjects), vec![(10, 3), (11, 5), (12, 4)]);
+        assert_eq!(objects(&target, 2), vec![(3, None), (4, Some(3)), (5, Some(3))]);
+    }
+
+    #[test]
+    fn copies_no_objects_of_a_game_without_any() {
+        let (source, target, mut ids) = setup();
+        add_game(&source, 2);
+        ids.insert(Table::Games, 2, 3);
+        assert_eq!(copy_game_objects(&source, 2, &target, &mut ids).unwrap(), 0);
+        assert!(ids.pairs(Table::GameObjects).is_empty());
+        assert!(objects(&target, 3).is_empty());
+    }
+
+    /// Copies the objects & players of the source's game 1 as the target's `game_id`.
+    fn copy_players(source: &Connection, target: &Connection, game_id: GameId) -> IdMap {
+        let mut ids = IdMap::default();
+        ids.insert(Table::Games, 1, game_id);
+        copy_game_objects(source, 1, target, &mut ids).unwrap();
+        assert_eq!(copy_game_players(source, 1, target, &mut ids).unwrap(), 1);
+        ids
+    }
+
+    #[test]
+    fn copies_the_players_of_games_sharing_player_object_ids() {
+        // Two sources whose games both have the player object 10, owning the object 11
+        let sources: Vec<Connection> = ["LEADER_GANDHI", "LEADER_GILGAMESH"]
+            .iter()
+            .map(|leader| {
+                let source = database();
+                add_game(&source, 1);
+                add_object(&source, 10, 1, None);
+                add_object(&source, 11, 1, Some(10));
+                add_player(&source, 10, leader);
+                source
+            })
+            .collect();
+        let target = database();
+        add_game(&target, 1);
+        add_game(&target, 2);
+        let first = copy_players(&sources[0], &target, 1);
+        let second = copy_players(&sources[1], &target, 2);
+
+        let (first_player, second_player) = (first.get(Table::GamePlayers, 10), second.get(Table::GamePlayers, 10));
+        assert_eq!(first_player, Some(1));
+        assert_eq!(second_player, Some(3));
+        assert_eq!(objects(&target, 1), vec![(1, None), (2, Some(1))]);
+        assert_eq!(objects(&target, 2), vec![(3, None), (4, Some(3))]);
+        let mut stmt = target
+            .prepare(
+                "SELECT go.GameId, gp.PlayerObjectId, gp.LeaderType FROM GamePlayers gp \
+                 JOIN GameObjects go ON go.ObjectId = gp.PlayerObjectId ORDER BY go.GameId",
+            )
+            .unwrap();
+        let players: Vec<(GameId, i64, String)> = stmt
+            .query_map(NO_PARAMS, |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)))
+            .unwrap()
+            .collect::<Result<_>>()
+            .unwrap();
+        assert_eq!(
+            players,
+            vec![(1, 1, "LEADER_GANDHI".to_string()), (2, 3, "LEADER_GILGAMESH".to_string())]
+        );
+    }
+
+    /// Merges a source whose game 1, played at `source_played` with Gandhi scoring 100, conflicts with
+    /// the target's game 1, played at 1000 with Gandhi scoring 50. Returns the target's games & the
+    /// journal of the games dropped.
+    fn merge_conflict(on_conflict: conflicts::Keep, source_played: i64) -> (Vec<(GameId, i64)>, serde_json::Value) {
+        let scratch = std::env::temp_dir().join(format!("civ6-hof-merge-conflict-{}-{:?}", std::process::id(), on_conflict));
+        let (source_path, journal_path) = (scratch.with_extension("sqlite"), scratch.with_extension("json"));
+        let source = Connection::open(&source_path).unwrap();
+        schema::create(&source).unwrap();
+        add_game(&source, 1);
+        source.execute("UPDATE Games SET LastPlayed = ?", params![source_played]).unwrap();
+        add_object(&source, 10, 1, Some(10));
+        add_player(&source, 10, "LEADER_GANDHI");
+        drop(source);
+
+        let target = database();
+        add_game(&target, 1);
+        target.execute("UPDATE Games SET LastPlayed = 1000", NO_PARAMS).unwrap();
+        add_object(&target, 1, 1, Some(1));
+        add_player(&target, 1, "LEADER_GANDHI");
+        target.execute("UPDATE GamePlayers SET Score = 50", NO_PARAMS).unwrap();
+
+        let options = MergeOptions {
+            dedupe_by: dedupe::Strategy::GameId,
+            on_conflict,
+            target_path: "target.sqlite".into(),
+            ..MergeOptions::default()
+        };
+        let mut journal = conflicts::Journal::default();
+        let run = history::start_run(&target).unwrap();
+        merge_source(&source_path, &target, None, run, &options, Some(&mut journal)).unwrap();
+        journal.save(&journal_path).unwrap();
+        let journaled = read_json(&journal_path).unwrap();
+        std::fs::remove_file(&source_path).unwrap();
+        std::fs::remove_file(&journal_path).unwrap();
+
+        let mut stmt = target.prepare("SELECT GameId, LastPlayed FROM Games ORDER BY GameId").unwrap();
+        let games = stmt.query_map(NO_PARAMS, |row| Ok((row.get(0)?, row.get(1)?))).unwrap();
+        (games.collect::<Result<_>>().unwrap(), journaled)
+    }
+
+    /// Database, GameId & score of each game in the journal.
+    fn journaled(journal: &serde_json::Value) -> Vec<(String, GameId, i64)> {
+        journal["games"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|game| {
+                let entry = &game["Journal"];
+                let database = entry["database"].as_str().unwrap().to_string();
+                (database, entry["gameId"].as_i64().unwrap(), game["GamePlayers"][0]["Score"].as_i64().unwrap())
+            })
+            .collect()
+    }
+
+    #[test]
+    fn keep_target_skips_the_source_game_and_journals_it() {
+        let (games, journal) = merge_conflict(conflicts::Keep::Target, 2000);
+        assert_eq!(games, vec![(1, 1000)]);
+        let journaled = journaled(&journal);
+        assert_eq!(journaled.len(), 1);
+        assert!(journaled[0].0.contains("civ6-hof-merge-conflict-"));
+        assert_eq!((journaled[0].1, journaled[0].2), (1, 100));
+    }
+
+    #[test]
+    fn keep_source_replaces_the_target_game_and_journals_it() {
+        let (games, journal) = merge_conflict(conflicts::Keep::Source, 500);
+        assert_eq!(games, vec![(2, 500)]);
+        assert_eq!(journaled(&journal), vec![("target.sqlite".to_string(), 1, 50)]);
+    }
+
+    #[test]
+    fn keep_latest_replaces_an_older_target_game_and_journals_it() {
+        let (games, journal) = merge_conflict(conflicts::Keep::Latest, 2000);
+        assert_eq!(games, vec![(2, 2000)]);
+        assert_eq!(journaled(&journal), vec![("target.sqlite".to_string(), 1, 50)]);
+    }
+
+    #[test]
+    fn keep_latest_skips_an_older_source_game_and_journals_it() {
+        let (games, journal) = merge_conflict(conflicts::Keep::Latest, 500);
+        assert_eq!(games, vec![(1, 1000)]);
+        let journaled = journaled(&journal);
+        assert_eq!(journaled.len(), 1);
+        assert!(journaled[0].0.contains("civ6-hof-merge-conflict-"));
+        assert_eq!((journaled[0].1, journaled[0].2), (1, 100));
+    }
+
+    #[test]
+    fn parses_sizes_with_unit_suffixes() {
+        for (text, size) in &[
+            ("0", 0),
+            ("512", 512),
+            ("512K", 512 << 10),
+            ("512k", 512 << 10),
+            ("64M", 64 << 20),
+            ("64m", 64 << 20),
+            ("1G", 1 << 30),
+            ("2g", 2 << 30),
+            (" 3 M ", 3 << 20),
+            ("17179869183G", 17_179_869_183 << 30),
+        ] {
+            assert_eq!(parse_size(text), Ok(*size), "{:?}", text);
+        }
+    }
+
+    #[test]
+    fn refuses_invalid_sizes() {
+        for text in &["", "K", "-1", "1.5G", "1T", "1KB", "one", "17179869184G", "18446744073709551616"] {
+            assert!(parse_size(text).is_err(), "{:?}", text);
+        }
+    }
+}
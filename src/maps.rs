@@ -0,0 +1,63 @@
+//! Map identifiers: `Games.Map` holds the map script in different path forms
+//! across platforms & game versions, e.g. `Continents.lua` or
+//! `..\Base\Assets\Maps\Continents.lua`.
+
+/// Alternative names of map scripts, by their canonical name.
+const ALIASES: &[(&str, &str)] = &[("continents_and_islands", "continents_islands")];
+
+/// The form of a map identifier used to compare games: The script's base
+/// name in lower case without `.lua`, spaces as underscores & aliases resolved.
+pub fn canonical(map: &str) -> String {
+    let name = map.rsplit(['/', '\\']).next().unwrap_or(map).trim().to_lowercase();
+    let name = name.strip_suffix(".lua").unwrap_or(&name).replace(' ', "_");
+    match ALIASES.iter().find(|(alias, _)| *alias == name) {
+        Some((_, canonical)) => canonical.to_string(),
+        None => name,
+    }
+}
+
+/// A map script renamed during the merge: `--rename-map OLD=NEW`.
+#[derive(Clone, Debug)]
+pub struct Rename {
+    from: String,
+    to: String,
+}
+
+impl std::str::FromStr for Rename {
+    type Err = String;
+
+    fn from_str(rename: &str) -> Result<Self, Self::Err> {
+        match rename.split_once('=') {
+            Some((from, to)) if !from.trim().is_empty() && !to.trim().is_empty() => Ok(Rename {
+                from: canonical(from),
+                to: to.trim().to_string(),
+            }),
+            _ => Err(format!("Invalid map rename {:?}, expected OLD=NEW", rename)),
+        }
+    }
+}
+
+/// The new name of `map` if one of the renames applies to it.
+pub fn renamed<'a>(renames: &'a [Rename], map: &str) -> Option<&'a str> {
+    let map = canonical(map);
+    renames
+        .iter()
+        .find(|rename| rename.from == map)
+        .map(|rename| rename.to.as_str())
+}
+
+/// Renames the maps of all games already in a database.
+pub fn rename_all(con: &rusqlite::Connection, renames: &[Rename]) -> rusqlite::Result<usize> {
+    let mut stmt = con.prepare("SELECT DISTINCT Map FROM Games")?;
+    let maps = stmt
+        .query_map(rusqlite::NO_PARAMS, |row| row.get::<_, String>(0))?
+        .collect::<rusqlite::Result<Vec<String>>>()?;
+
+    let mut renamed_games = 0;
+    for map in maps {
+        if let Some(to) = renamed(renames, &map) {
+            renamed_games += con.execute("UPDATE Games SET Map = ? WHERE Map = ?", rusqlite::params![to, map])?;
+        }
+    }
+    Ok(renamed_games)
+}
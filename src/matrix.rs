@@ -0,0 +1,292 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use rusqlite::{Connection, ToSql, NO_PARAMS};
+
+use crate::names::Names;
+use crate::profiles::PROFILE_JOIN;
+use crate::table::Table;
+use crate::LOCAL_PLAYER_JOIN;
+
+/// Victory types of the base game & expansions, shown even if never achieved.
+const VICTORY_TYPES: [&str; 6] = [
+    "VICTORY_TECHNOLOGY",
+    "VICTORY_CULTURE",
+    "VICTORY_CONQUEST",
+    "VICTORY_RELIGIOUS",
+    "VICTORY_DIPLOMATIC",
+    "VICTORY_DEFAULT",
+];
+
+/// Leader × victory type grid of the local player's games.
+pub struct Matrix {
+    pub victory_types: Vec<String>,
+    pub rows: Vec<MatrixRow>,
+}
+
+pub struct MatrixRow {
+    pub leader_type: String,
+    /// Number of games the local player played with this leader
+    pub games: i64,
+    pub won: BTreeSet<String>,
+}
+
+impl MatrixRow {
+    pub fn cell(&self, victory_type: &str) -> Cell {
+        if self.won.contains(victory_type) {
+            Cell::Won
+        } else if self.games > 0 {
+            Cell::Attempted
+        } else {
+            Cell::NeverPlayed
+        }
+    }
+}
+
+#[derive(PartialEq)]
+pub enum Cell {
+    Won,
+    Attempted,
+    NeverPlayed,
+}
+
+/// Strips the `LEADER_`/`VICTORY_` style prefix of a type for display.
+pub fn short_name(type_name: &str) -> &str {
+    type_name.split_once('_').map_or(type_name, |(_, name)| name)
+}
+
+/// Builds the grid from all leaders that appear in the database - also as
+/// opponents, so never played leaders show up as empty rows. With a
+/// `profile` only that profile's games count.
+pub fn load(con: &Connection, profile: Option<&str>) -> rusqlite::Result<Matrix> {
+    let mut leaders: BTreeMap<String, MatrixRow> = BTreeMap::new();
+    let mut stmt = con.prepare("SELECT DISTINCT LeaderType FROM GamePlayers WHERE IsMajor")?;
+    for leader_type in stmt.query_map(NO_PARAMS, |row| row.get::<_, String>(0))? {
+        let leader_type = leader_type?;
+        leaders.insert(
+            leader_type.clone(),
+            MatrixRow {
+                leader_type,
+                games: 0,
+                won: BTreeSet::new(),
+            },
+        );
+    }
+
+    let mut victory_types: Vec<String> = VICTORY_TYPES.iter().map(|v| v.to_string()).collect();
+    let mut stmt = match profile {
+        Some(_) => con.prepare(&format!(
+            "SELECT lp.LeaderType, g.VictoryType, g.VictorTeamId = lp.TeamId FROM Games g {} {} \
+             WHERE lp.LeaderType IS NOT NULL AND mp.Profile = ?",
+            LOCAL_PLAYER_JOIN, PROFILE_JOIN
+        ))?,
+        None => con.prepare(&format!(
+            "SELECT lp.LeaderType, g.VictoryType, g.VictorTeamId = lp.TeamId FROM Games g {} WHERE lp.LeaderType IS NOT NULL",
+            LOCAL_PLAYER_JOIN
+        ))?,
+    };
+    let values: Vec<&dyn ToSql> = profile.iter().map(|profile| profile as &dyn ToSql).collect();
+    let rows = stmt.query_map(values, |row| {
+        Ok((
+            row.get::<_, String>(0)?,
+            row.get::<_, Option<String>>(1)?,
+            row.get::<_, Option<bool>>(2)?.unwrap_or(false),
+        ))
+    })?;
+    for row in rows {
+        let (leader_type, victory_type, won) = row?;
+        let entry = leaders.entry(leader_type.clone()).or_insert_with(|| MatrixRow {
+            leader_type,
+            games: 0,
+            won: BTreeSet::new(),
+        });
+        entry.games += 1;
+        if let (Some(victory_type), true) = (victory_type, won) {
+            if !victory_types.contains(&victory_type) {
+                victory_types.push(victory_type.clone());
+            }
+            entry.won.insert(victory_type);
+        }
+    }
+
+    Ok(Matrix {
+        victory_types,
+        rows: leaders.into_values().collect(),
+    })
+}
+
+impl Matrix {
+    /// Leaders the local player has won every victory type with.
+    pub fn is_complete(&self, row: &MatrixRow) -> bool {
+        self.victory_types.iter().all(|victory_type| row.won.contains(victory_type))
+    }
+
+    fn won_count(&self) -> usize {
+        self.rows.iter().map(|row| row.won.len()).sum()
+    }
+
+    fn total_count(&self) -> usize {
+        self.rows.len() * self.victory_types.len()
+    }
+}
+
+fn mark(cell: Cell) -> &'static str {
+    match cell {
+        Cell::Won => "W",
+        Cell::Attempted => "-",
+        Cell::NeverPlayed => " ",
+    }
+}
+
+/// Renders the grid as a plain text table: `W` won, `-` leader played but
+/// not won this way, blank for never played leaders.
+pub fn render_text(matrix: &Matrix, names: &Names) -> String {
+    let width = matrix
+        .rows
+        .iter()
+        .map(|row| names.display(&row.leader_type).len())
+        .max()
+        .unwrap_or(0)
+        .max("Leader".len());
+
+    let mut text = format!("{:width$}", "Leader", width = width);
+    for victory_type in &matrix.victory_types {
+        text.push(' ');
+        text.push_str(names.display(victory_type));
+    }
+    text.push('\n');
+
+    for row in &matrix.rows {
+        text.push_str(&format!("{:width$}", names.display(&row.leader_type), width = width));
+        for victory_type in &matrix.victory_types {
+            text.push_str(&format!(
+                " {:^width$}",
+                mark(row.cell(victory_type)),
+                width = names.display(victory_type).len()
+            ));
+        }
+        text.push('\n');
+    }
+
+    text.push_str(&format!(
+        "{} of {} combinations won\n",
+        matrix.won_count(),
+        matrix.total_count()
+    ));
+    text
+}
+
+/// The grid as rows of `W`/`-`/empty cells.
+pub fn to_table(matrix: &Matrix, names: &Names) -> Table {
+    let mut headers = vec!["Leader"];
+    headers.extend(matrix.victory_types.iter().map(|victory_type| names.display(victory_type)));
+    let mut table = Table::new(&headers);
+    for row in &matrix.rows {
+        let mut cells = vec![names.display(&row.leader_type).to_string()];
+        cells.extend(
+            matrix
+                .victory_types
+                .iter()
+                .map(|victory_type| mark(row.cell(victory_type)).trim().to_string()),
+        );
+        table.push(cells);
+    }
+    table
+}
+
+/// Renders the grid as a Markdown table, leaders with all victory types won in bold.
+pub fn render_markdown(matrix: &Matrix, names: &Names, highlight: bool) -> String {
+    let mut markdown = String::from("| Leader |");
+    for victory_type in &matrix.victory_types {
+        markdown.push_str(&format!(" {} |", names.display(victory_type)));
+    }
+    markdown.push_str("\n|---|");
+    markdown.push_str(&":---:|".repeat(matrix.victory_types.len()));
+    markdown.push('\n');
+
+    for row in &matrix.rows {
+        let leader = names.display(&row.leader_type);
+        if highlight && matrix.is_complete(row) {
+            markdown.push_str(&format!("| **{}** |", leader));
+        } else {
+            markdown.push_str(&format!("| {} |", leader));
+        }
+        for victory_type in &matrix.victory_types {
+            let cell = match row.cell(victory_type) {
+                Cell::Won => "✅",
+                Cell::Attempted => "·",
+                Cell::NeverPlayed => "",
+            };
+            markdown.push_str(&format!(" {} |", cell));
+        }
+        markdown.push('\n');
+    }
+
+    markdown.push_str(&format!(
+        "\n{} of {} combinations won\n",
+        matrix.won_count(),
+        matrix.total_count()
+    ));
+    markdown
+}
+
+/// Renders the grid as an SVG image, rows of leaders with all victory types
+/// won get the `highlight` background color.
+pub fn render_svg(matrix: &Matrix, names: &Names, highlight: Option<&str>) -> String {
+    const LEADER_WIDTH: usize = 160;
+    const CELL_WIDTH: usize = 90;
+    const ROW_HEIGHT: usize = 24;
+
+    let width = LEADER_WIDTH + CELL_WIDTH * matrix.victory_types.len();
+    let height = ROW_HEIGHT * (matrix.rows.len() + 2);
+    let mut svg = format!(
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{0}\" height=\"{1}\" viewBox=\"0 0 {0} {1}\" \
+         font-family=\"sans-serif\" font-size=\"12\">\n<rect width=\"100%\" height=\"100%\" fill=\"white\"/>\n",
+        width, height
+    );
+
+    for (column, victory_type) in matrix.victory_types.iter().enumerate() {
+        svg.push_str(&format!(
+            "<text x=\"{}\" y=\"16\" text-anchor=\"middle\" font-weight=\"bold\">{}</text>\n",
+            LEADER_WIDTH + CELL_WIDTH * column + CELL_WIDTH / 2,
+            names.display(victory_type)
+        ));
+    }
+
+    for (index, row) in matrix.rows.iter().enumerate() {
+        let y = ROW_HEIGHT * (index + 1);
+        if let (Some(color), true) = (highlight, matrix.is_complete(row)) {
+            svg.push_str(&format!(
+                "<rect x=\"0\" y=\"{}\" width=\"{}\" height=\"{}\" fill=\"{}\"/>\n",
+                y, width, ROW_HEIGHT, color
+            ));
+        }
+        svg.push_str(&format!(
+            "<text x=\"6\" y=\"{}\">{}</text>\n",
+            y + 16,
+            names.display(&row.leader_type)
+        ));
+        for (column, victory_type) in matrix.victory_types.iter().enumerate() {
+            let fill = match row.cell(victory_type) {
+                Cell::Won => "#4caf50",
+                Cell::Attempted => "#e0e0e0",
+                Cell::NeverPlayed => "none",
+            };
+            svg.push_str(&format!(
+                "<rect x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\" fill=\"{}\" stroke=\"#bdbdbd\"/>\n",
+                LEADER_WIDTH + CELL_WIDTH * column + 2,
+                y + 2,
+                CELL_WIDTH - 4,
+                ROW_HEIGHT - 4,
+                fill
+            ));
+        }
+    }
+
+    svg.push_str(&format!(
+        "<text x=\"6\" y=\"{}\">{} of {} combinations won</text>\n</svg>\n",
+        height - 8,
+        matrix.won_count(),
+        matrix.total_count()
+    ));
+    svg
+}
@@ -0,0 +1,68 @@
+use std::collections::HashMap;
+use std::hash::Hasher;
+
+use rusqlite::{params, Connection, NO_PARAMS};
+use serde_rusqlite::from_rows;
+
+use crate::hash::Fnv64;
+use crate::{cache, Game, GameId};
+
+/// The source games each merge already handled - copied or matched to a
+/// target game - by source, so merging the same source again skips them
+/// without checking the target for duplicates.
+const TABLE: &str = "CREATE TABLE IF NOT EXISTS MergeLog (Source TEXT NOT NULL, SourceGameId INTEGER NOT NULL, \
+    GameHash TEXT NOT NULL, GameId INTEGER NOT NULL, PRIMARY KEY (Source, SourceGameId))";
+
+/// The logged games of one source.
+pub struct MergeLog {
+    source: String,
+    games: HashMap<GameId, String>,
+}
+
+/// Identifies a source by its oldest game, which stays the same while the
+/// game adds new ones - unlike the file's content or path.
+fn source_fingerprint(con: &Connection) -> Result<Option<String>, Box<dyn std::error::Error>> {
+    let mut stmt = con.prepare("SELECT * FROM Games ORDER BY GameId LIMIT 1")?;
+    let oldest = match from_rows::<Game>(stmt.query(NO_PARAMS)?).next() {
+        Some(game) => game?,
+        None => return Ok(None),
+    };
+    let mut hasher = Fnv64::default();
+    hasher.write_i64(oldest.game_id);
+    hasher.write_u64(cache::game_hash(&oldest));
+    Ok(Some(format!("{:016x}", hasher.finish())))
+}
+
+impl MergeLog {
+    /// Loads what the target logged for the source, creating the table if needed.
+    pub fn load(source: &Connection, target: &Connection) -> Result<MergeLog, Box<dyn std::error::Error>> {
+        target.execute(TABLE, NO_PARAMS)?;
+        let mut log = MergeLog {
+            source: source_fingerprint(source)?.unwrap_or_default(),
+            games: HashMap::new(),
+        };
+        let mut stmt = target.prepare("SELECT SourceGameId, GameHash FROM MergeLog WHERE Source = ?")?;
+        let games = stmt.query_map(params![log.source], |row| Ok((row.get(0)?, row.get(1)?)))?;
+        for game in games {
+            let (game_id, hash) = game?;
+            log.games.insert(game_id, hash);
+        }
+        Ok(log)
+    }
+
+    /// Whether the game was handled before - & hasn't changed since.
+    pub fn contains(&self, game: &Game) -> bool {
+        self.games.get(&game.game_id) == Some(&format!("{:016x}", cache::game_hash(game)))
+    }
+
+    /// Logs that the source's `game` was copied as or matched to the target's `game_id`.
+    pub fn record(&mut self, target: &Connection, game: &Game, game_id: GameId) -> rusqlite::Result<()> {
+        let hash = format!("{:016x}", cache::game_hash(game));
+        target.execute(
+            "INSERT OR REPLACE INTO MergeLog (Source, SourceGameId, GameHash, GameId) VALUES (?, ?, ?, ?)",
+            params![self.source, game.game_id, hash, game_id],
+        )?;
+        self.games.insert(game.game_id, hash);
+        Ok(())
+    }
+}
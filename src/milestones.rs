@@ -0,0 +1,130 @@
+use rusqlite::{Connection, OptionalExtension, NO_PARAMS};
+
+use crate::dates;
+use crate::names::Names;
+use crate::{GameId, LOCAL_PLAYER_JOIN};
+
+/// Condition of games the local player (`lp`) won.
+const WON: &str = "g.VictorTeamId = lp.TeamId";
+
+/// A record of the archive, e.g. `Fastest Science victory` - `Trajan in 230 turns`.
+pub struct Milestone {
+    pub title: String,
+    pub record: String,
+}
+
+/// Leader, turns, score & date of a game, rendered into a record.
+struct Highlight {
+    game_id: GameId,
+    leader: String,
+    turns: i64,
+    score: i64,
+    last_played: i64,
+}
+
+fn highlight(con: &Connection, names: &Names, condition: &str, order: &str) -> rusqlite::Result<Option<Highlight>> {
+    con.query_row(
+        &format!(
+            "SELECT g.GameId, lp.LeaderType, lp.LeaderName, g.TurnCount, lp.Score, g.LastPlayed \
+             FROM Games g {} WHERE lp.LeaderType IS NOT NULL AND {} ORDER BY {} LIMIT 1",
+            LOCAL_PLAYER_JOIN, condition, order
+        ),
+        NO_PARAMS,
+        |row| {
+            let leader_type: String = row.get(1)?;
+            let leader_name: Option<String> = row.get(2)?;
+            Ok(Highlight {
+                game_id: row.get(0)?,
+                leader: names.leader(&leader_type, leader_name.as_deref()),
+                turns: row.get(3)?,
+                score: row.get(4)?,
+                last_played: row.get(5)?,
+            })
+        },
+    )
+    .optional()
+}
+
+/// Fun records of the archive: Fastest victories, highest score, longest game,
+/// first Deity win & the most successful leaders.
+pub fn compute(con: &Connection, names: &Names) -> rusqlite::Result<Vec<Milestone>> {
+    let mut milestones = Vec::new();
+    let game = |h: &Highlight| format!("game {}, {}", h.game_id, dates::format_date(h.last_played));
+
+    let mut stmt = con.prepare(&format!(
+        "SELECT DISTINCT g.VictoryType FROM Games g {} WHERE {} AND g.VictoryType IS NOT NULL ORDER BY 1",
+        LOCAL_PLAYER_JOIN, WON
+    ))?;
+    let victory_types = stmt
+        .query_map(NO_PARAMS, |row| row.get::<_, String>(0))?
+        .collect::<rusqlite::Result<Vec<String>>>()?;
+    for victory_type in victory_types {
+        let condition = format!("{} AND g.VictoryType = '{}'", WON, victory_type.replace('\'', "''"));
+        if let Some(h) = highlight(con, names, &condition, "g.TurnCount, g.LastPlayed")? {
+            milestones.push(Milestone {
+                title: format!("Fastest {} victory", names.display(&victory_type)),
+                record: format!("{} in {} turns ({})", h.leader, h.turns, game(&h)),
+            });
+        }
+    }
+
+    if let Some(h) = highlight(con, names, "1", "lp.Score DESC")? {
+        milestones.push(Milestone {
+            title: "Highest score".to_string(),
+            record: format!("{} with {} points ({})", h.leader, h.score, game(&h)),
+        });
+    }
+    if let Some(h) = highlight(con, names, "1", "g.TurnCount DESC")? {
+        milestones.push(Milestone {
+            title: "Longest game".to_string(),
+            record: format!("{} with {} turns ({})", h.leader, h.turns, game(&h)),
+        });
+    }
+    let deity = format!("{} AND lp.DifficultyType = 'DIFFICULTY_DEITY'", WON);
+    if let Some(h) = highlight(con, names, &deity, "g.LastPlayed")? {
+        milestones.push(Milestone {
+            title: "First Deity win".to_string(),
+            record: format!("{} in {} turns ({})", h.leader, h.turns, game(&h)),
+        });
+    }
+
+    let mut stmt = con.prepare(&format!(
+        "SELECT lp.LeaderType, MAX(lp.LeaderName), SUM({}) AS Wins FROM Games g {} \
+         WHERE lp.LeaderType IS NOT NULL GROUP BY 1 HAVING Wins > 0 ORDER BY Wins DESC, 1 LIMIT 3",
+        WON, LOCAL_PLAYER_JOIN
+    ))?;
+    let leaders = stmt
+        .query_map(NO_PARAMS, |row| {
+            let leader_type: String = row.get(0)?;
+            let leader_name: Option<String> = row.get(1)?;
+            let wins: i64 = row.get(2)?;
+            Ok(format!("{} ({})", names.leader(&leader_type, leader_name.as_deref()), wins))
+        })?
+        .collect::<rusqlite::Result<Vec<String>>>()?;
+    if !leaders.is_empty() {
+        milestones.push(Milestone {
+            title: "Most wins".to_string(),
+            record: leaders.join(", "),
+        });
+    }
+
+    let (games, wins): (i64, Option<i64>) = con.query_row(
+        &format!("SELECT COUNT(*), SUM({}) FROM Games g {}", WON, LOCAL_PLAYER_JOIN),
+        NO_PARAMS,
+        |row| Ok((row.get(0)?, row.get(1)?)),
+    )?;
+    milestones.push(Milestone {
+        title: "Games played".to_string(),
+        record: format!("{}, {} won", games, wins.unwrap_or(0)),
+    });
+    Ok(milestones)
+}
+
+/// Renders the milestones as a Markdown list, ready to be posted.
+pub fn render(milestones: &[Milestone]) -> String {
+    let mut text = String::from("## Hall of Fame milestones\n\n");
+    for milestone in milestones {
+        text.push_str(&format!("* **{}**: {}\n", milestone.title, milestone.record));
+    }
+    text
+}
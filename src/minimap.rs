@@ -0,0 +1,177 @@
+use std::collections::BTreeMap;
+use std::fmt::Write as _;
+
+use rusqlite::{params, Connection};
+
+use crate::names::Names;
+use crate::GameId;
+
+/// Width & height in plots of the official map sizes.
+const MAP_SIZES: [(&str, i64, i64); 6] = [
+    ("MAPSIZE_DUEL", 44, 26),
+    ("MAPSIZE_TINY", 60, 38),
+    ("MAPSIZE_SMALL", 74, 46),
+    ("MAPSIZE_STANDARD", 84, 54),
+    ("MAPSIZE_LARGE", 96, 60),
+    ("MAPSIZE_HUGE", 106, 66),
+];
+
+/// Colors of the objects' owners, in order of appearance.
+const PALETTE: [&str; 8] = [
+    "#4e79a7", "#f28e2b", "#e15759", "#76b7b2", "#59a14f", "#edc948", "#b07aa1", "#ff9da7",
+];
+
+/// Pixels between two plot centers.
+const PLOT: f64 = 10.0;
+const LEGEND_ROW: f64 = 18.0;
+
+/// Width & height in plots of a map size.
+pub fn dimensions(map_size: &str) -> Option<(i64, i64)> {
+    MAP_SIZES
+        .iter()
+        .find(|(known, _, _)| *known == map_size)
+        .map(|(_, width, height)| (*width, *height))
+}
+
+struct PlacedObject {
+    kind: String,
+    name: Option<String>,
+    plot: i64,
+    owner: Option<String>,
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;")
+}
+
+/// Renders the objects of a game with a plot - cities, wonders & other
+/// landmarks - on a grid of the game's map size, colored by owner.
+/// Plot 0 is the bottom left corner & odd rows are shifted by half a plot
+/// like the game's hexes.
+pub fn render_svg(con: &Connection, game_id: GameId, names: &Names) -> Result<String, Box<dyn std::error::Error>> {
+    let map_size: String = con
+        .query_row("SELECT MapSizeType FROM Games WHERE GameId = ?", params![game_id], |row| row.get(0))
+        .map_err(|_| format!("Game {} not found", game_id))?;
+    let (width, height) = dimensions(&map_size).ok_or_else(|| format!("Unknown map size {}", map_size))?;
+
+    let mut stmt = con.prepare(
+        "SELECT go.Type, go.Name, go.PlotIndex, gp.LeaderType, gp.LeaderName FROM GameObjects go \
+         LEFT JOIN GamePlayers gp ON gp.PlayerObjectId = go.PlayerObjectId \
+         WHERE go.GameId = ? AND go.PlotIndex IS NOT NULL ORDER BY go.ObjectId",
+    )?;
+    let objects = stmt
+        .query_map(params![game_id], |row| {
+            Ok(PlacedObject {
+                kind: row.get(0)?,
+                name: row.get(1)?,
+                plot: row.get(2)?,
+                owner: match row.get::<_, Option<String>>(3)? {
+                    Some(leader_type) => Some(names.leader(&leader_type, row.get::<_, Option<String>>(4)?.as_deref())),
+                    None => None,
+                },
+            })
+        })?
+        .collect::<rusqlite::Result<Vec<_>>>()?;
+
+    let mut colors: BTreeMap<String, &str> = BTreeMap::new();
+    for object in &objects {
+        let owner = object.owner.clone().unwrap_or_else(|| "Unowned".to_string());
+        let next = PALETTE[colors.len() % PALETTE.len()];
+        colors.entry(owner).or_insert(next);
+    }
+
+    let map_width = (width as f64 + 0.5) * PLOT;
+    let map_height = height as f64 * PLOT;
+    let svg_height = map_height + LEGEND_ROW * (colors.len() as f64 + 1.0);
+    let mut svg = format!(
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{0}\" height=\"{1}\" viewBox=\"0 0 {0} {1}\" \
+         font-family=\"sans-serif\" font-size=\"9\">\n<rect width=\"100%\" height=\"100%\" fill=\"white\"/>\n\
+         <rect width=\"{0}\" height=\"{2}\" fill=\"#dfe9f3\"/>\n",
+        map_width, svg_height, map_height
+    );
+    for x in (10..width).step_by(10) {
+        let _ = writeln!(
+            svg,
+            "<line x1=\"{0}\" y1=\"0\" x2=\"{0}\" y2=\"{1}\" stroke=\"#c3d3e3\"/>",
+            x as f64 * PLOT,
+            map_height
+        );
+    }
+    for y in (10..height).step_by(10) {
+        let _ = writeln!(
+            svg,
+            "<line x1=\"0\" y1=\"{0}\" x2=\"{1}\" y2=\"{0}\" stroke=\"#c3d3e3\"/>",
+            map_height - y as f64 * PLOT,
+            map_width
+        );
+    }
+
+    for object in &objects {
+        if object.plot < 0 || object.plot >= width * height {
+            continue;
+        }
+        let (x, y) = (object.plot % width, object.plot / width);
+        let cx = (x as f64 + 0.5 + if y % 2 == 1 { 0.5 } else { 0.0 }) * PLOT;
+        let cy = map_height - (y as f64 + 0.5) * PLOT;
+        let color = colors[object.owner.as_deref().unwrap_or("Unowned")];
+        let marker = match object.kind.as_str() {
+            "CITY" => format!(
+                "<rect x=\"{}\" y=\"{}\" width=\"{2}\" height=\"{2}\" fill=\"{3}\" stroke=\"black\"/>",
+                cx - PLOT * 0.4,
+                cy - PLOT * 0.4,
+                PLOT * 0.8,
+                color
+            ),
+            "WONDER" => format!(
+                "<polygon points=\"{},{} {},{} {},{}\" fill=\"{}\" stroke=\"black\"/>",
+                cx,
+                cy - PLOT * 0.5,
+                cx + PLOT * 0.45,
+                cy + PLOT * 0.35,
+                cx - PLOT * 0.45,
+                cy + PLOT * 0.35,
+                color
+            ),
+            _ => format!(
+                "<circle cx=\"{}\" cy=\"{}\" r=\"{}\" fill=\"{}\" stroke=\"black\"/>",
+                cx,
+                cy,
+                PLOT * 0.35,
+                color
+            ),
+        };
+        let label = object.name.as_deref().unwrap_or(&object.kind);
+        let _ = writeln!(
+            svg,
+            "<g><title>{0} ({1}, {2})</title>{3}<text x=\"{4}\" y=\"{5}\">{0}</text></g>",
+            escape(label),
+            x,
+            y,
+            marker,
+            cx + PLOT * 0.6,
+            cy + 3.0
+        );
+    }
+
+    for (index, (owner, color)) in colors.iter().enumerate() {
+        let y = map_height + LEGEND_ROW * (index as f64 + 1.0);
+        let _ = writeln!(
+            svg,
+            "<rect x=\"6\" y=\"{}\" width=\"10\" height=\"10\" fill=\"{}\"/><text x=\"22\" y=\"{}\" font-size=\"12\">{}</text>",
+            y - 10.0,
+            color,
+            y,
+            escape(owner)
+        );
+    }
+    let _ = writeln!(
+        svg,
+        "<text x=\"6\" y=\"{}\" font-size=\"12\">Game {}: {} objects on a {}x{} map - squares are cities, triangles wonders</text>\n</svg>",
+        svg_height - 4.0,
+        game_id,
+        objects.len(),
+        width,
+        height
+    );
+    Ok(svg)
+}
@@ -0,0 +1,47 @@
+use std::collections::HashMap;
+use std::path::Path;
+
+use log::info;
+
+use crate::matrix::short_name;
+
+/// Display names for LeaderType, CivilizationType & other type keys, e.g. for
+/// modded leaders the game's localization doesn't know.
+#[derive(Clone, Default)]
+pub struct Names(HashMap<String, String>);
+
+impl Names {
+    /// Reads a mapping file: Either CSV lines `LEADER_X,Name` or flat TOML
+    /// `LEADER_X = "Name"` - `#` comments & `[sections]` are ignored.
+    pub fn load(path: &Path) -> Result<Names, Box<dyn std::error::Error>> {
+        let mut names = HashMap::new();
+        for (number, line) in std::fs::read_to_string(path)?.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') || line.starts_with('[') {
+                continue;
+            }
+            let (key, name) = line
+                .split_once('=')
+                .or_else(|| line.split_once(','))
+                .ok_or_else(|| format!("{:?} line {}: Expected KEY = \"Name\" or KEY,Name", path, number + 1))?;
+            let unquote = |text: &str| text.trim().trim_matches('"').to_string();
+            names.insert(unquote(key), unquote(name));
+        }
+        info!("Loaded {} display names from {:?}", names.len(), &path);
+        Ok(Names(names))
+    }
+
+    /// The mapped name of a type key, otherwise the key without its prefix.
+    pub fn display<'a>(&'a self, key: &'a str) -> &'a str {
+        self.0.get(key).map_or_else(|| short_name(key), String::as_str)
+    }
+
+    /// The mapped name of a leader, otherwise the name stored in the game, otherwise the short key.
+    pub fn leader(&self, leader_type: &str, leader_name: Option<&str>) -> String {
+        match (self.0.get(leader_type), leader_name) {
+            (Some(name), _) => name.clone(),
+            (None, Some(name)) => name.to_string(),
+            (None, None) => short_name(leader_type).to_string(),
+        }
+    }
+}
@@ -0,0 +1,60 @@
+use std::io::{BufRead, Read, Write};
+use std::path::Path;
+
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+
+/// The first bytes of a gzip stream.
+pub const GZIP_MAGIC: [u8; 2] = [0x1f, 0x8b];
+
+/// Whether an output path means stdout: `-` or none at all.
+pub fn is_stdout(path: Option<&Path>) -> bool {
+    path.is_none_or(|path| path == Path::new("-"))
+}
+
+/// Writes `content` to a file, or to stdout for `-`/no path - logs always go
+/// to stderr, so the output can be piped into other tools. Files ending in
+/// `.gz` are gzip-compressed.
+pub fn write(path: Option<&Path>, content: &str) -> std::io::Result<()> {
+    match path {
+        Some(path) if !is_stdout(Some(path)) => {
+            if path.extension().is_some_and(|ext| ext == "gz") {
+                compress(path, content)
+            } else {
+                std::fs::write(path, content)
+            }
+        }
+        _ => print(content),
+    }
+}
+
+/// Writes `content` to stdout. A reader that went away - as `| head` does
+/// after enough lines - ends the output, not the program with a panic.
+pub fn print(content: &str) -> std::io::Result<()> {
+    let mut out = std::io::stdout().lock();
+    match out.write_all(content.as_bytes()).and_then(|()| out.flush()) {
+        Err(e) if e.kind() == std::io::ErrorKind::BrokenPipe => Ok(()),
+        result => result,
+    }
+}
+
+/// Writes `content` gzip-compressed, whatever the extension of `path`.
+pub fn compress(path: &Path, content: &str) -> std::io::Result<()> {
+    let file = std::io::BufWriter::new(std::fs::File::create(path)?);
+    let mut gzip = GzEncoder::new(file, Compression::default());
+    gzip.write_all(content.as_bytes())?;
+    gzip.finish()?.flush()
+}
+
+/// Reads a text file, decompressing it if it is gzip-compressed.
+pub fn read(path: &Path) -> std::io::Result<String> {
+    let mut file = std::io::BufReader::new(std::fs::File::open(path)?);
+    let mut content = String::new();
+    if file.fill_buf()?.starts_with(&GZIP_MAGIC) {
+        GzDecoder::new(file).read_to_string(&mut content)?;
+    } else {
+        file.read_to_string(&mut content)?;
+    }
+    Ok(content)
+}
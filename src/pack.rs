@@ -0,0 +1,68 @@
+//! `pack` & `unpack`: Games bundled into one gzip-compressed file to mail or
+//! post - the JSON export of the games with a manifest describing them - and
+//! merged from it into any Hall of Fame again.
+
+use rusqlite::Connection;
+use serde_json::json;
+
+use crate::names::Names;
+use crate::{dates, export, load_game, show, GameId};
+
+/// Marks a JSON document as a pack.
+const FORMAT: &str = "civ6-hof-merge pack";
+
+/// Version of the pack format `unpack` reads.
+const VERSION: i64 = 1;
+
+/// The export of the games with the manifest: The games' one-line summaries, where & when they were packed.
+pub fn pack(
+    con: &Connection,
+    names: &Names,
+    source: &std::path::Path,
+    game_ids: &[GameId],
+) -> Result<serde_json::Value, Box<dyn std::error::Error>> {
+    let mut games = Vec::new();
+    for game_id in game_ids {
+        games.push(json!({
+            "GameId": game_id,
+            "summary": show::describe(con, names, &load_game(con, *game_id)?)?,
+        }));
+    }
+    let created = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map_or(0, |elapsed| elapsed.as_secs() as i64);
+    let mut document = export::json(con, game_ids)?;
+    document["manifest"] = json!({
+        "format": FORMAT,
+        "version": VERSION,
+        "created": dates::format_date_time(created),
+        "source": source.file_name().map(|name| name.to_string_lossy()),
+        "games": games,
+    });
+    Ok(document)
+}
+
+/// The manifest of a pack, failing for other documents & newer pack versions.
+pub fn manifest(document: &serde_json::Value) -> Result<&serde_json::Value, String> {
+    let manifest = document
+        .get("manifest")
+        .filter(|manifest| manifest["format"] == FORMAT)
+        .ok_or("Not a civ6-hof-merge pack: No manifest")?;
+    match manifest["version"].as_i64() {
+        Some(version) if version <= VERSION => Ok(manifest),
+        version => Err(format!(
+            "Pack version {:?} is newer than this civ6-hof-merge reads ({})",
+            version, VERSION
+        )),
+    }
+}
+
+/// Prints the manifest: When & where the pack was made & its games.
+pub fn print_manifest(manifest: &serde_json::Value) {
+    let text = |key: &str| manifest[key].as_str().unwrap_or("?").to_string();
+    let games = manifest["games"].as_array().map_or(&[][..], Vec::as_slice);
+    println!("Pack of {} game(s) from {}, created {}", games.len(), text("source"), text("created"));
+    for game in games {
+        println!("  {}", game["summary"].as_str().unwrap_or("?"));
+    }
+}
@@ -0,0 +1,121 @@
+use std::io::{BufRead, Write};
+
+use log::info;
+use rusqlite::{params, Connection, ToSql, NO_PARAMS};
+
+use crate::{dates, GameId, LOCAL_PLAYER_JOIN};
+
+/// Joins the profile (`mp.Profile`) a game (`g`) is assigned to.
+pub const PROFILE_JOIN: &str = "LEFT JOIN MergeProfiles mp ON mp.GameId = g.GameId";
+
+/// Which games to assign to a profile - all given criteria have to match.
+#[derive(Default)]
+pub struct Rule {
+    pub game_ids: Vec<GameId>,
+    pub leader: Option<String>,
+    pub since: Option<i64>,
+    pub until: Option<i64>,
+}
+
+pub fn ensure_table(con: &Connection) -> rusqlite::Result<()> {
+    con.execute(
+        "CREATE TABLE IF NOT EXISTS MergeProfiles (GameId INTEGER PRIMARY KEY NOT NULL, Profile TEXT NOT NULL)",
+        NO_PARAMS,
+    )?;
+    Ok(())
+}
+
+/// Assigns the games matching `rule` to `profile`, asking for each game if `interactive`.
+pub fn assign(con: &Connection, profile: &str, rule: &Rule, interactive: bool) -> Result<usize, Box<dyn std::error::Error>> {
+    ensure_table(con)?;
+
+    let mut conditions = vec!["1".to_string()];
+    let mut values: Vec<Box<dyn ToSql>> = Vec::new();
+    if !rule.game_ids.is_empty() {
+        conditions.push(format!("g.GameId IN ({})", vec!["?"; rule.game_ids.len()].join(", ")));
+        for game_id in &rule.game_ids {
+            values.push(Box::new(*game_id));
+        }
+    }
+    if let Some(leader) = &rule.leader {
+        conditions.push("(lp.LeaderType = ? OR lp.LeaderName = ?)".to_string());
+        values.push(Box::new(leader.clone()));
+        values.push(Box::new(leader.clone()));
+    }
+    if let Some(since) = rule.since {
+        conditions.push("g.LastPlayed >= ?".to_string());
+        values.push(Box::new(since));
+    }
+    if let Some(until) = rule.until {
+        conditions.push("g.LastPlayed < ?".to_string());
+        values.push(Box::new(until));
+    }
+
+    let mut stmt = con.prepare(&format!(
+        "SELECT g.GameId, g.LastPlayed, COALESCE(lp.LeaderName, lp.LeaderType), g.Map, g.TurnCount, g.VictoryType, mp.Profile \
+         FROM Games g {} {} WHERE {} ORDER BY g.LastPlayed",
+        LOCAL_PLAYER_JOIN,
+        PROFILE_JOIN,
+        conditions.join(" AND ")
+    ))?;
+    let games = stmt
+        .query_map(values.iter().map(|v| v.as_ref()), |row| {
+            Ok((
+                row.get::<_, GameId>(0)?,
+                format!(
+                    "Game {} on {}: {} on {}, {} turns, {} (profile {})",
+                    row.get::<_, GameId>(0)?,
+                    dates::format_date(row.get(1)?),
+                    row.get::<_, Option<String>>(2)?.unwrap_or_else(|| "-".to_string()),
+                    row.get::<_, String>(3)?,
+                    row.get::<_, i32>(4)?,
+                    row.get::<_, Option<String>>(5)?.unwrap_or_else(|| "no victory".to_string()),
+                    row.get::<_, Option<String>>(6)?.unwrap_or_else(|| "-".to_string()),
+                ),
+            ))
+        })?
+        .collect::<rusqlite::Result<Vec<_>>>()?;
+
+    let stdin = std::io::stdin();
+    let mut answers = stdin.lock().lines();
+    let mut assigned = 0;
+    for (game_id, description) in games {
+        if interactive {
+            print!("{} - assign to {}? [y/n/q] ", description, profile);
+            std::io::stdout().flush()?;
+            match answers.next().transpose()?.as_deref().map(str::trim) {
+                Some("y") | Some("Y") => {}
+                Some("q") | Some("Q") | None => break,
+                _ => continue,
+            }
+        }
+        con.execute(
+            "INSERT OR REPLACE INTO MergeProfiles (GameId, Profile) VALUES (?, ?)",
+            params![game_id, profile],
+        )?;
+        info!("Assigned game {} to {}", &game_id, &profile);
+        assigned += 1;
+    }
+    Ok(assigned)
+}
+
+/// Number of games per profile, unassigned games as `None`.
+pub fn list(con: &Connection) -> rusqlite::Result<Vec<(Option<String>, i64)>> {
+    ensure_table(con)?;
+    let mut stmt = con.prepare(&format!(
+        "SELECT mp.Profile, COUNT(*) FROM Games g {} GROUP BY mp.Profile ORDER BY mp.Profile",
+        PROFILE_JOIN
+    ))?;
+    let rows = stmt.query_map(NO_PARAMS, |row| Ok((row.get(0)?, row.get(1)?)))?;
+    rows.collect()
+}
+
+#[cfg(feature = "charts")]
+pub fn table_exists(con: &Connection) -> rusqlite::Result<bool> {
+    let count: i64 = con.query_row(
+        "SELECT COUNT(*) FROM sqlite_master WHERE type = 'table' AND name = 'MergeProfiles'",
+        NO_PARAMS,
+        |row| row.get(0),
+    )?;
+    Ok(count > 0)
+}
@@ -0,0 +1,161 @@
+//! `stats --ratings`: Elo ratings of the leaders, evolving game by game in
+//! the order they were played. Every pair of major players in a game is a
+//! match: The victor's team beats everyone else, otherwise the higher score
+//! wins. The AI plays stronger the higher the difficulty, so against the
+//! local player it counts 100 points more per level above Prince - beating a
+//! Deity AI is worth more than beating a Prince one.
+//!
+//! Only uses `Games` & `GamePlayers`, so it works without graph data.
+
+use std::collections::{BTreeMap, HashMap};
+
+use rusqlite::{Connection, NO_PARAMS};
+
+use crate::names::Names;
+use crate::table::Table;
+use crate::{content, stats, GameId};
+
+/// Rating of a leader not rated yet.
+const INITIAL_RATING: f64 = 1500.0;
+
+/// Rating points at stake per game, shared among its matches.
+const K_FACTOR: f64 = 32.0;
+
+/// A leader as the local player plays it or as the AI does - separated, as the AI's Gandhi says
+/// nothing about the player's.
+type Rated = (String, bool);
+
+#[derive(Default)]
+struct Rating {
+    /// As stored in the games
+    leader_name: Option<String>,
+    rating: f64,
+    games: i64,
+    wins: i64,
+}
+
+/// A major player in a game.
+struct Player {
+    leader: String,
+    leader_name: Option<String>,
+    local: bool,
+    won: bool,
+    score: i64,
+}
+
+/// Points of `a` in a match against `b`: 1 for a win, 0.5 for a draw.
+fn outcome(a: &Player, b: &Player) -> f64 {
+    match (a.won, b.won) {
+        (true, false) => 1.0,
+        (false, true) => 0.0,
+        _ => match a.score.cmp(&b.score) {
+            std::cmp::Ordering::Greater => 1.0,
+            std::cmp::Ordering::Less => 0.0,
+            std::cmp::Ordering::Equal => 0.5,
+        },
+    }
+}
+
+/// Rating points the AI has on top of its rating against the local player at a difficulty.
+fn ai_bonus(difficulty: Option<&str>) -> f64 {
+    400.0 * (stats::difficulty_weight(difficulty) - 1.0)
+}
+
+/// The major players of each game in the order the games were played, with the local player's difficulty.
+fn games(con: &Connection, exclude_modded: bool) -> rusqlite::Result<Vec<(Option<String>, Vec<Player>)>> {
+    let modded = if exclude_modded {
+        content::modded_games(con)?
+    } else {
+        BTreeMap::new()
+    };
+    let mut stmt = con.prepare(
+        "SELECT go.GameId, gp.LeaderType, gp.IsLocal AND NOT gp.IsAI, COALESCE(gp.TeamId = g.VictorTeamId, 0), \
+         COALESCE(gp.Score, 0), gp.DifficultyType, gp.LeaderName FROM GamePlayers gp \
+         JOIN GameObjects go ON go.ObjectId = gp.PlayerObjectId JOIN Games g ON g.GameId = go.GameId \
+         WHERE gp.IsMajor AND gp.LeaderType IS NOT NULL ORDER BY g.LastPlayed, g.GameId",
+    )?;
+    let mut games: Vec<(GameId, Option<String>, Vec<Player>)> = Vec::new();
+    let mut rows = stmt.query(NO_PARAMS)?;
+    while let Some(row) = rows.next()? {
+        let game_id: GameId = row.get(0)?;
+        if modded.contains_key(&game_id) {
+            continue;
+        }
+        if games.last().map(|(last, _, _)| *last) != Some(game_id) {
+            games.push((game_id, None, Vec::new()));
+        }
+        let (_, difficulty, players) = games.last_mut().expect("pushed above");
+        let player = Player {
+            leader: row.get(1)?,
+            leader_name: row.get(6)?,
+            local: row.get(2)?,
+            won: row.get(3)?,
+            score: row.get(4)?,
+        };
+        if player.local {
+            *difficulty = row.get(5)?;
+        }
+        players.push(player);
+    }
+    Ok(games.into_iter().map(|(_, difficulty, players)| (difficulty, players)).collect())
+}
+
+/// The leaders' ratings after all games, highest first, without modded games if `exclude_modded`.
+pub fn ratings(con: &Connection, names: &Names, exclude_modded: bool) -> rusqlite::Result<Table> {
+    let mut ratings: HashMap<Rated, Rating> = HashMap::new();
+    for (difficulty, players) in games(con, exclude_modded)? {
+        if players.len() < 2 {
+            continue;
+        }
+        let bonus = ai_bonus(difficulty.as_deref());
+        // Each player's strength in this game, before any of its matches is rated
+        let strengths: Vec<f64> = players
+            .iter()
+            .map(|player| {
+                ratings
+                    .get(&(player.leader.clone(), player.local))
+                    .map_or(INITIAL_RATING, |rated| rated.rating)
+            })
+            .collect();
+        let k = K_FACTOR / (players.len() - 1) as f64;
+        for (i, player) in players.iter().enumerate() {
+            let mut change = 0.0;
+            for (j, opponent) in players.iter().enumerate() {
+                if i == j {
+                    continue;
+                }
+                let mut difference = strengths[j] - strengths[i];
+                if player.local && !opponent.local {
+                    difference += bonus;
+                } else if !player.local && opponent.local {
+                    difference -= bonus;
+                }
+                let expected = 1.0 / (1.0 + 10f64.powf(difference / 400.0));
+                change += k * (outcome(player, opponent) - expected);
+            }
+            let rated = ratings.entry((player.leader.clone(), player.local)).or_insert(Rating {
+                rating: INITIAL_RATING,
+                ..Rating::default()
+            });
+            rated.leader_name = player.leader_name.clone().or_else(|| rated.leader_name.take());
+            rated.rating = strengths[i] + change;
+            rated.games += 1;
+            rated.wins += player.won as i64;
+        }
+    }
+
+    let mut ratings: Vec<(Rated, Rating)> = ratings.into_iter().collect();
+    ratings.sort_by(|a, b| b.1.rating.total_cmp(&a.1.rating).then_with(|| a.0.cmp(&b.0)));
+    let mut table = Table::new(&["#", "Leader", "Played by", "Rating", "Games", "Wins"]);
+    for (rank, ((leader, local), rated)) in ratings.into_iter().enumerate() {
+        table.push(vec![
+            (rank + 1).to_string(),
+            names.leader(&leader, rated.leader_name.as_deref()),
+            if local { "you" } else { "AI" }.to_string(),
+            format!("{:.0}", rated.rating),
+            rated.games.to_string(),
+            rated.wins.to_string(),
+        ]);
+    }
+    Ok(table)
+}
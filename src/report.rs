@@ -0,0 +1,395 @@
+use std::collections::BTreeMap;
+use std::fmt::Write as _;
+
+use log::info;
+use rusqlite::{params, Connection, NO_PARAMS};
+
+use crate::dates;
+use crate::leaderboard;
+use crate::names::Names;
+use crate::output;
+use crate::profiles::{self, PROFILE_JOIN};
+use crate::stats;
+use crate::LOCAL_PLAYER_JOIN;
+
+/// Colors cycled through by all charts.
+pub const PALETTE: [&str; 8] = [
+    "#4e79a7", "#f28e2b", "#e15759", "#76b7b2", "#59a14f", "#edc948", "#b07aa1", "#ff9da7",
+];
+
+struct GameRow {
+    game_id: i64,
+    last_played: i64,
+    leader: Option<String>,
+    map: String,
+    speed: String,
+    turns: i32,
+    victory: Option<String>,
+    profile: Option<String>,
+    difficulty: Option<String>,
+    won: bool,
+}
+
+struct Series {
+    label: String,
+    points: Vec<(f64, f64)>,
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// Highest scores listed in the report's leaderboard.
+const TOP_SCORES: i64 = 10;
+
+/// Writes a self-contained HTML page with a summary, the leaderboard, a table
+/// of all games & interactive charts (victory breakdown, win rate per leader,
+/// score over turns).
+pub fn write_report(con: &Connection, out: &std::path::Path, names: &Names) -> Result<(), Box<dyn std::error::Error>> {
+    let with_profiles = profiles::table_exists(con)?;
+    let games = load_games(con, with_profiles, names)?;
+
+    let mut victories: BTreeMap<String, f64> = BTreeMap::new();
+    for game in &games {
+        let victory = game.victory.clone().unwrap_or_else(|| "None".to_string());
+        *victories.entry(victory).or_default() += 1.0;
+    }
+
+    let mut html = String::new();
+    html.push_str("<!DOCTYPE html>\n<html><head><meta charset=\"utf-8\"><title>Hall of Fame</title>\n");
+    html.push_str(STYLE);
+    html.push_str("</head><body>\n<h1>Hall of Fame</h1>\n");
+
+    let wins = games.iter().filter(|game| game.won).count();
+    html.push_str("<h2>Summary</h2>\n<ul>\n");
+    let _ = writeln!(html, "<li>{} game(s), {} won</li>", games.len(), wins);
+    if !games.is_empty() {
+        let _ = writeln!(html, "<li>Win rate {:.0}%</li>", 100.0 * wins as f64 / games.len() as f64);
+    }
+    if let (Some(first), Some(last)) = (
+        games.iter().map(|game| game.last_played).min(),
+        games.iter().map(|game| game.last_played).max(),
+    ) {
+        let _ = writeln!(
+            html,
+            "<li>Played from {} to {}</li>",
+            dates::format_date(first),
+            dates::format_date(last)
+        );
+    }
+    let mut leaders: Vec<&str> = games.iter().filter_map(|game| game.leader.as_deref()).collect();
+    leaders.sort_unstable();
+    leaders.dedup();
+    let _ = writeln!(html, "<li>{} leader(s) played</li>", leaders.len());
+    html.push_str("</ul>\n");
+
+    for (title, table) in leaderboard::sections(con, names, TOP_SCORES)? {
+        let _ = write!(html, "<h2>{}</h2>\n{}", title, table.render_html());
+    }
+
+    html.push_str("<h2>Victories</h2>\n");
+    html.push_str(&pie_chart(&victories.into_iter().collect::<Vec<_>>()));
+
+    html.push_str("<h2>Win rate by leader</h2>\n");
+    html.push_str(&bar_chart(&leader_win_rates(con, names)?));
+
+    if with_profiles {
+        html.push_str("<h2>Win rate by profile</h2>\n");
+        html.push_str(&bar_chart(&profile_win_rates(con)?));
+    }
+
+    let series = score_series(con)?;
+    if !series.is_empty() {
+        html.push_str("<h2>Score over turns</h2>\n<select onchange=\"showGame(this.value)\">\n");
+        for (game_id, _) in &series {
+            let _ = writeln!(html, "<option value=\"{0}\">Game {0}</option>", game_id);
+        }
+        html.push_str("</select>\n");
+        for (index, (game_id, lines)) in series.iter().enumerate() {
+            let _ = writeln!(
+                html,
+                "<div class=\"game-chart\" id=\"game-{}\"{}>{}</div>",
+                game_id,
+                if index == 0 { "" } else { " hidden" },
+                line_chart(lines)
+            );
+        }
+    }
+
+    html.push_str("<h2>Games</h2>\n<table class=\"sortable\"><thead><tr><th>GameId</th><th>Last played</th><th>Leader</th>\
+        <th>Map</th><th>Speed</th><th>Turns</th><th>Standard turns</th><th>Victory</th><th>Rating</th>");
+    if with_profiles {
+        html.push_str("<th>Profile</th>");
+    }
+    html.push_str("</tr></thead><tbody>\n");
+    for game in &games {
+        let profile = match (with_profiles, &game.profile) {
+            (true, profile) => format!("<td>{}</td>", escape(profile.as_deref().unwrap_or("-"))),
+            (false, _) => String::new(),
+        };
+        let rating = if game.won {
+            format!("{:.2}", stats::rating(&game.speed, game.turns as i64, game.difficulty.as_deref()))
+        } else {
+            "-".to_string()
+        };
+        let _ = writeln!(
+            html,
+            "<tr><td>{}</td><td data-sort=\"{}\">{}</td><td>{}</td><td>{}</td><td>{}</td><td>{}</td><td>{:.0}</td><td>{}</td><td>{}</td>{}</tr>",
+            game.game_id,
+            game.last_played,
+            dates::format_date(game.last_played),
+            escape(game.leader.as_deref().unwrap_or("-")),
+            escape(&game.map),
+            escape(names.display(&game.speed)),
+            game.turns,
+            stats::standard_turns(&game.speed, game.turns as i64),
+            escape(game.victory.as_deref().map_or("-", |victory| names.display(victory))),
+            rating,
+            profile
+        );
+    }
+    html.push_str("</tbody></table>\n");
+    html.push_str(SCRIPT);
+    html.push_str("</body></html>\n");
+
+    output::write(Some(out), &html)?;
+    info!("Wrote report of {} games to {:?}", games.len(), &out);
+    Ok(())
+}
+
+fn load_games(con: &Connection, with_profiles: bool, names: &Names) -> rusqlite::Result<Vec<GameRow>> {
+    let mut stmt = con.prepare(&format!(
+        "SELECT g.GameId, g.LastPlayed, lp.LeaderType, g.Map, g.GameSpeedType, g.TurnCount, g.VictoryType, {}, lp.LeaderName, \
+         lp.DifficultyType, COALESCE(g.VictorTeamId = lp.TeamId, 0) FROM Games g {} {} ORDER BY g.LastPlayed",
+        if with_profiles { "mp.Profile" } else { "NULL" },
+        LOCAL_PLAYER_JOIN,
+        if with_profiles { PROFILE_JOIN } else { "" }
+    ))?;
+    let rows = stmt.query_map(NO_PARAMS, |row| {
+        Ok(GameRow {
+            game_id: row.get(0)?,
+            last_played: row.get(1)?,
+            leader: match (row.get::<_, Option<String>>(2)?, row.get::<_, Option<String>>(8)?) {
+                (Some(leader_type), leader_name) => Some(names.leader(&leader_type, leader_name.as_deref())),
+                (None, _) => None,
+            },
+            map: row.get(3)?,
+            speed: row.get(4)?,
+            turns: row.get(5)?,
+            victory: row.get(6)?,
+            profile: row.get(7)?,
+            difficulty: row.get(9)?,
+            won: row.get(10)?,
+        })
+    })?;
+    rows.collect()
+}
+
+/// Percentage of won games per local leader.
+fn leader_win_rates(con: &Connection, names: &Names) -> rusqlite::Result<Vec<(String, f64)>> {
+    let mut stmt = con.prepare(&format!(
+        "SELECT lp.LeaderType, MAX(lp.LeaderName), COUNT(*), SUM(g.VictorTeamId = lp.TeamId) \
+         FROM Games g {} WHERE lp.LeaderType IS NOT NULL GROUP BY 1 ORDER BY 1",
+        LOCAL_PLAYER_JOIN
+    ))?;
+    let rows = stmt.query_map(NO_PARAMS, |row| {
+        let leader_type: String = row.get(0)?;
+        let leader_name: Option<String> = row.get(1)?;
+        let games: f64 = row.get(2)?;
+        let wins: Option<f64> = row.get(3)?;
+        Ok((
+            names.leader(&leader_type, leader_name.as_deref()),
+            100.0 * wins.unwrap_or(0.0) / games,
+        ))
+    })?;
+    rows.collect()
+}
+
+/// Percentage of won games per profile.
+fn profile_win_rates(con: &Connection) -> rusqlite::Result<Vec<(String, f64)>> {
+    let mut stmt = con.prepare(&format!(
+        "SELECT COALESCE(mp.Profile, '(unassigned)'), COUNT(*), SUM(g.VictorTeamId = lp.TeamId) \
+         FROM Games g {} {} GROUP BY 1 ORDER BY 1",
+        LOCAL_PLAYER_JOIN, PROFILE_JOIN
+    ))?;
+    let rows = stmt.query_map(NO_PARAMS, |row| {
+        let games: f64 = row.get(1)?;
+        let wins: Option<f64> = row.get(2)?;
+        Ok((row.get(0)?, 100.0 * wins.unwrap_or(0.0) / games))
+    })?;
+    rows.collect()
+}
+
+/// Per-turn score of every player, for each game that has score data sets.
+fn score_series(con: &Connection) -> rusqlite::Result<Vec<(i64, Vec<Series>)>> {
+    let mut sets = con.prepare(
+        "SELECT ds.GameId, ds.DataSetId, COALESCE(gp.LeaderName, go.Name, ds.DataSet) FROM DataSets ds \
+         LEFT JOIN GamePlayers gp ON gp.PlayerObjectId = ds.ObjectId \
+         LEFT JOIN GameObjects go ON go.ObjectId = ds.ObjectId \
+         WHERE ds.DataSet LIKE '%SCORE%' ORDER BY ds.GameId, ds.DataSetId",
+    )?;
+    let mut values = con.prepare("SELECT X, Y FROM DataSetValues WHERE DataSetId = ? ORDER BY X")?;
+
+    let mut games: Vec<(i64, Vec<Series>)> = Vec::new();
+    let rows = sets.query_map(NO_PARAMS, |row| Ok((row.get::<_, i64>(0)?, row.get::<_, i64>(1)?, row.get::<_, String>(2)?)))?;
+    for row in rows {
+        let (game_id, data_set_id, label) = row?;
+        let points = values
+            .query_map(params![data_set_id], |row| Ok((row.get(0)?, row.get(1)?)))?
+            .collect::<rusqlite::Result<Vec<(f64, f64)>>>()?;
+        let series = Series { label, points };
+        match games.last_mut() {
+            Some((id, lines)) if *id == game_id => lines.push(series),
+            _ => games.push((game_id, vec![series])),
+        }
+    }
+    Ok(games)
+}
+
+fn pie_chart(slices: &[(String, f64)]) -> String {
+    let total: f64 = slices.iter().map(|(_, value)| value).sum();
+    let mut svg = String::from("<svg class=\"chart\" viewBox=\"-110 -110 420 220\" width=\"420\" height=\"220\">\n");
+    let mut angle = -std::f64::consts::FRAC_PI_2;
+    for (index, (label, value)) in slices.iter().enumerate() {
+        let color = PALETTE[index % PALETTE.len()];
+        let title = format!("{}: {} ({:.0}%)", escape(label), value, 100.0 * value / total);
+        if (value - total).abs() < f64::EPSILON {
+            let _ = writeln!(svg, "<circle r=\"100\" fill=\"{}\"><title>{}</title></circle>", color, title);
+        } else {
+            let sweep = 2.0 * std::f64::consts::PI * value / total;
+            let (x1, y1) = (100.0 * angle.cos(), 100.0 * angle.sin());
+            angle += sweep;
+            let (x2, y2) = (100.0 * angle.cos(), 100.0 * angle.sin());
+            let _ = writeln!(
+                svg,
+                "<path d=\"M0,0 L{:.2},{:.2} A100,100 0 {} 1 {:.2},{:.2} Z\" fill=\"{}\"><title>{}</title></path>",
+                x1,
+                y1,
+                if sweep > std::f64::consts::PI { 1 } else { 0 },
+                x2,
+                y2,
+                color,
+                title
+            );
+        }
+        let _ = writeln!(
+            svg,
+            "<rect x=\"120\" y=\"{}\" width=\"12\" height=\"12\" fill=\"{}\"/><text x=\"138\" y=\"{}\">{}</text>",
+            -100 + 20 * index as i32,
+            color,
+            -89 + 20 * index as i32,
+            title
+        );
+    }
+    svg.push_str("</svg>\n");
+    svg
+}
+
+fn bar_chart(bars: &[(String, f64)]) -> String {
+    let mut svg = format!(
+        "<svg class=\"chart\" viewBox=\"0 0 600 {0}\" width=\"600\" height=\"{0}\">\n",
+        22 * bars.len() + 4
+    );
+    for (index, (label, percent)) in bars.iter().enumerate() {
+        let y = 22 * index + 2;
+        let _ = writeln!(
+            svg,
+            "<text x=\"195\" y=\"{}\" text-anchor=\"end\">{}</text><rect x=\"200\" y=\"{}\" width=\"{:.1}\" height=\"18\" fill=\"{}\">\
+             <title>{}: {:.0}%</title></rect><text x=\"{:.1}\" y=\"{}\">{:.0}%</text>",
+            y + 14,
+            escape(label),
+            y,
+            3.5 * percent,
+            PALETTE[index % PALETTE.len()],
+            escape(label),
+            percent,
+            205.0 + 3.5 * percent,
+            y + 14,
+            percent
+        );
+    }
+    svg.push_str("</svg>\n");
+    svg
+}
+
+fn line_chart(lines: &[Series]) -> String {
+    let points = lines.iter().flat_map(|series| series.points.iter());
+    let (max_x, max_y) = points.fold((1.0f64, 1.0f64), |(x, y), point| (x.max(point.0), y.max(point.1)));
+    let scale = |(x, y): (f64, f64)| (40.0 + 540.0 * x / max_x, 270.0 - 250.0 * y / max_y);
+
+    let mut svg = String::from("<svg class=\"chart\" viewBox=\"0 0 600 300\" width=\"600\" height=\"300\">\n");
+    let _ = writeln!(
+        svg,
+        "<line x1=\"40\" y1=\"270\" x2=\"580\" y2=\"270\" stroke=\"#888\"/><line x1=\"40\" y1=\"20\" x2=\"40\" y2=\"270\" stroke=\"#888\"/>\
+         <text x=\"40\" y=\"290\">0</text><text x=\"560\" y=\"290\">{}</text><text x=\"0\" y=\"25\">{}</text>",
+        max_x, max_y
+    );
+    for (index, series) in lines.iter().enumerate() {
+        let color = PALETTE[index % PALETTE.len()];
+        let path: Vec<String> = series
+            .points
+            .iter()
+            .map(|&point| {
+                let (x, y) = scale(point);
+                format!("{:.1},{:.1}", x, y)
+            })
+            .collect();
+        let _ = writeln!(
+            svg,
+            "<polyline fill=\"none\" stroke=\"{}\" stroke-width=\"2\" points=\"{}\"><title>{}</title></polyline>",
+            color,
+            path.join(" "),
+            escape(&series.label)
+        );
+        for &(x, y) in &series.points {
+            let (px, py) = scale((x, y));
+            let _ = writeln!(
+                svg,
+                "<circle cx=\"{:.1}\" cy=\"{:.1}\" r=\"3\" fill=\"{}\"><title>{} - turn {}: {}</title></circle>",
+                px,
+                py,
+                color,
+                escape(&series.label),
+                x,
+                y
+            );
+        }
+    }
+    svg.push_str("</svg>\n");
+    svg
+}
+
+const STYLE: &str = "<style>
+body { font-family: sans-serif; margin: 2em; }
+table { border-collapse: collapse; margin-bottom: 1em; }
+th, td { border: 1px solid #ccc; padding: 0.2em 0.6em; }
+th { cursor: pointer; background: #eee; }
+.chart text { font-size: 11px; }
+.chart circle:hover, .chart path:hover, .chart rect:hover { opacity: 0.7; }
+</style>
+";
+
+const SCRIPT: &str = "<script>
+function showGame(id) {
+  document.querySelectorAll('.game-chart').forEach(function (chart) { chart.hidden = chart.id !== 'game-' + id; });
+}
+document.querySelectorAll('table.sortable th').forEach(function (th, column) {
+  th.addEventListener('click', function () {
+    var body = th.closest('table').tBodies[0];
+    var ascending = th.dataset.ascending !== 'true';
+    th.dataset.ascending = ascending;
+    var value = function (row) {
+      var cell = row.cells[column];
+      var text = cell.dataset.sort || cell.textContent;
+      return isNaN(text) ? text : Number(text);
+    };
+    Array.from(body.rows)
+      .sort(function (a, b) { var x = value(a), y = value(b); return (x < y ? -1 : x > y ? 1 : 0) * (ascending ? 1 : -1); })
+      .forEach(function (row) { body.appendChild(row); });
+  });
+});
+</script>
+";
@@ -0,0 +1,126 @@
+use std::path::{Path, PathBuf};
+
+use log::{debug, info};
+use rusqlite::{params, Connection, NO_PARAMS};
+
+use crate::matrix::short_name;
+use crate::{GameId, LOCAL_PLAYER_JOIN};
+
+/// A save file associated with a Hall-of-Fame game.
+pub struct SaveLink {
+    pub game_id: GameId,
+    pub path: PathBuf,
+    /// How well the save matches the game: Leader & turn in its name count 2 each, modified within an hour of LastPlayed 1
+    pub score: i32,
+}
+
+struct SaveFile {
+    path: PathBuf,
+    name: String,
+    modified: i64,
+    numbers: Vec<i32>,
+}
+
+fn find_saves(dir: &Path, saves: &mut Vec<SaveFile>) -> std::io::Result<()> {
+    for entry in std::fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.is_dir() {
+            find_saves(&path, saves)?;
+            continue;
+        }
+        let is_save = path
+            .extension()
+            .and_then(|extension| extension.to_str())
+            .is_some_and(|extension| extension.eq_ignore_ascii_case("Civ6Save"));
+        if !is_save {
+            continue;
+        }
+
+        let modified = std::fs::metadata(&path)?
+            .modified()?
+            .duration_since(std::time::UNIX_EPOCH)
+            .map_or(0, |duration| duration.as_secs() as i64);
+        let name = path
+            .file_stem()
+            .map(|stem| stem.to_string_lossy().to_uppercase())
+            .unwrap_or_default();
+        let numbers = name
+            .split(|c: char| !c.is_ascii_digit())
+            .filter_map(|number| number.parse().ok())
+            .collect();
+        saves.push(SaveFile {
+            path,
+            name,
+            modified,
+            numbers,
+        });
+    }
+    Ok(())
+}
+
+/// Matches the save files below `saves_dir` to the games of the database:
+/// A save must have been written within `window` seconds of the game's
+/// LastPlayed and is scored by leader name & turn number in its file name.
+/// The best match of each game is recorded in the `MergeSaveLinks` table.
+pub fn link_saves(con: &Connection, saves_dir: &Path, window: i64) -> Result<Vec<SaveLink>, Box<dyn std::error::Error>> {
+    let mut saves = Vec::new();
+    find_saves(saves_dir, &mut saves)?;
+    info!("Found {} save files below {:?}", saves.len(), &saves_dir);
+
+    con.execute(
+        "CREATE TABLE IF NOT EXISTS MergeSaveLinks (GameId INTEGER PRIMARY KEY NOT NULL, Path TEXT NOT NULL, Score INTEGER NOT NULL)",
+        NO_PARAMS,
+    )?;
+
+    let mut stmt = con.prepare(&format!(
+        "SELECT g.GameId, g.LastPlayed, g.TurnCount, lp.LeaderType FROM Games g {}",
+        LOCAL_PLAYER_JOIN
+    ))?;
+    let games = stmt
+        .query_map(NO_PARAMS, |row| {
+            Ok((
+                row.get::<_, GameId>(0)?,
+                row.get::<_, i64>(1)?,
+                row.get::<_, i32>(2)?,
+                row.get::<_, Option<String>>(3)?,
+            ))
+        })?
+        .collect::<rusqlite::Result<Vec<_>>>()?;
+
+    let mut links = Vec::new();
+    for (game_id, last_played, turn_count, leader_type) in games {
+        let leader = leader_type.as_deref().map(short_name);
+        let best = saves
+            .iter()
+            .filter(|save| (save.modified - last_played).abs() <= window)
+            .map(|save| {
+                let mut score = 0;
+                if leader.is_some_and(|leader| save.name.contains(leader)) {
+                    score += 2;
+                }
+                if save.numbers.contains(&turn_count) {
+                    score += 2;
+                }
+                if (save.modified - last_played).abs() <= 3600 {
+                    score += 1;
+                }
+                (score, -(save.modified - last_played).abs(), save)
+            })
+            .filter(|(score, _, _)| *score >= 2)
+            .max_by_key(|(score, closeness, _)| (*score, *closeness));
+
+        if let Some((score, _, save)) = best {
+            debug!("Game {} matches {:?} with score {}", &game_id, &save.path, &score);
+            con.execute(
+                "INSERT OR REPLACE INTO MergeSaveLinks (GameId, Path, Score) VALUES (?, ?, ?)",
+                params![game_id, save.path.to_string_lossy(), score],
+            )?;
+            links.push(SaveLink {
+                game_id,
+                path: save.path.clone(),
+                score,
+            });
+        }
+    }
+    Ok(links)
+}
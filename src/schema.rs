@@ -0,0 +1,248 @@
+use std::str::FromStr;
+use std::sync::OnceLock;
+
+use rusqlite::{Connection, NO_PARAMS};
+
+static CHECK: OnceLock<Check> = OnceLock::new();
+
+/// How databases lacking Hall-of-Fame tables are treated, set with `--schema-check`.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum Check {
+    /// They are refused
+    #[default]
+    Strict,
+    /// A warning, then they are used as if the missing tables were empty
+    Lenient,
+    /// The tables aren't checked - queries of missing ones fail
+    Off,
+}
+
+impl FromStr for Check {
+    type Err = String;
+
+    fn from_str(check: &str) -> Result<Self, Self::Err> {
+        match check {
+            "strict" => Ok(Check::Strict),
+            "lenient" => Ok(Check::Lenient),
+            "off" => Ok(Check::Off),
+            _ => Err(format!("Unknown schema check {:?}, expected strict, lenient or off", check)),
+        }
+    }
+}
+
+/// Sets how databases are checked for the rest of the run.
+pub fn set_check(check: Check) {
+    let _ = CHECK.set(check);
+}
+
+pub fn check() -> Check {
+    CHECK.get().copied().unwrap_or_default()
+}
+
+/// A table with its columns' names & SQL declarations.
+pub struct Table {
+    pub name: &'static str,
+    pub columns: &'static [(&'static str, &'static str)],
+    /// Table constraints following the columns, e.g. a compound primary key
+    pub constraints: &'static [&'static str],
+}
+
+/// The Hall-of-Fame schema the merge knows.
+pub struct Schema {
+    pub name: &'static str,
+    pub tables: &'static [Table],
+}
+
+const TABLES: &[Table] = &[
+    Table {
+        name: "Migrations",
+        columns: &[("Version", "INTEGER NOT NULL"), ("Name", "TEXT")],
+        constraints: &[],
+    },
+    Table {
+        name: "Rulesets",
+        columns: &[("Ruleset", "TEXT PRIMARY KEY NOT NULL"), ("Name", "TEXT"), ("Description", "TEXT")],
+        constraints: &[],
+    },
+    Table {
+        name: "RulesetTypes",
+        columns: &[
+            ("Ruleset", "TEXT NOT NULL"),
+            ("Type", "TEXT NOT NULL"),
+            ("Kind", "TEXT"),
+            ("Name", "TEXT"),
+            ("Icon", "TEXT"),
+        ],
+        constraints: &["PRIMARY KEY (Ruleset, Type)"],
+    },
+    Table {
+        name: "RulesetDataPointValues",
+        columns: &[
+            ("Ruleset", "TEXT NOT NULL"),
+            ("DataPoint", "TEXT NOT NULL"),
+            ("ValueType", "TEXT"),
+            ("ValueString", "TEXT"),
+            ("ValueNumeric", "INTEGER"),
+        ],
+        constraints: &[],
+    },
+    Table {
+        name: "Games",
+        columns: &[
+            ("GameId", "INTEGER PRIMARY KEY AUTOINCREMENT"),
+            ("Ruleset", "TEXT NOT NULL"),
+            ("GameMode", "INTEGER NOT NULL"),
+            ("TurnCount", "INTEGER NOT NULL"),
+            ("GameSpeedType", "TEXT NOT NULL"),
+            ("MapSizeType", "TEXT NOT NULL"),
+            ("Map", "TEXT NOT NULL"),
+            ("StartEraType", "TEXT NOT NULL"),
+            ("StartTurn", "INTEGER NOT NULL"),
+            ("VictorTeamId", "INTEGER"),
+            ("VictoryType", "TEXT"),
+            ("LastPlayed", "INTEGER NOT NULL"),
+        ],
+        constraints: &[],
+    },
+    Table {
+        name: "GameObjects",
+        columns: &[
+            ("ObjectId", "INTEGER PRIMARY KEY AUTOINCREMENT"),
+            ("GameId", "INTEGER NOT NULL"),
+            ("PlayerObjectId", "INTEGER"),
+            ("Type", "TEXT NOT NULL"),
+            ("Name", "TEXT"),
+            ("PlotIndex", "INTEGER"),
+            ("ExtraData", "TEXT"),
+            ("Icon", "TEXT"),
+        ],
+        constraints: &[],
+    },
+    Table {
+        name: "GamePlayers",
+        columns: &[
+            ("PlayerObjectId", "INTEGER PRIMARY KEY"),
+            ("IsLocal", "BOOLEAN NOT NULL"),
+            ("IsAI", "BOOLEAN NOT NULL"),
+            ("IsMajor", "BOOLEAN NOT NULL"),
+            ("LeaderType", "TEXT NOT NULL"),
+            ("LeaderName", "TEXT"),
+            ("CivilizationType", "TEXT"),
+            ("CivilizationName", "TEXT"),
+            ("DifficultyType", "TEXT"),
+            ("Score", "INTEGER NOT NULL"),
+            ("PlayerId", "INTEGER NOT NULL"),
+            ("TeamId", "INTEGER NOT NULL"),
+        ],
+        constraints: &[],
+    },
+    Table {
+        name: "GameDataPointValues",
+        columns: &[
+            ("DataPoint", "TEXT NOT NULL"),
+            ("GameId", "INTEGER NOT NULL"),
+            ("ValueObjectId", "INTEGER"),
+            ("ValueType", "TEXT"),
+            ("ValueString", "TEXT"),
+            ("ValueNumeric", "INTEGER"),
+        ],
+        constraints: &[],
+    },
+    Table {
+        name: "ObjectDataPointValues",
+        columns: &[
+            ("ObjectId", "INTEGER NOT NULL"),
+            ("DataPoint", "TEXT NOT NULL"),
+            ("ValueObjectId", "INTEGER"),
+            ("ValueType", "TEXT"),
+            ("ValueString", "TEXT"),
+            ("ValueNumeric", "INTEGER"),
+        ],
+        constraints: &[],
+    },
+    Table {
+        name: "DataSets",
+        columns: &[
+            ("DataSetId", "INTEGER PRIMARY KEY AUTOINCREMENT"),
+            ("GameId", "INTEGER NOT NULL"),
+            ("ObjectId", "INTEGER"),
+            ("DataSet", "TEXT NOT NULL"),
+            ("Type", "TEXT"),
+        ],
+        constraints: &[],
+    },
+    Table {
+        name: "DataSetValues",
+        columns: &[("DataSetId", "INTEGER NOT NULL"), ("X", "INTEGER NOT NULL"), ("Y", "REAL NOT NULL")],
+        constraints: &[],
+    },
+];
+
+pub const SCHEMA: Schema = Schema {
+    name: "Hall of Fame (Gathering Storm)",
+    tables: TABLES,
+};
+
+fn definitions(table: &Table) -> String {
+    let mut definitions: Vec<String> = table
+        .columns
+        .iter()
+        .map(|(column, declaration)| format!("{} {}", column, declaration))
+        .collect();
+    definitions.extend(table.constraints.iter().map(|constraint| constraint.to_string()));
+    definitions.join(", ")
+}
+
+/// Creates the tables of the schema in an empty database.
+pub fn create(con: &Connection) -> rusqlite::Result<()> {
+    for table in SCHEMA.tables {
+        con.execute(&format!("CREATE TABLE {} ({})", table.name, definitions(table)), NO_PARAMS)?;
+    }
+    Ok(())
+}
+
+/// Creates the tables of the schema the database lacks, returning their
+/// names. With `temporary` they are TEMP tables standing in for the missing
+/// ones on this connection only, leaving the file untouched.
+pub fn create_missing(con: &Connection, temporary: bool) -> rusqlite::Result<Vec<&'static str>> {
+    let mut created = Vec::new();
+    for table in SCHEMA.tables {
+        if columns(con, table.name)?.is_empty() {
+            let kind = if temporary { "TEMP TABLE" } else { "TABLE" };
+            con.execute(&format!("CREATE {} {} ({})", kind, table.name, definitions(table)), NO_PARAMS)?;
+            created.push(table.name);
+        }
+    }
+    Ok(created)
+}
+
+/// Columns a table has in the database.
+pub fn columns(con: &Connection, table: &str) -> rusqlite::Result<Vec<String>> {
+    let mut stmt = con.prepare(&format!("PRAGMA table_info({})", table))?;
+    let columns = stmt.query_map(NO_PARAMS, |row| row.get(1))?;
+    columns.collect()
+}
+
+/// Describes where the database deviates from the schema: Missing tables &
+/// columns as well as columns the schema doesn't know.
+pub fn differences(con: &Connection) -> rusqlite::Result<Vec<String>> {
+    let mut differences = Vec::new();
+    for table in SCHEMA.tables {
+        let actual = columns(con, table.name)?;
+        if actual.is_empty() {
+            differences.push(format!("Table {} is missing", table.name));
+            continue;
+        }
+        for (column, _) in table.columns {
+            if !actual.iter().any(|a| a == column) {
+                differences.push(format!("Column {}.{} is missing", table.name, column));
+            }
+        }
+        for column in &actual {
+            if !table.columns.iter().any(|(c, _)| c == column) {
+                differences.push(format!("Column {}.{} is unknown", table.name, column));
+            }
+        }
+    }
+    Ok(differences)
+}
@@ -0,0 +1,106 @@
+//! `search`: Finds games by the local player's leader & civilization, the map
+//! script or the victory type. Terms match the game's keys - `LEADER_TRAJAN` -
+//! as well as display names - `trajan`, `Teddy Roosevelt` - ignoring case &
+//! underscores, also as part of them.
+
+use rusqlite::{Connection, NO_PARAMS};
+
+use crate::names::Names;
+use crate::{load_game, maps, show, GameId, LOCAL_PLAYER_JOIN};
+
+/// The terms of a search; a game has to match all of them.
+#[derive(Debug, Default)]
+pub struct Query {
+    /// Each matching the leader, civilization, map or victory
+    pub terms: Vec<String>,
+    pub leaders: Vec<String>,
+    pub civilizations: Vec<String>,
+    pub maps: Vec<String>,
+    pub victories: Vec<String>,
+}
+
+/// A game found, with the normalized texts searched.
+pub struct Found {
+    pub game_id: GameId,
+    leader: Vec<String>,
+    civilization: Vec<String>,
+    map: Vec<String>,
+    victory: Vec<String>,
+}
+
+/// Lower case with underscores as spaces, so `LEADER_T_ROOSEVELT` contains `t roosevelt`.
+fn normalize(text: &str) -> String {
+    text.trim().to_lowercase().replace('_', " ")
+}
+
+/// The normalized forms of a type key: The key itself & its display name.
+fn forms(names: &Names, key: Option<&str>) -> Vec<String> {
+    match key {
+        Some(key) => vec![normalize(key), normalize(names.display(key))],
+        None => Vec::new(),
+    }
+}
+
+fn matches(forms: &[String], term: &str) -> bool {
+    let term = normalize(term);
+    forms.iter().any(|form| form.contains(&term))
+}
+
+impl Found {
+    fn matches(&self, query: &Query) -> bool {
+        let fields = [&self.leader, &self.civilization, &self.map, &self.victory];
+        query.terms.iter().all(|term| fields.iter().any(|field| matches(field, term)))
+            && query.leaders.iter().all(|term| matches(&self.leader, term))
+            && query.civilizations.iter().all(|term| matches(&self.civilization, term))
+            && query.maps.iter().all(|term| matches(&self.map, term))
+            && query.victories.iter().all(|term| matches(&self.victory, term))
+    }
+}
+
+/// The games matching the query, most recently played first.
+pub fn search(con: &Connection, names: &Names, query: &Query) -> rusqlite::Result<Vec<Found>> {
+    let mut stmt = con.prepare(&format!(
+        "SELECT g.GameId, lp.LeaderType, lp.LeaderName, lp.CivilizationType, g.Map, g.VictoryType \
+         FROM Games g {} ORDER BY g.LastPlayed DESC",
+        LOCAL_PLAYER_JOIN
+    ))?;
+    let mut found = Vec::new();
+    let mut rows = stmt.query(NO_PARAMS)?;
+    while let Some(row) = rows.next()? {
+        let leader_type: Option<String> = row.get(1)?;
+        let mut leader = forms(names, leader_type.as_deref());
+        if let (Some(leader_type), Some(leader_name)) = (&leader_type, row.get::<_, Option<String>>(2)?) {
+            leader.push(normalize(&names.leader(leader_type, Some(&leader_name))));
+        }
+        let map: String = row.get(4)?;
+        let game = Found {
+            game_id: row.get(0)?,
+            leader,
+            civilization: forms(names, row.get::<_, Option<String>>(3)?.as_deref()),
+            map: vec![normalize(&map), normalize(&maps::canonical(&map))],
+            victory: forms(names, row.get::<_, Option<String>>(5)?.as_deref()),
+        };
+        if game.matches(query) {
+            found.push(game);
+        }
+    }
+    Ok(found)
+}
+
+/// Prints the games found, one line each, & the `--only-games` picking them.
+pub fn print(con: &Connection, names: &Names, found: &[Found]) -> Result<(), Box<dyn std::error::Error>> {
+    let mut lines = Vec::new();
+    for game in found {
+        lines.push(show::describe(con, names, &load_game(con, game.game_id)?)?);
+    }
+    lines.push(format!("Found {} game(s)", found.len()));
+    if !found.is_empty() {
+        lines.push(format!("--only-games {}", ids(found)));
+    }
+    Ok(crate::output::print(&format!("{}\n", lines.join("\n")))?)
+}
+
+/// The GameIds found, comma separated.
+pub fn ids(found: &[Found]) -> String {
+    found.iter().map(|game| game.game_id.to_string()).collect::<Vec<_>>().join(",")
+}
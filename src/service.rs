@@ -0,0 +1,130 @@
+//! Registers a periodic merge with the platform's scheduler: A systemd user
+//! timer on Linux, a launchd agent on macOS & a scheduled task on Windows.
+
+use std::path::PathBuf;
+use std::process::Command;
+
+use log::info;
+
+const NAME: &str = "civ6-hof-merge";
+const LAUNCHD_LABEL: &str = "io.github.derjust.civ6-hof-merge";
+
+fn home() -> std::io::Result<PathBuf> {
+    std::env::var_os("HOME")
+        .or_else(|| std::env::var_os("USERPROFILE"))
+        .map(PathBuf::from)
+        .ok_or_else(|| std::io::Error::other("Neither HOME nor USERPROFILE is set"))
+}
+
+fn run(program: &str, args: &[&str]) -> std::io::Result<()> {
+    info!("Running {} {}", program, args.join(" "));
+    let status = Command::new(program).args(args).status()?;
+    if !status.success() {
+        return Err(std::io::Error::other(format!("{} {} failed: {}", program, args.join(" "), status)));
+    }
+    Ok(())
+}
+
+/// The merge's arguments with existing files & directories made absolute -
+/// the scheduler doesn't run it from the current directory.
+fn absolute(args: &[String]) -> Vec<String> {
+    args.iter()
+        .map(|arg| match std::fs::canonicalize(arg) {
+            Ok(path) if !arg.starts_with('-') => path.to_string_lossy().into_owned(),
+            _ => arg.clone(),
+        })
+        .collect()
+}
+
+fn quote(arg: &str) -> String {
+    if arg.contains(|c: char| c.is_whitespace() || c == '"') {
+        format!("\"{}\"", arg.replace('"', "\\\""))
+    } else {
+        arg.to_owned()
+    }
+}
+
+fn escape_xml(text: &str) -> String {
+    text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;")
+}
+
+/// Schedules `civ6-hof-merge <args>` to run every `minutes`, replacing an earlier registration.
+pub fn install(minutes: u64, args: &[String]) -> Result<String, Box<dyn std::error::Error>> {
+    let executable = std::env::current_exe()?.to_string_lossy().into_owned();
+    let mut command = vec![executable];
+    command.extend(absolute(args));
+
+    if cfg!(windows) {
+        let task = command.iter().map(|arg| quote(arg)).collect::<Vec<_>>().join(" ");
+        run(
+            "schtasks",
+            &["/Create", "/F", "/SC", "MINUTE", "/MO", &minutes.to_string(), "/TN", NAME, "/TR", &task],
+        )?;
+        Ok(format!("Scheduled task {}", NAME))
+    } else if cfg!(target_os = "macos") {
+        let path = home()?.join("Library/LaunchAgents").join(format!("{}.plist", LAUNCHD_LABEL));
+        let arguments: String = command
+            .iter()
+            .map(|arg| format!("    <string>{}</string>\n", escape_xml(arg)))
+            .collect();
+        let plist = format!(
+            "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n\
+             <!DOCTYPE plist PUBLIC \"-//Apple//DTD PLIST 1.0//EN\" \"http://www.apple.com/DTDs/PropertyList-1.0.dtd\">\n\
+             <plist version=\"1.0\">\n<dict>\n  <key>Label</key>\n  <string>{}</string>\n  \
+             <key>ProgramArguments</key>\n  <array>\n{}  </array>\n  \
+             <key>StartInterval</key>\n  <integer>{}</integer>\n</dict>\n</plist>\n",
+            LAUNCHD_LABEL,
+            arguments,
+            minutes * 60
+        );
+        std::fs::create_dir_all(path.parent().unwrap_or(&path))?;
+        let _ = run("launchctl", &["unload", &path.to_string_lossy()]);
+        std::fs::write(&path, plist)?;
+        run("launchctl", &["load", "-w", &path.to_string_lossy()])?;
+        Ok(format!("launchd agent {:?}", path))
+    } else {
+        let directory = home()?.join(".config/systemd/user");
+        std::fs::create_dir_all(&directory)?;
+        let exec = command.iter().map(|arg| quote(arg)).collect::<Vec<_>>().join(" ");
+        std::fs::write(
+            directory.join(format!("{}.service", NAME)),
+            format!(
+                "[Unit]\nDescription=Merges the Civilization VI Hall of Fame\n\n\
+                 [Service]\nType=oneshot\nExecStart={}\n",
+                exec
+            ),
+        )?;
+        std::fs::write(
+            directory.join(format!("{}.timer", NAME)),
+            format!(
+                "[Unit]\nDescription=Merges the Civilization VI Hall of Fame every {} minutes\n\n\
+                 [Timer]\nOnBootSec=5min\nOnUnitActiveSec={}min\n\n[Install]\nWantedBy=timers.target\n",
+                minutes, minutes
+            ),
+        )?;
+        run("systemctl", &["--user", "daemon-reload"])?;
+        run("systemctl", &["--user", "enable", "--now", &format!("{}.timer", NAME)])?;
+        Ok(format!("systemd user timer {}.timer in {:?}", NAME, directory))
+    }
+}
+
+/// Removes the registration of `install`.
+pub fn uninstall() -> Result<String, Box<dyn std::error::Error>> {
+    if cfg!(windows) {
+        run("schtasks", &["/Delete", "/F", "/TN", NAME])?;
+        Ok(format!("scheduled task {}", NAME))
+    } else if cfg!(target_os = "macos") {
+        let path = home()?.join("Library/LaunchAgents").join(format!("{}.plist", LAUNCHD_LABEL));
+        run("launchctl", &["unload", "-w", &path.to_string_lossy()])?;
+        std::fs::remove_file(&path)?;
+        Ok(format!("launchd agent {:?}", path))
+    } else {
+        let directory = home()?.join(".config/systemd/user");
+        run("systemctl", &["--user", "disable", "--now", &format!("{}.timer", NAME)])?;
+        for unit in &["service", "timer"] {
+            std::fs::remove_file(directory.join(format!("{}.{}", NAME, unit)))?;
+        }
+        run("systemctl", &["--user", "daemon-reload"])?;
+        Ok(format!("systemd user timer {}.timer", NAME))
+    }
+}
@@ -0,0 +1,118 @@
+//! Interactive prompt keeping a database open between commands.
+
+use std::io::{BufRead, Write};
+
+use rusqlite::Connection;
+
+use crate::names::Names;
+use crate::table::Format;
+#[cfg(feature = "charts")]
+use crate::graph;
+use crate::{history, milestones, show, stats, GameId, MergeOptions};
+
+const HELP: &str = "\
+Commands:
+  list [count]                 Most recently played games, 20 unless given
+  show <game-id>               Everything known about a game
+  graph <game-id> [stat]       Sparklines of a data set, SCORE unless given
+  stats [by <breakdown>]       Win rates by start-era, leader, map & more, and the leaderboard
+  milestones                   Records of the archive
+  merge <path>                 Merges another Hall of Fame into the database
+  undo [run]                   Undoes a merge run, the most recent unless given
+  help                         This list
+  quit                         Leaves the shell";
+
+fn game_id(argument: Option<&str>) -> Result<GameId, Box<dyn std::error::Error>> {
+    let argument = argument.ok_or("Missing game id")?;
+    Ok(argument.parse().map_err(|_| format!("Invalid game id {:?}", argument))?)
+}
+
+/// Runs a single command line, returning false to leave the shell.
+fn execute(con: &Connection, names: &Names, line: &str) -> Result<bool, Box<dyn std::error::Error>> {
+    let words: Vec<&str> = line.split_whitespace().collect();
+    match words.as_slice() {
+        [] => {}
+        ["quit"] | ["exit"] => return Ok(false),
+        ["help"] => println!("{}", HELP),
+        ["list"] => print!("{}", show::list(con, names, show::Sort::Date, Some(20))?.render(Format::Text)),
+        ["list", count] => {
+            let count: i64 = count.parse().map_err(|_| format!("Invalid count {:?}", count))?;
+            print!("{}", show::list(con, names, show::Sort::Date, Some(count))?.render(Format::Text));
+        }
+        ["show", id] => show::print_game(con, names, game_id(Some(id))?)?,
+        #[cfg(feature = "charts")]
+        ["graph", rest @ ..] => {
+            let game_id = game_id(rest.first().copied())?;
+            let stat = rest.get(1).copied().unwrap_or("SCORE");
+            let series = graph::series(con, game_id, stat)?;
+            if series.is_empty() {
+                let stats = graph::stats(con, game_id)?;
+                return Err(format!("Game {} has no data set {}, only: {}", game_id, stat, stats.join(", ")).into());
+            }
+            print!("{}", graph::render_sparklines(&series, false));
+        }
+        ["stats"] => {
+            for breakdown in stats::BREAKDOWNS {
+                println!("{}", stats::breakdown(con, *breakdown, names, false)?.render(Format::Text));
+            }
+            print!("{}", stats::leaderboard(con, names, false)?.render(Format::Text));
+        }
+        ["stats", "by", by] => print!("{}", stats::breakdown(con, by.parse()?, names, false)?.render(Format::Text)),
+        ["milestones"] => print!("{}", milestones::render(&milestones::compute(con, names)?)),
+        ["merge", ..] => {
+            let path = std::path::PathBuf::from(line.trim_start()["merge".len()..].trim());
+            let transaction = con.unchecked_transaction()?;
+            let run = history::start_run(con)?;
+            let summary = crate::merge_source(&path, con, None, run, &MergeOptions::default(), None)?;
+            crate::stamp_merge_metadata(con)?;
+            transaction.commit()?;
+            println!(
+                "Merged {:?}: {} game(s) copied, {} duplicate(s) skipped",
+                &path, summary.games_copied, summary.games_skipped
+            );
+        }
+        ["undo"] => match crate::undo_run(con, None)? {
+            Some((run, removed, restored)) => println!(
+                "Undid merge run {}: Removed {} game(s), restored {} replaced game(s)",
+                run, removed, restored
+            ),
+            None => println!("No merge history to undo"),
+        },
+        ["undo", run] => {
+            let run: history::RunId = run.parse().map_err(|_| format!("Invalid run {:?}", run))?;
+            match crate::undo_run(con, Some(run))? {
+                Some((run, removed, restored)) => println!(
+                    "Undid merge run {}: Removed {} game(s), restored {} replaced game(s)",
+                    run, removed, restored
+                ),
+                None => println!("No merge run {} to undo", run),
+            }
+        }
+        _ => return Err(format!("Unknown command {:?}, try help", line.trim()).into()),
+    }
+    Ok(true)
+}
+
+/// Reads commands from stdin until `quit` or end of input; errors are
+/// printed & don't end the shell.
+pub fn run(con: &Connection, names: &Names) -> Result<(), Box<dyn std::error::Error>> {
+    let stdin = std::io::stdin();
+    let mut lines = stdin.lock().lines();
+    loop {
+        print!("hof> ");
+        std::io::stdout().flush()?;
+        let line = match lines.next() {
+            Some(line) => line?,
+            None => {
+                println!();
+                break;
+            }
+        };
+        match execute(con, names, &line) {
+            Ok(true) => {}
+            Ok(false) => break,
+            Err(error) => eprintln!("Error: {}", error),
+        }
+    }
+    Ok(())
+}
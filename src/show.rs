@@ -0,0 +1,372 @@
+use std::collections::HashMap;
+use std::str::FromStr;
+
+use rusqlite::types::Value;
+use rusqlite::{params, Connection, NO_PARAMS};
+use serde_rusqlite::from_rows;
+
+use crate::copier::{self, Row};
+use crate::names::Names;
+use crate::table::{Format, Table};
+use crate::{dates, stats, Game, GameDataPointValue, GameId, LOCAL_PLAYER_JOIN};
+
+/// Labels of well-known setup data points, others are shown by their key.
+const SETUP_LABELS: [(&str, &str); 2] = [("MAP_SEED", "Map seed"), ("GAME_SEED", "Game seed")];
+
+/// Setup of a game as stored in its GameDataPointValues: Seeds & the
+/// advanced options, i.e. all data points with a plain (non-object) value.
+pub struct Setup {
+    pub seeds: Vec<(String, String)>,
+    pub options: Vec<(String, String)>,
+}
+
+fn value_text(value: &GameDataPointValue) -> Option<String> {
+    match (&value.value_string, value.value_numeric) {
+        (Some(text), _) => Some(text.clone()),
+        (None, Some(number)) => Some(number.to_string()),
+        (None, None) => None,
+    }
+}
+
+pub fn load_setup(con: &Connection, game_id: GameId) -> Result<Setup, Box<dyn std::error::Error>> {
+    let mut stmt = con.prepare(
+        "SELECT DataPoint, GameId, ValueObjectId, ValueType, ValueString, ValueNumeric FROM GameDataPointValues \
+         WHERE GameId = ? AND ValueObjectId IS NULL ORDER BY DataPoint",
+    )?;
+    let mut setup = Setup {
+        seeds: Vec::new(),
+        options: Vec::new(),
+    };
+    for value in from_rows::<GameDataPointValue>(stmt.query(params![game_id])?) {
+        let value = value?;
+        let text = match value_text(&value) {
+            Some(text) => text,
+            None => continue,
+        };
+        let label = SETUP_LABELS
+            .iter()
+            .find(|(key, _)| *key == value.data_point)
+            .map_or_else(|| value.data_point.clone(), |(_, label)| label.to_string());
+        if value.data_point.contains("SEED") {
+            setup.seeds.push((label, text));
+        } else {
+            setup.options.push((label, text));
+        }
+    }
+    Ok(setup)
+}
+
+/// Prints everything known about a game.
+fn text(value: &Value) -> String {
+    match value {
+        Value::Null => "-".to_string(),
+        Value::Integer(number) => number.to_string(),
+        Value::Real(number) => number.to_string(),
+        Value::Text(text) => text.clone(),
+        Value::Blob(bytes) => format!("{} bytes", bytes.len()),
+    }
+}
+
+/// A GameObject as `TYPE Name`, e.g. `WONDER Pyramids`.
+fn object_text(object: &Row) -> String {
+    match object.get("Name") {
+        Value::Null => text(object.get("Type")),
+        name => format!("{} {}", text(object.get("Type")), text(name)),
+    }
+}
+
+/// A data point's value, objects by their type & name.
+fn data_point_text(value: &Row, objects: &HashMap<i64, String>) -> String {
+    match value.id("ValueObjectId") {
+        Some(id) => objects.get(&id).cloned().unwrap_or_else(|| format!("missing object {}", id)),
+        None => match value.get("ValueString") {
+            Value::Null => text(value.get("ValueNumeric")),
+            string => text(string),
+        },
+    }
+}
+
+/// Prints the players, data points & objects of a game.
+fn print_rows(con: &Connection, names: &Names, game_id: GameId) -> rusqlite::Result<()> {
+    let objects = copier::read(con, "GameObjects", "WHERE t.GameId = ? ORDER BY t.ObjectId", params![game_id])?;
+    let object_texts: HashMap<i64, String> = objects
+        .iter()
+        .filter_map(|object| Some((object.id("ObjectId")?, object_text(object))))
+        .collect();
+
+    let players = copier::read(
+        con,
+        "GamePlayers",
+        "JOIN GameObjects go ON go.ObjectId = t.PlayerObjectId WHERE go.GameId = ? ORDER BY t.PlayerId",
+        params![game_id],
+    )?;
+    let mut leaders = HashMap::new();
+    if !players.is_empty() {
+        let mut table = Table::new(&["Player", "Leader", "Civilization", "Team", "Difficulty", "Score", "Kind"]);
+        for player in &players {
+            let leader = match player.get("LeaderType") {
+                Value::Text(leader_type) => names.leader(leader_type, player.text("LeaderName")),
+                other => text(other),
+            };
+            if let Some(id) = player.id("PlayerObjectId") {
+                leaders.insert(id, leader.clone());
+            }
+            let kind = match (player.id("IsLocal"), player.id("IsAI"), player.id("IsMajor")) {
+                (Some(1), _, _) => "local",
+                (_, Some(0), _) => "human",
+                (_, _, Some(0)) => "minor",
+                _ => "AI",
+            };
+            table.push(vec![
+                text(player.get("PlayerId")),
+                leader,
+                player.text("CivilizationType").map_or("-", |civilization| names.display(civilization)).to_string(),
+                text(player.get("TeamId")),
+                player.text("DifficultyType").map_or("-", |difficulty| names.display(difficulty)).to_string(),
+                text(player.get("Score")),
+                kind.to_string(),
+            ]);
+        }
+        println!("Players");
+        print!("{}", table.render(Format::Text));
+    }
+
+    let values = copier::read(
+        con,
+        "GameDataPointValues",
+        "WHERE t.GameId = ? ORDER BY t.DataPoint",
+        params![game_id],
+    )?;
+    if !values.is_empty() {
+        let mut table = Table::new(&["Data point", "Value"]);
+        for value in &values {
+            table.push(vec![text(value.get("DataPoint")), data_point_text(value, &object_texts)]);
+        }
+        println!("Data points");
+        print!("{}", table.render(Format::Text));
+    }
+
+    let object_values = copier::read(
+        con,
+        "ObjectDataPointValues",
+        "JOIN GameObjects go ON go.ObjectId = t.ObjectId WHERE go.GameId = ? ORDER BY t.ObjectId, t.DataPoint",
+        params![game_id],
+    )?;
+    let mut values_by_object: HashMap<i64, Vec<String>> = HashMap::new();
+    for value in &object_values {
+        if let Some(id) = value.id("ObjectId") {
+            values_by_object.entry(id).or_default().push(format!(
+                "{}: {}",
+                text(value.get("DataPoint")),
+                data_point_text(value, &object_texts)
+            ));
+        }
+    }
+    // Players are listed above already
+    let objects: Vec<&Row> = objects.iter().filter(|object| object.text("Type") != Some("PLAYER")).collect();
+    if !objects.is_empty() {
+        let mut table = Table::new(&["Object", "Type", "Name", "Player", "Plot", "Values"]);
+        for object in objects {
+            let id = object.id("ObjectId");
+            table.push(vec![
+                text(object.get("ObjectId")),
+                text(object.get("Type")),
+                text(object.get("Name")),
+                object
+                    .id("PlayerObjectId")
+                    .and_then(|player| leaders.get(&player).cloned())
+                    .unwrap_or_else(|| "-".to_string()),
+                text(object.get("PlotIndex")),
+                id.and_then(|id| values_by_object.get(&id)).map_or_else(String::new, |values| values.join(", ")),
+            ]);
+        }
+        println!("Objects");
+        print!("{}", table.render(Format::Text));
+    }
+    Ok(())
+}
+
+pub fn print_game(con: &Connection, names: &Names, game_id: GameId) -> Result<(), Box<dyn std::error::Error>> {
+    let mut stmt = con.prepare("SELECT * FROM Games WHERE GameId = ?")?;
+    let game = from_rows::<Game>(stmt.query(params![game_id])?)
+        .next()
+        .ok_or_else(|| format!("Game {} not found", game_id))??;
+
+    println!("Game {}", game.game_id);
+    println!("  Last played:  {}", dates::format_date(game.last_played as i64));
+    println!("  Ruleset:      {}", game.rule_set);
+    println!("  Map:          {} ({})", game.map, game.map_size_type);
+    println!("  Speed:        {}", game.game_speed_type);
+    println!("  Start:        {} (turn {})", game.start_era_type, game.start_turn);
+    println!("  Turns:        {}", game.turn_count);
+    match (&game.victory_type, game.victor_team_id) {
+        (Some(victory), Some(team)) => println!("  Victory:      {} by team {}", victory, team),
+        (Some(victory), None) => println!("  Victory:      {}", victory),
+        _ => println!("  Victory:      -"),
+    }
+    if let Some(traces) = crate::content::modded_games(con)?.get(&game_id) {
+        println!("  Modded:       {}", traces.join(", "));
+    }
+
+    let setup = load_setup(con, game_id)?;
+    if !setup.seeds.is_empty() {
+        println!("Seeds");
+        for (label, value) in &setup.seeds {
+            println!("  {}: {}", label, value);
+        }
+    }
+    if !setup.options.is_empty() {
+        println!("Setup");
+        for (label, value) in &setup.options {
+            println!("  {}: {}", label, value);
+        }
+    }
+    print_rows(con, names, game_id)?;
+
+    let has_links: i64 = con.query_row(
+        "SELECT COUNT(*) FROM sqlite_master WHERE type = 'table' AND name = 'MergeSaveLinks'",
+        NO_PARAMS,
+        |row| row.get(0),
+    )?;
+    if has_links > 0 {
+        let mut stmt = con.prepare("SELECT Path FROM MergeSaveLinks WHERE GameId = ?")?;
+        let mut rows = stmt.query(params![game_id])?;
+        if let Some(row) = rows.next()? {
+            println!("Save file: {}", row.get::<_, String>(0)?);
+        }
+    }
+    Ok(())
+}
+
+/// One line on a game: Its leader, civilization, map, victory, turns & date.
+pub fn describe(con: &Connection, names: &Names, game: &Game) -> rusqlite::Result<String> {
+    let (leader_type, leader_name, civilization) = con.query_row(
+        &format!(
+            "SELECT lp.LeaderType, lp.LeaderName, lp.CivilizationType FROM Games g {} WHERE g.GameId = ?",
+            LOCAL_PLAYER_JOIN
+        ),
+        params![game.game_id],
+        |row| {
+            Ok((
+                row.get::<_, Option<String>>(0)?,
+                row.get::<_, Option<String>>(1)?,
+                row.get::<_, Option<String>>(2)?,
+            ))
+        },
+    )?;
+    let leader = match leader_type {
+        Some(leader_type) => names.leader(&leader_type, leader_name.as_deref()),
+        None => "-".to_string(),
+    };
+    Ok(format!(
+        "Game {}: {} ({}) on {}, {}, {} turns, {}",
+        game.game_id,
+        leader,
+        civilization.as_deref().map_or("-", |civilization| names.display(civilization)),
+        game.map,
+        game.victory_type.as_deref().map_or("no victory", |victory| names.display(victory)),
+        game.turn_count,
+        dates::format_date(i64::from(game.last_played)),
+    ))
+}
+
+/// Order of the games `list` shows.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum Sort {
+    /// Most recently played first
+    #[default]
+    Date,
+    Game,
+    Leader,
+    Map,
+    /// Longest first
+    Turns,
+    Victory,
+}
+
+impl FromStr for Sort {
+    type Err = String;
+
+    fn from_str(sort: &str) -> Result<Self, Self::Err> {
+        match sort {
+            "date" => Ok(Sort::Date),
+            "game" => Ok(Sort::Game),
+            "leader" => Ok(Sort::Leader),
+            "map" => Ok(Sort::Map),
+            "turns" => Ok(Sort::Turns),
+            "victory" => Ok(Sort::Victory),
+            _ => Err(format!(
+                "Unknown sort order {:?}, expected date, game, leader, map, turns or victory",
+                sort
+            )),
+        }
+    }
+}
+
+impl Sort {
+    fn order_by(self) -> &'static str {
+        match self {
+            Sort::Date => "g.LastPlayed DESC",
+            Sort::Game => "g.GameId",
+            Sort::Leader => "lp.LeaderType, g.LastPlayed DESC",
+            Sort::Map => "g.Map, g.LastPlayed DESC",
+            Sort::Turns => "g.TurnCount DESC, g.LastPlayed DESC",
+            Sort::Victory => "g.VictoryType, g.LastPlayed DESC",
+        }
+    }
+}
+
+/// The games of a database - at most `limit` of them - with their GameIds to pick them by.
+pub fn list(con: &Connection, names: &Names, sort: Sort, limit: Option<i64>) -> rusqlite::Result<Table> {
+    let mut stmt = con.prepare(&format!(
+        "SELECT g.GameId, g.LastPlayed, lp.LeaderType, lp.LeaderName, g.Map, g.TurnCount, g.VictoryType, \
+         COALESCE(g.VictorTeamId = lp.TeamId, 0), lp.Score, g.GameSpeedType, lp.DifficultyType, lp.CivilizationType \
+         FROM Games g {} ORDER BY {} LIMIT {}",
+        LOCAL_PLAYER_JOIN,
+        sort.order_by(),
+        limit.unwrap_or(-1)
+    ))?;
+    let mut table = Table::new(&[
+        "Game",
+        "Last played",
+        "Leader",
+        "Civilization",
+        "Map",
+        "Speed",
+        "Turns",
+        "Victory",
+        "Weighted score",
+    ]);
+    let mut rows = stmt.query(NO_PARAMS)?;
+    while let Some(row) = rows.next()? {
+        let leader = match row.get::<_, Option<String>>(2)? {
+            Some(leader_type) => names.leader(&leader_type, row.get::<_, Option<String>>(3)?.as_deref()),
+            None => "-".to_string(),
+        };
+        let victory = match (row.get::<_, Option<String>>(6)?, row.get::<_, bool>(7)?) {
+            (Some(victory), true) => format!("{} (won)", names.display(&victory)),
+            (Some(victory), false) => names.display(&victory).to_string(),
+            (None, _) => "-".to_string(),
+        };
+        let weighted = match row.get::<_, Option<i64>>(8)? {
+            Some(score) => {
+                let weighted = stats::weighted_score(score, &row.get::<_, String>(9)?, row.get::<_, Option<String>>(10)?.as_deref());
+                format!("{:.0}", weighted)
+            }
+            None => "-".to_string(),
+        };
+        table.push(vec![
+            row.get::<_, GameId>(0)?.to_string(),
+            dates::format_date(row.get(1)?),
+            leader,
+            row.get::<_, Option<String>>(11)?
+                .map_or_else(|| "-".to_string(), |civilization| names.display(&civilization).to_string()),
+            row.get(4)?,
+            names.display(&row.get::<_, String>(9)?).to_string(),
+            row.get::<_, i64>(5)?.to_string(),
+            victory,
+            weighted,
+        ]);
+    }
+    Ok(table)
+}
@@ -0,0 +1,539 @@
+use std::collections::HashMap;
+use std::io::{BufRead, Read, Write};
+use std::path::{Path, PathBuf};
+
+use log::{debug, info, warn};
+
+/// File name the game uses for its Hall-of-Fame database.
+const HOF_FILE_NAME: &str = "HallofFame.sqlite";
+
+/// A single database file to merge & the command line argument it was found through.
+#[derive(Debug)]
+pub struct Source {
+    pub path: PathBuf,
+    pub origin: PathBuf,
+}
+
+/// A source that is skipped because it is the same file as - or has the same
+/// content as - an earlier source.
+#[derive(Debug)]
+pub struct Duplicate {
+    pub source: Source,
+    pub duplicate_of: PathBuf,
+}
+
+/// How downloaded sources are verified before they are merged.
+#[derive(Debug, Default)]
+#[cfg_attr(not(feature = "remote"), allow(dead_code))]
+pub struct Verification {
+    /// Accepted SHA-256 checksums (hex) of downloads
+    pub sha256: Vec<String>,
+    /// Whether to fetch `<url>.sha256` & check each download against it
+    pub sidecar: bool,
+}
+
+/// Files written to the temp directory for the sources - downloads &
+/// decompressed archives - removed when dropped, however the merge ends.
+#[derive(Debug, Default)]
+pub struct TempFiles(Vec<PathBuf>);
+
+impl TempFiles {
+    /// Registers `path` for removal - before it is written, so a partial file is removed as well.
+    fn add(&mut self, path: PathBuf) -> PathBuf {
+        self.0.push(path.clone());
+        path
+    }
+}
+
+impl Drop for TempFiles {
+    fn drop(&mut self) {
+        for path in &self.0 {
+            match std::fs::remove_file(path) {
+                Ok(()) => debug!("Removed {:?}", &path),
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+                Err(e) => warn!("Couldn't remove {:?}: {}", &path, e),
+            }
+        }
+    }
+}
+
+/// Expands the given arguments into database files: Files are taken as-is,
+/// directories are searched recursively for `HallofFame.sqlite` files and
+/// glob patterns (`*`, `?` & `**`) are expanded - shells on Windows don't.
+/// `.gz` & `.zip` files are decompressed into the temp directory.
+pub fn discover(args: &[PathBuf], verification: &Verification, temp_files: &mut TempFiles) -> std::io::Result<Vec<Source>> {
+    let mut sources = find(args, verification, temp_files)?;
+    for source in &mut sources {
+        if let Some(compression) = compression(&source.path)? {
+            source.path = decompress(&source.path, compression, temp_files)?;
+        }
+    }
+    Ok(sources)
+}
+
+fn find(args: &[PathBuf], verification: &Verification, temp_files: &mut TempFiles) -> std::io::Result<Vec<Source>> {
+    let mut sources = Vec::new();
+    for arg in args {
+        if is_url(arg) {
+            let path = download_verified(arg, verification, temp_files)?;
+            sources.push(Source {
+                path,
+                origin: arg.clone(),
+            });
+        } else if is_pattern(arg) {
+            let matches = expand_glob(arg)?;
+            if matches.is_empty() {
+                warn!("Pattern {:?} didn't match any file", &arg);
+            }
+            for path in matches {
+                if path.is_dir() {
+                    let mut found = Vec::new();
+                    find_hof_files(&path, &mut found)?;
+                    found.sort();
+                    sources.extend(found.into_iter().map(|path| Source {
+                        path,
+                        origin: arg.clone(),
+                    }));
+                } else {
+                    sources.push(Source {
+                        path,
+                        origin: arg.clone(),
+                    });
+                }
+            }
+        } else if arg.is_dir() {
+            let mut found = Vec::new();
+            find_hof_files(arg, &mut found)?;
+            found.sort();
+            info!("Found {} Hall-of-Fame file(s) below {:?}", found.len(), &arg);
+            sources.extend(found.into_iter().map(|path| Source {
+                path,
+                origin: arg.clone(),
+            }));
+        } else {
+            sources.push(Source {
+                path: arg.clone(),
+                origin: arg.clone(),
+            });
+        }
+    }
+    Ok(sources)
+}
+
+fn find_hof_files(dir: &Path, found: &mut Vec<PathBuf>) -> std::io::Result<()> {
+    for entry in std::fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.is_dir() {
+            find_hof_files(&path, found)?;
+        } else if path
+            .file_name()
+            .and_then(|name| name.to_str())
+            .is_some_and(|name| name.eq_ignore_ascii_case(HOF_FILE_NAME))
+        {
+            debug!("Discovered {:?}", &path);
+            found.push(path);
+        }
+    }
+    Ok(())
+}
+
+/// Removes sources that resolve to the same file or are byte-identical
+/// copies of an earlier source, keeping the first occurrence.
+pub fn dedupe(sources: Vec<Source>) -> std::io::Result<(Vec<Source>, Vec<Duplicate>)> {
+    let mut by_path: HashMap<PathBuf, PathBuf> = HashMap::new();
+    let mut by_content: HashMap<(u64, u64), PathBuf> = HashMap::new();
+    let mut unique = Vec::new();
+    let mut duplicates = Vec::new();
+
+    for source in sources {
+        let canonical = std::fs::canonicalize(&source.path)?;
+        let content = (
+            std::fs::metadata(&canonical)?.len(),
+            crate::hash::file_hash(&canonical)?,
+        );
+
+        let earlier = by_path
+            .get(&canonical)
+            .or_else(|| by_content.get(&content))
+            .cloned();
+        match earlier {
+            Some(duplicate_of) => {
+                info!("Skipping {:?}: Same as {:?}", &source.path, &duplicate_of);
+                duplicates.push(Duplicate { source, duplicate_of });
+            }
+            None => {
+                by_path.insert(canonical, source.path.clone());
+                by_content.insert(content, source.path.clone());
+                unique.push(source);
+            }
+        }
+    }
+    Ok((unique, duplicates))
+}
+
+/// Whether two paths name the same file - also through symlinks, hardlinks or
+/// differently spelled paths. A path that doesn't exist yet is no file.
+pub fn same_file(a: &Path, b: &Path) -> std::io::Result<bool> {
+    let (a, b) = match (std::fs::canonicalize(a), std::fs::canonicalize(b)) {
+        (Ok(a), Ok(b)) => (a, b),
+        _ => return Ok(false),
+    };
+    Ok(a == b || matches!((file_id(&a)?, file_id(&b)?), (Some(a), Some(b)) if a == b))
+}
+
+/// Device & inode of a file, the same for all its hardlinks.
+#[cfg(unix)]
+fn file_id(path: &Path) -> std::io::Result<Option<(u64, u64)>> {
+    use std::os::unix::fs::MetadataExt;
+    let metadata = std::fs::metadata(path)?;
+    Ok(Some((metadata.dev(), metadata.ino())))
+}
+
+#[cfg(not(unix))]
+fn file_id(_: &Path) -> std::io::Result<Option<(u64, u64)>> {
+    Ok(None)
+}
+
+/// Reads source arguments from a file (or stdin for `-`): One path, pattern
+/// or URL per line, empty lines & lines starting with `#` are ignored.
+pub fn read_list(list: &Path) -> std::io::Result<Vec<PathBuf>> {
+    let reader: Box<dyn BufRead> = if list == Path::new("-") {
+        Box::new(std::io::BufReader::new(std::io::stdin()))
+    } else {
+        Box::new(std::io::BufReader::new(std::fs::File::open(list)?))
+    };
+
+    let mut args = Vec::new();
+    for line in reader.lines() {
+        let line = line?;
+        let line = line.trim();
+        if !line.is_empty() && !line.starts_with('#') {
+            args.push(PathBuf::from(line));
+        }
+    }
+    info!("Read {} source(s) from {:?}", args.len(), &list);
+    Ok(args)
+}
+
+fn is_url(arg: &Path) -> bool {
+    arg.to_str()
+        .is_some_and(|s| s.starts_with("http://") || s.starts_with("https://"))
+}
+
+/// A file in the temp directory, unique per process & `key`.
+fn temp_path(key: &str) -> PathBuf {
+    let file_name = format!(
+        "civ6-hof-merge-{}-{}.sqlite",
+        std::process::id(),
+        key.bytes().fold(0u32, |hash, b| hash.wrapping_mul(31).wrapping_add(b as u32))
+    );
+    std::env::temp_dir().join(file_name)
+}
+
+#[cfg(not(feature = "remote"))]
+fn download_verified(url: &Path, _: &Verification, _: &mut TempFiles) -> std::io::Result<PathBuf> {
+    Err(std::io::Error::other(format!(
+        "{:?} is a URL - downloading needs civ6-hof-merge built with `--features remote`",
+        url
+    )))
+}
+
+/// Downloads a remote database & verifies it.
+#[cfg(feature = "remote")]
+fn download_verified(url: &Path, verification: &Verification, temp_files: &mut TempFiles) -> std::io::Result<PathBuf> {
+    let path = download(url, temp_files)?;
+    verify(url, &path, verification, temp_files)?;
+    Ok(path)
+}
+
+/// Fetches a remote database with `curl` into the temp directory.
+#[cfg(feature = "remote")]
+fn download(url: &Path, temp_files: &mut TempFiles) -> std::io::Result<PathBuf> {
+    let url = url.to_string_lossy();
+    let path = temp_files.add(temp_path(&url));
+    fetch(&url, &path)?;
+    Ok(path)
+}
+
+/// Checks a download against the given checksums and/or its `.sha256`
+/// sidecar - a truncated or tampered download fails the merge.
+#[cfg(feature = "remote")]
+fn verify(url: &Path, path: &Path, verification: &Verification, temp_files: &mut TempFiles) -> std::io::Result<()> {
+    if verification.sha256.is_empty() && !verification.sidecar {
+        return Ok(());
+    }
+    let actual = crate::hash::file_sha256(path)?;
+
+    let mut expected: Vec<String> = verification
+        .sha256
+        .iter()
+        .map(|sha256| sha256.to_lowercase())
+        .collect();
+    if verification.sidecar {
+        let sidecar_url = format!("{}.sha256", url.to_string_lossy());
+        let sidecar = temp_files.add(temp_path(&sidecar_url));
+        fetch(&sidecar_url, &sidecar)?;
+        // `sha256sum` format: The checksum followed by the file name
+        let content = std::fs::read_to_string(&sidecar)?;
+        std::fs::remove_file(&sidecar)?;
+        match content.split_whitespace().next() {
+            Some(sha256) => expected.push(sha256.to_lowercase()),
+            None => return Err(std::io::Error::other(format!("{} is empty", &sidecar_url))),
+        }
+    }
+
+    if expected.contains(&actual) {
+        info!("Verified {:?}: SHA-256 {}", &url, &actual);
+        Ok(())
+    } else {
+        Err(std::io::Error::other(format!(
+            "Checksum mismatch for {:?}: SHA-256 is {}, expected {}",
+            &url,
+            &actual,
+            expected.join(" or ")
+        )))
+    }
+}
+
+#[cfg(feature = "remote")]
+fn fetch(url: &str, path: &Path) -> std::io::Result<()> {
+    info!("Downloading {} to {:?}", &url, &path);
+    let status = std::process::Command::new("curl")
+        .args(["--fail", "--silent", "--show-error", "--location", "--output"])
+        .arg(path)
+        .arg(url)
+        .status()?;
+    if !status.success() {
+        return Err(std::io::Error::other(format!("Downloading {} failed: {}", &url, status)));
+    }
+    Ok(())
+}
+
+/// How a source is compressed.
+enum Compression {
+    Gzip,
+    Zip,
+}
+
+/// Tells a compressed source by its magic bytes - also a download, whose
+/// temp file is named `.sqlite` whatever the URL ends in.
+fn compression(path: &Path) -> std::io::Result<Option<Compression>> {
+    let mut magic = Vec::with_capacity(4);
+    match std::fs::File::open(path) {
+        Ok(file) => file.take(4).read_to_end(&mut magic)?,
+        // Reported when the source is opened
+        Err(_) => return Ok(None),
+    };
+    Ok(if magic.starts_with(&crate::output::GZIP_MAGIC) {
+        Some(Compression::Gzip)
+    } else if magic.starts_with(b"PK\x03\x04") {
+        Some(Compression::Zip)
+    } else {
+        None
+    })
+}
+
+/// Extracts a gzipped database, or the Hall-of-Fame database within a zip
+/// archive, into the temp directory - streamed, never held in memory whole.
+fn decompress(archive: &Path, compression: Compression, temp_files: &mut TempFiles) -> std::io::Result<PathBuf> {
+    let path = temp_files.add(temp_path(&archive.to_string_lossy()));
+    let mut out = std::io::BufWriter::new(std::fs::File::create(&path)?);
+    let input = std::io::BufReader::new(std::fs::File::open(archive)?);
+    let copied = match compression {
+        Compression::Gzip => {
+            info!("Decompressing {:?} to {:?}", &archive, &path);
+            std::io::copy(&mut flate2::read::GzDecoder::new(input), &mut out)
+        }
+        Compression::Zip => {
+            let mut zip = zip::ZipArchive::new(input)?;
+            let entry = zip_entry(archive, zip.file_names())?;
+            info!("Extracting {:?} from {:?} to {:?}", &entry, &archive, &path);
+            let mut file = zip.by_name(&entry)?;
+            std::io::copy(&mut file, &mut out)
+        }
+    };
+    copied.map_err(|e| std::io::Error::other(format!("Decompressing {:?} failed: {}", &archive, e)))?;
+    out.flush()?;
+    Ok(path)
+}
+
+/// Picks the `HallofFame.sqlite` entry of a zip archive - or its only
+/// `.sqlite` entry if it was renamed.
+fn zip_entry<'a>(archive: &Path, entries: impl Iterator<Item = &'a str>) -> std::io::Result<String> {
+    let entries: Vec<&str> = entries.collect();
+    let file_name = |entry: &str| entry.rsplit('/').next().unwrap_or(entry).to_owned();
+
+    let mut databases = entries
+        .iter()
+        .filter(|entry| file_name(entry).to_lowercase().ends_with(".sqlite"));
+    match entries
+        .iter()
+        .find(|entry| file_name(entry).eq_ignore_ascii_case(HOF_FILE_NAME))
+    {
+        Some(entry) => Ok(entry.to_string()),
+        None => match (databases.next(), databases.next()) {
+            (Some(entry), None) => Ok(entry.to_string()),
+            _ => Err(std::io::Error::other(format!(
+                "{:?} doesn't contain a single {}",
+                &archive, HOF_FILE_NAME
+            ))),
+        },
+    }
+}
+
+fn is_pattern(arg: &Path) -> bool {
+    arg.to_str().is_some_and(|s| s.contains(['*', '?']))
+}
+
+/// Walks the file system from the longest wildcard-free prefix of `pattern`,
+/// matching each following path component.
+fn expand_glob(pattern: &Path) -> std::io::Result<Vec<PathBuf>> {
+    let mut base = PathBuf::new();
+    let mut parts: Vec<String> = Vec::new();
+    for component in pattern.components() {
+        let part = component.as_os_str().to_string_lossy();
+        if parts.is_empty() && !part.contains(['*', '?']) {
+            base.push(component);
+        } else {
+            parts.push(part.into_owned());
+        }
+    }
+    if base.as_os_str().is_empty() {
+        base.push(".");
+    }
+
+    let mut found = Vec::new();
+    glob_walk(&base, &parts, &mut found)?;
+    found.sort();
+    found.dedup();
+    debug!("Pattern {:?} matched {:?}", &pattern, &found);
+    Ok(found)
+}
+
+fn glob_walk(dir: &Path, parts: &[String], found: &mut Vec<PathBuf>) -> std::io::Result<()> {
+    let (part, rest) = match parts.split_first() {
+        Some(split) => split,
+        None => {
+            if dir.exists() {
+                found.push(dir.to_path_buf());
+            }
+            return Ok(());
+        }
+    };
+    if !dir.is_dir() {
+        return Ok(());
+    }
+
+    if part == "**" {
+        if !rest.is_empty() {
+            glob_walk(dir, rest, found)?;
+        }
+        for entry in std::fs::read_dir(dir)? {
+            let path = entry?.path();
+            if path.is_dir() {
+                glob_walk(&path, parts, found)?;
+            } else if rest.is_empty() {
+                found.push(path);
+            }
+        }
+        return Ok(());
+    }
+
+    for entry in std::fs::read_dir(dir)? {
+        let entry = entry?;
+        if wildcard_match(part, &entry.file_name().to_string_lossy()) {
+            glob_walk(&entry.path(), rest, found)?;
+        }
+    }
+    Ok(())
+}
+
+/// Compares characters of file names - ignoring case where the file system does.
+fn same_char(a: char, b: char) -> bool {
+    if cfg!(windows) {
+        a == b || a.to_lowercase().eq(b.to_lowercase())
+    } else {
+        a == b
+    }
+}
+
+/// Matches a single path component against a pattern with `*` & `?` wildcards.
+fn wildcard_match(pattern: &str, name: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let name: Vec<char> = name.chars().collect();
+    let (mut p, mut n) = (0, 0);
+    let mut backtrack: Option<(usize, usize)> = None;
+
+    while n < name.len() {
+        if p < pattern.len() && (pattern[p] == '?' || same_char(pattern[p], name[n])) {
+            p += 1;
+            n += 1;
+        } else if p < pattern.len() && pattern[p] == '*' {
+            backtrack = Some((p, n));
+            p += 1;
+        } else if let Some((star_p, star_n)) = backtrack {
+            p = star_p + 1;
+            n = star_n + 1;
+            backtrack = Some((star_p, star_n + 1));
+        } else {
+            return false;
+        }
+    }
+    pattern[p..].iter().all(|&c| c == '*')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn wildcard_match_matches_stars_and_question_marks() {
+        assert!(wildcard_match("*", "HallofFame.sqlite"));
+        assert!(wildcard_match("*.sqlite", "HallofFame.sqlite"));
+        assert!(wildcard_match("Hall*Fame*", "HallofFame.sqlite"));
+        assert!(wildcard_match("*.sqlite", ".sqlite"));
+        assert!(wildcard_match("HallofFame-?.sqlite", "HallofFame-2.sqlite"));
+        assert!(wildcard_match("?*?", "ab"));
+    }
+
+    #[test]
+    fn wildcard_match_rejects_other_names() {
+        assert!(!wildcard_match("*.sqlite", "HallofFame.sqlite.gz"));
+        assert!(!wildcard_match("HallofFame-?.sqlite", "HallofFame-12.sqlite"));
+        assert!(!wildcard_match("HallofFame-?.sqlite", "HallofFame-.sqlite"));
+        assert!(!wildcard_match("?*?", "a"));
+        assert!(!wildcard_match("", "a"));
+    }
+
+    #[test]
+    fn wildcard_match_ignores_case_only_on_windows() {
+        assert_eq!(wildcard_match("*.SQLITE", "HallofFame.sqlite"), cfg!(windows));
+        assert_eq!(wildcard_match("halloffame-?.sqlite", "HallofFame-1.sqlite"), cfg!(windows));
+    }
+
+    #[test]
+    fn expand_glob_descends_with_double_stars() {
+        let root = std::env::temp_dir().join(format!("civ6-hof-merge-glob-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&root);
+        for file in &["a.sqlite", "b.txt", "x/c.sqlite", "x/y/d.sqlite", "x/y/e.txt"] {
+            let path = root.join(file);
+            std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+            std::fs::write(path, "").unwrap();
+        }
+
+        let matched = |pattern: &str| -> Vec<PathBuf> {
+            let found = expand_glob(&root.join(pattern)).unwrap();
+            found.iter().map(|path| path.strip_prefix(&root).unwrap().to_path_buf()).collect()
+        };
+        let paths = |files: &[&str]| -> Vec<PathBuf> { files.iter().map(PathBuf::from).collect() };
+        assert_eq!(matched("*.sqlite"), paths(&["a.sqlite"]));
+        assert_eq!(matched("?.txt"), paths(&["b.txt"]));
+        assert_eq!(matched("**/*.sqlite"), paths(&["a.sqlite", "x/c.sqlite", "x/y/d.sqlite"]));
+        assert_eq!(matched("x/**"), paths(&["x/c.sqlite", "x/y/d.sqlite", "x/y/e.txt"]));
+        assert_eq!(matched("*/?/*.txt"), paths(&["x/y/e.txt"]));
+        assert!(matched("**/*.gz").is_empty());
+        assert!(matched("z*/*.sqlite").is_empty());
+
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+}
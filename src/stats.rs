@@ -0,0 +1,356 @@
+use std::collections::BTreeMap;
+use std::str::FromStr;
+
+use rusqlite::{Connection, NO_PARAMS};
+
+use crate::{content, dates, maps};
+use crate::names::Names;
+use crate::table::Table;
+use crate::{GameId, LOCAL_PLAYER_JOIN};
+
+/// Start eras in the order of the game.
+const ERAS: [&str; 9] = [
+    "ERA_ANCIENT",
+    "ERA_CLASSICAL",
+    "ERA_MEDIEVAL",
+    "ERA_RENAISSANCE",
+    "ERA_INDUSTRIAL",
+    "ERA_MODERN",
+    "ERA_ATOMIC",
+    "ERA_INFORMATION",
+    "ERA_FUTURE",
+];
+
+/// Turn costs of the game speeds in percent of Standard's.
+const SPEED_PERCENTS: [(&str, f64); 5] = [
+    ("GAMESPEED_ONLINE", 50.0),
+    ("GAMESPEED_QUICK", 67.0),
+    ("GAMESPEED_STANDARD", 100.0),
+    ("GAMESPEED_EPIC", 150.0),
+    ("GAMESPEED_MARATHON", 300.0),
+];
+
+/// Difficulties from easiest to hardest, Prince being the "fair" level 4.
+pub const DIFFICULTIES: [&str; 8] = [
+    "DIFFICULTY_SETTLER",
+    "DIFFICULTY_CHIEFTAIN",
+    "DIFFICULTY_WARLORD",
+    "DIFFICULTY_PRINCE",
+    "DIFFICULTY_KING",
+    "DIFFICULTY_EMPEROR",
+    "DIFFICULTY_IMMORTAL",
+    "DIFFICULTY_DEITY",
+];
+
+/// Turn costs of a speed in percent of Standard's, unknown speeds count as Standard.
+fn speed_percent(speed: &str) -> f64 {
+    SPEED_PERCENTS
+        .iter()
+        .find(|(known, _)| *known == speed)
+        .map_or(100.0, |(_, percent)| *percent)
+}
+
+/// Turns a game would have taken at Standard speed, e.g. 115 Online turns are 230 Standard ones.
+pub fn standard_turns(speed: &str, turns: i64) -> f64 {
+    turns as f64 * 100.0 / speed_percent(speed)
+}
+
+/// Score scaled by the difficulty weight & to Standard speed - slower speeds leave more turns to
+/// score in, so a Marathon score counts a third.
+pub fn weighted_score(score: i64, speed: &str, difficulty: Option<&str>) -> f64 {
+    score as f64 * difficulty_weight(difficulty) * 100.0 / speed_percent(speed)
+}
+
+/// Weight of a win at a difficulty: 1 at Prince, a quarter more or less per level above or below.
+/// Unknown difficulties weigh like Prince.
+pub fn difficulty_weight(difficulty: Option<&str>) -> f64 {
+    let level = difficulty
+        .and_then(|difficulty| DIFFICULTIES.iter().position(|known| *known == difficulty))
+        .unwrap_or(3);
+    1.0 + 0.25 * (level as f64 - 3.0)
+}
+
+/// Rating of a win, comparable across speeds & difficulties: The difficulty weight per
+/// 100 Standard turns taken - faster & harder wins rate higher.
+pub fn rating(speed: &str, turns: i64, difficulty: Option<&str>) -> f64 {
+    difficulty_weight(difficulty) * 100.0 / standard_turns(speed, turns).max(1.0)
+}
+
+/// Width of the longest bar of the timeline's histogram.
+const TIMELINE_WIDTH: usize = 40;
+
+/// What the games are grouped by.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Breakdown {
+    StartEra,
+    /// The local player's leader
+    Leader,
+    /// The victory type the game ended in - won by anyone
+    Victory,
+    Speed,
+    /// The local player's difficulty
+    Difficulty,
+    /// The map script, most played first
+    Map,
+    /// Expansion & DLC packs inferred from the ruleset & leaders
+    Content,
+}
+
+pub const BREAKDOWNS: &[Breakdown] = &[
+    Breakdown::StartEra,
+    Breakdown::Leader,
+    Breakdown::Victory,
+    Breakdown::Speed,
+    Breakdown::Difficulty,
+    Breakdown::Map,
+    Breakdown::Content,
+];
+
+impl FromStr for Breakdown {
+    type Err = String;
+
+    fn from_str(by: &str) -> Result<Self, Self::Err> {
+        match by {
+            "start-era" => Ok(Breakdown::StartEra),
+            "leader" => Ok(Breakdown::Leader),
+            "victory" => Ok(Breakdown::Victory),
+            "speed" => Ok(Breakdown::Speed),
+            "difficulty" => Ok(Breakdown::Difficulty),
+            "map" => Ok(Breakdown::Map),
+            "content" => Ok(Breakdown::Content),
+            _ => Err(format!(
+                "Unknown breakdown {:?}, expected start-era, leader, victory, speed, difficulty, map or content",
+                by
+            )),
+        }
+    }
+}
+
+impl Breakdown {
+    pub fn title(self) -> &'static str {
+        match self {
+            Breakdown::StartEra => "Start era",
+            Breakdown::Leader => "Leader",
+            Breakdown::Victory => "Victory",
+            Breakdown::Speed => "Speed",
+            Breakdown::Difficulty => "Difficulty",
+            Breakdown::Map => "Map",
+            Breakdown::Content => "Content",
+        }
+    }
+}
+
+/// Games & wins of the local player per group, without modded games if `exclude_modded`.
+pub fn breakdown(con: &Connection, by: Breakdown, names: &Names, exclude_modded: bool) -> rusqlite::Result<Table> {
+    let column = match by {
+        Breakdown::StartEra => "g.StartEraType",
+        Breakdown::Leader => "lp.LeaderType",
+        Breakdown::Victory => "g.VictoryType",
+        Breakdown::Speed => "g.GameSpeedType",
+        Breakdown::Difficulty => "lp.DifficultyType",
+        Breakdown::Map => "g.Map",
+        Breakdown::Content => "NULL",
+    };
+    let mut stmt = con.prepare(&format!(
+        "SELECT g.GameId, {}, COALESCE(g.VictorTeamId = lp.TeamId, 0), lp.Score, g.GameSpeedType, lp.DifficultyType, \
+         g.TurnCount FROM Games g {}",
+        column, LOCAL_PLAYER_JOIN
+    ))?;
+    let games = stmt
+        .query_map(NO_PARAMS, |row| {
+            let weighted = match row.get::<_, Option<i64>>(3)? {
+                Some(score) => Some(weighted_score(
+                    score,
+                    &row.get::<_, String>(4)?,
+                    row.get::<_, Option<String>>(5)?.as_deref(),
+                )),
+                None => None,
+            };
+            Ok((
+                row.get::<_, GameId>(0)?,
+                row.get::<_, Option<String>>(1)?,
+                row.get::<_, bool>(2)?,
+                weighted,
+                row.get::<_, i64>(6)?,
+            ))
+        })?
+        .collect::<rusqlite::Result<Vec<_>>>()?;
+    let configurations = match by {
+        Breakdown::Content => content::configurations(con)?,
+        _ => BTreeMap::new(),
+    };
+
+    let modded = if exclude_modded {
+        content::modded_games(con)?
+    } else {
+        BTreeMap::new()
+    };
+
+    let mut groups: BTreeMap<String, (i64, i64, f64, i64)> = BTreeMap::new();
+    for (game_id, group, won, weighted, turns) in games {
+        if modded.contains_key(&game_id) {
+            continue;
+        }
+        let group = match by {
+            Breakdown::Content => configurations.get(&game_id).cloned().unwrap_or_default(),
+            Breakdown::Leader => names.leader(group.as_deref().unwrap_or("NULL"), None),
+            Breakdown::Victory => group.as_deref().map_or("none", |victory| names.display(victory)).to_string(),
+            Breakdown::Map => maps::canonical(group.as_deref().unwrap_or("NULL")),
+            _ => names.display(group.as_deref().unwrap_or("NULL")).to_string(),
+        };
+        let counts = groups.entry(group).or_default();
+        counts.0 += 1;
+        counts.1 += won as i64;
+        counts.2 += weighted.unwrap_or_default();
+        counts.3 += turns;
+    }
+    let mut groups: Vec<(String, (i64, i64, f64, i64))> = groups.into_iter().collect();
+    let position = |known: &[&str], group: &str| {
+        known
+            .iter()
+            .position(|key| names.display(key) == group)
+            .unwrap_or(known.len())
+    };
+    match by {
+        Breakdown::StartEra => groups.sort_by_key(|(era, _)| position(&ERAS, era)),
+        Breakdown::Difficulty => groups.sort_by_key(|(difficulty, _)| position(&DIFFICULTIES, difficulty)),
+        Breakdown::Speed => {
+            let speeds: Vec<&str> = SPEED_PERCENTS.iter().map(|(speed, _)| *speed).collect();
+            groups.sort_by_key(|(speed, _)| position(&speeds, speed))
+        }
+        Breakdown::Map => groups.sort_by_key(|(_, counts)| -counts.0),
+        _ => {}
+    }
+
+    let mut table = Table::new(&[by.title(), "Games", "Wins", "Win rate", "Avg weighted score", "Avg turns"]);
+    for (group, (games, wins, weighted, turns)) in groups {
+        table.push(vec![
+            group,
+            games.to_string(),
+            wins.to_string(),
+            format!("{:.0}%", 100.0 * wins as f64 / games as f64),
+            format!("{:.0}", weighted / games as f64),
+            format!("{:.0}", turns as f64 / games as f64),
+        ]);
+    }
+    Ok(table)
+}
+
+/// The local player's wins, best rating first, without modded games if `exclude_modded`.
+pub fn leaderboard(con: &Connection, names: &Names, exclude_modded: bool) -> rusqlite::Result<Table> {
+    let mut stmt = con.prepare(&format!(
+        "SELECT g.GameId, lp.LeaderType, lp.LeaderName, g.VictoryType, g.GameSpeedType, lp.DifficultyType, g.TurnCount, \
+         lp.Score \
+         FROM Games g {} WHERE g.VictorTeamId = lp.TeamId",
+        LOCAL_PLAYER_JOIN
+    ))?;
+    let wins = stmt
+        .query_map(NO_PARAMS, |row| {
+            Ok((
+                row.get::<_, GameId>(0)?,
+                names.leader(&row.get::<_, String>(1)?, row.get::<_, Option<String>>(2)?.as_deref()),
+                row.get::<_, Option<String>>(3)?,
+                row.get::<_, String>(4)?,
+                row.get::<_, Option<String>>(5)?,
+                row.get::<_, i64>(6)?,
+                row.get::<_, i64>(7)?,
+            ))
+        })?
+        .collect::<rusqlite::Result<Vec<_>>>()?;
+    let modded = if exclude_modded {
+        content::modded_games(con)?
+    } else {
+        BTreeMap::new()
+    };
+
+    let mut wins: Vec<_> = wins
+        .into_iter()
+        .filter(|win| !modded.contains_key(&win.0))
+        .map(|win| {
+            let rating = rating(&win.3, win.5, win.4.as_deref());
+            (win, rating)
+        })
+        .collect();
+    wins.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap_or(std::cmp::Ordering::Equal));
+
+    let mut table = Table::new(&[
+        "Game",
+        "Leader",
+        "Victory",
+        "Speed",
+        "Difficulty",
+        "Turns",
+        "Standard turns",
+        "Weighted score",
+        "Rating",
+    ]);
+    for ((game_id, leader, victory, speed, difficulty, turns, score), rating) in wins {
+        table.push(vec![
+            game_id.to_string(),
+            leader,
+            names.display(victory.as_deref().unwrap_or("-")).to_string(),
+            names.display(&speed).to_string(),
+            names.display(difficulty.as_deref().unwrap_or("-")).to_string(),
+            turns.to_string(),
+            format!("{:.0}", standard_turns(&speed, turns)),
+            format!("{:.0}", weighted_score(score, &speed, difficulty.as_deref())),
+            format!("{:.2}", rating),
+        ]);
+    }
+    Ok(table)
+}
+
+/// Year & month of a timestamp.
+fn month(timestamp: i64) -> (i64, i64) {
+    let date = dates::format_date(timestamp);
+    (date[..4].parse().unwrap_or_default(), date[5..7].parse().unwrap_or_default())
+}
+
+/// Games played & won by the local player per month of `LastPlayed`, months without games included. With
+/// `histogram` a column of bars - `█` won, `░` the other games - scaled to the busiest month.
+pub fn timeline(con: &Connection, exclude_modded: bool, histogram: bool) -> rusqlite::Result<Table> {
+    let mut stmt = con.prepare(&format!(
+        "SELECT g.GameId, g.LastPlayed, COALESCE(g.VictorTeamId = lp.TeamId, 0) FROM Games g {}",
+        LOCAL_PLAYER_JOIN
+    ))?;
+    let games = stmt
+        .query_map(NO_PARAMS, |row| Ok((row.get::<_, GameId>(0)?, row.get::<_, i64>(1)?, row.get::<_, bool>(2)?)))?
+        .collect::<rusqlite::Result<Vec<_>>>()?;
+    let modded = if exclude_modded {
+        content::modded_games(con)?
+    } else {
+        BTreeMap::new()
+    };
+    let mut months: BTreeMap<(i64, i64), (usize, usize)> = BTreeMap::new();
+    for (game_id, last_played, won) in games {
+        if modded.contains_key(&game_id) {
+            continue;
+        }
+        let counts = months.entry(month(last_played)).or_default();
+        counts.0 += 1;
+        counts.1 += won as usize;
+    }
+    if let (Some(&first), Some(&last)) = (months.keys().next(), months.keys().next_back()) {
+        let mut month = first;
+        while month < last {
+            months.entry(month).or_default();
+            month = if month.1 == 12 { (month.0 + 1, 1) } else { (month.0, month.1 + 1) };
+        }
+    }
+
+    let busiest = months.values().map(|(games, _)| *games).max().unwrap_or(0).max(1);
+    let mut header = vec!["Month", "Games", "Wins"];
+    if histogram {
+        header.push("");
+    }
+    let mut table = Table::new(&header);
+    for ((year, month), (games, wins)) in months {
+        let mut row = vec![format!("{:04}-{:02}", year, month), games.to_string(), wins.to_string()];
+        if histogram {
+            let scale = |count: usize| (count * TIMELINE_WIDTH).div_ceil(busiest);
+            row.push(format!("{}{}", "█".repeat(scale(wins)), "░".repeat(scale(games) - scale(wins))));
+        }
+        table.push(row);
+    }
+    Ok(table)
+}
@@ -0,0 +1,168 @@
+use std::str::FromStr;
+
+use serde_json::{Map, Value};
+
+/// Output formats of listing commands.
+#[derive(Clone, Copy, PartialEq)]
+pub enum Format {
+    /// Columns aligned for reading in a terminal
+    Text,
+    /// Tab-separated with a header line & no decoration, for awk/cut & spreadsheets
+    Tsv,
+    /// Comma-separated with a header line, quoted as RFC 4180
+    Csv,
+    /// An array of objects keyed by the headers
+    Json,
+}
+
+impl FromStr for Format {
+    type Err = String;
+
+    fn from_str(format: &str) -> Result<Self, Self::Err> {
+        match format {
+            "text" => Ok(Format::Text),
+            "tsv" => Ok(Format::Tsv),
+            "csv" => Ok(Format::Csv),
+            "json" => Ok(Format::Json),
+            _ => Err(format!("Unknown format {:?}, expected text, tsv, csv or json", format)),
+        }
+    }
+}
+
+/// Rows of a listing, rendered in one of the `Format`s.
+pub struct Table {
+    headers: Vec<String>,
+    rows: Vec<Vec<String>>,
+}
+
+impl Table {
+    pub fn new(headers: &[&str]) -> Table {
+        Table {
+            headers: headers.iter().map(|header| header.to_string()).collect(),
+            rows: Vec::new(),
+        }
+    }
+
+    pub fn push(&mut self, row: Vec<String>) {
+        self.rows.push(row);
+    }
+
+    pub fn render(&self, format: Format) -> String {
+        match format {
+            Format::Text => self.render_text(),
+            Format::Tsv => self.render_tsv(),
+            Format::Csv => self.render_csv(),
+            Format::Json => format!("{:#}\n", self.to_json()),
+        }
+    }
+
+    fn render_text(&self) -> String {
+        let mut widths: Vec<usize> = self.headers.iter().map(|header| header.chars().count()).collect();
+        for row in &self.rows {
+            for (width, cell) in widths.iter_mut().zip(row) {
+                *width = (*width).max(cell.chars().count());
+            }
+        }
+
+        let line = |cells: &[String]| {
+            let padded: Vec<String> = cells
+                .iter()
+                .zip(&widths)
+                .map(|(cell, width)| format!("{:width$}", cell, width = width))
+                .collect();
+            format!("{}\n", padded.join("  ").trim_end())
+        };
+        let mut text = line(&self.headers);
+        for row in &self.rows {
+            text.push_str(&line(row));
+        }
+        text
+    }
+
+    fn render_tsv(&self) -> String {
+        let line = |cells: &[String]| {
+            let cleaned: Vec<String> = cells.iter().map(|cell| cell.replace(['\t', '\n', '\r'], " ")).collect();
+            format!("{}\n", cleaned.join("\t"))
+        };
+        let mut tsv = line(&self.headers);
+        for row in &self.rows {
+            tsv.push_str(&line(row));
+        }
+        tsv
+    }
+
+    fn render_csv(&self) -> String {
+        let line = |cells: &[String]| {
+            let quoted: Vec<String> = cells
+                .iter()
+                .map(|cell| {
+                    if cell.contains([',', '"', '\n', '\r']) {
+                        format!("\"{}\"", cell.replace('"', "\"\""))
+                    } else {
+                        cell.clone()
+                    }
+                })
+                .collect();
+            format!("{}\n", quoted.join(","))
+        };
+        let mut csv = line(&self.headers);
+        for row in &self.rows {
+            csv.push_str(&line(row));
+        }
+        csv
+    }
+
+    /// The rows as objects keyed by the headers, integers as numbers - e.g. to combine several tables
+    /// into one document.
+    pub fn to_json(&self) -> Value {
+        let rows = self.rows.iter().map(|row| {
+            let cells: Map<String, Value> = self
+                .headers
+                .iter()
+                .zip(row)
+                .map(|(header, cell)| {
+                    let value = match cell.parse::<i64>() {
+                        Ok(number) => Value::from(number),
+                        Err(_) => Value::String(cell.clone()),
+                    };
+                    (header.clone(), value)
+                })
+                .collect();
+            Value::Object(cells)
+        });
+        Value::Array(rows.collect())
+    }
+
+    /// A Markdown table - for wikis & forum posts.
+    pub fn render_markdown(&self) -> String {
+        let line = |cells: &[String]| {
+            let cleaned: Vec<String> = cells.iter().map(|cell| cell.replace('|', "\\|").replace('\n', " ")).collect();
+            format!("| {} |\n", cleaned.join(" | "))
+        };
+        let mut markdown = line(&self.headers);
+        markdown.push_str(&format!("|{}\n", "---|".repeat(self.headers.len())));
+        for row in &self.rows {
+            markdown.push_str(&line(row));
+        }
+        markdown
+    }
+
+    /// An HTML `<table>` element.
+    pub fn render_html(&self) -> String {
+        let escape = |text: &str| text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;");
+        let mut html = String::from("<table>\n<tr>");
+        for header in &self.headers {
+            html.push_str(&format!("<th>{}</th>", escape(header)));
+        }
+        html.push_str("</tr>\n");
+        for row in &self.rows {
+            html.push_str("<tr>");
+            for cell in row {
+                html.push_str(&format!("<td>{}</td>", escape(cell)));
+            }
+            html.push_str("</tr>\n");
+        }
+        html.push_str("</table>\n");
+        html
+    }
+}
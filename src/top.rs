@@ -0,0 +1,77 @@
+//! `top`: The local player's fastest victories - fewest turns first - of a
+//! victory type, speed, difficulty & ruleset.
+
+use rusqlite::Connection;
+
+use crate::names::Names;
+use crate::table::Table;
+use crate::{dates, filters, stats, LOCAL_PLAYER_JOIN};
+
+/// Which wins `top` ranks, each matching all if empty.
+#[derive(Debug, Default)]
+pub struct Query {
+    pub victories: Vec<String>,
+    pub speeds: Vec<String>,
+    pub difficulties: Vec<String>,
+    pub rulesets: Vec<String>,
+}
+
+/// The `limit` wins of the local player matching the query with the fewest turns.
+pub fn fastest(con: &Connection, names: &Names, query: &Query, limit: i64) -> rusqlite::Result<Table> {
+    let mut conditions = vec!["g.VictorTeamId = lp.TeamId".to_string(), "g.VictoryType IS NOT NULL".to_string()];
+    let mut values: Vec<String> = Vec::new();
+    for (column, prefix, filter) in &[
+        ("g.VictoryType", "VICTORY_", &query.victories),
+        ("g.GameSpeedType", "GAMESPEED_", &query.speeds),
+        ("lp.DifficultyType", "DIFFICULTY_", &query.difficulties),
+        ("g.RuleSet", "RULESET_", &query.rulesets),
+    ] {
+        if !filter.is_empty() {
+            conditions.push(format!("{} IN ({})", column, vec!["?"; filter.len()].join(", ")));
+            values.extend(filter.iter().map(|value| filters::key(prefix, value)));
+        }
+    }
+    let mut stmt = con.prepare(&format!(
+        "SELECT g.GameId, g.VictoryType, lp.LeaderType, lp.LeaderName, g.TurnCount, g.GameSpeedType, \
+         lp.DifficultyType, g.RuleSet, g.Map, g.LastPlayed FROM Games g {} WHERE {} \
+         ORDER BY g.TurnCount, g.LastPlayed LIMIT {}",
+        LOCAL_PLAYER_JOIN,
+        conditions.join(" AND "),
+        limit
+    ))?;
+    let mut table = Table::new(&[
+        "#",
+        "Game",
+        "Victory",
+        "Leader",
+        "Turns",
+        "Standard turns",
+        "Speed",
+        "Difficulty",
+        "Ruleset",
+        "Map",
+        "Date",
+    ]);
+    let mut rows = stmt.query(&values)?;
+    let mut rank = 0;
+    while let Some(row) = rows.next()? {
+        rank += 1;
+        let speed: String = row.get(5)?;
+        let turns: i64 = row.get(4)?;
+        table.push(vec![
+            rank.to_string(),
+            row.get::<_, i64>(0)?.to_string(),
+            names.display(&row.get::<_, String>(1)?).to_string(),
+            names.leader(&row.get::<_, String>(2)?, row.get::<_, Option<String>>(3)?.as_deref()),
+            turns.to_string(),
+            format!("{:.0}", stats::standard_turns(&speed, turns)),
+            names.display(&speed).to_string(),
+            row.get::<_, Option<String>>(6)?
+                .map_or_else(|| "-".to_string(), |difficulty| names.display(&difficulty).to_string()),
+            names.display(&row.get::<_, String>(7)?).to_string(),
+            row.get(8)?,
+            dates::format_date(row.get(9)?),
+        ]);
+    }
+    Ok(table)
+}
@@ -0,0 +1,33 @@
+use log::info;
+use rusqlite::{Connection, NO_PARAMS};
+
+/// Views installed by `create-views` - recreated on every run so updated definitions get applied.
+const VIEWS: [(&str, &str); 3] = [
+    (
+        "v_GamePlayers",
+        "SELECT go.GameId, gp.* FROM GamePlayers gp JOIN GameObjects go ON go.ObjectId = gp.PlayerObjectId",
+    ),
+    (
+        "v_GameResults",
+        "SELECT g.GameId, g.LastPlayed, g.Ruleset, g.Map, g.MapSizeType, g.GameSpeedType, g.StartEraType, g.TurnCount, \
+         g.VictoryType, p.LeaderType, p.LeaderName, p.CivilizationType, p.CivilizationName, p.DifficultyType, p.Score, \
+         g.VictorTeamId IS NOT NULL AND g.VictorTeamId = p.TeamId AS IsWin \
+         FROM Games g LEFT JOIN v_GamePlayers p ON p.GameId = g.GameId AND p.IsLocal",
+    ),
+    (
+        "v_LeaderStats",
+        "SELECT LeaderType, MAX(LeaderName) AS LeaderName, COUNT(*) AS Games, SUM(IsWin) AS Wins, \
+         AVG(Score) AS AvgScore, MAX(Score) AS BestScore, MIN(CASE WHEN IsWin THEN TurnCount END) AS FastestWin \
+         FROM v_GameResults WHERE LeaderType IS NOT NULL GROUP BY LeaderType",
+    ),
+];
+
+/// Installs read-only views with the common join paths for ad-hoc SQL analysis.
+pub fn create_views(con: &Connection) -> rusqlite::Result<()> {
+    for (name, select) in VIEWS.iter() {
+        con.execute(&format!("DROP VIEW IF EXISTS {}", name), NO_PARAMS)?;
+        con.execute(&format!("CREATE VIEW {} AS {}", name, select), NO_PARAMS)?;
+        info!("Created view {}", name);
+    }
+    Ok(())
+}